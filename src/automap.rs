@@ -0,0 +1,231 @@
+//! The automap: the level seen from above, drawn over the 3D view.
+//!
+//! Only walls the player has seen are shown, solid ones in red and portals
+//! in yellow, with an arrow for the player. North is up. The map follows the
+//! player unless follow mode is turned off, in which case the movement
+//! actions pan it and the player stands still.
+
+use crate::colormap::LEVELS;
+use crate::input::{Action, Actions};
+use crate::math::Vec2;
+use crate::world::{World, PLAYER_RADIUS};
+use crate::{HEIGHT, WIDTH};
+
+/// Pixels per map unit when the automap is first shown.
+const DEFAULT_SCALE: f64 = 0.5;
+
+/// Closest and furthest zoom, in pixels per map unit.
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 4.0;
+
+/// Zoom factor per tick while zooming.
+const ZOOM_SPEED: f64 = 1.04;
+
+/// Screen pixels panned per tick.
+const PAN_SPEED: f64 = 4.0;
+
+/// Map units between grid lines.
+const GRID_SIZE: i32 = 128;
+
+/// Grid lines closer than this many pixels are not drawn.
+const MIN_GRID_SPACING: f64 = 4.0;
+
+/// Colormap the 3D view is darkened with under the map.
+const DIM: usize = LEVELS - 8;
+
+const SOLID_COLOR: [u8; 3] = [255, 0, 0];
+const PORTAL_COLOR: [u8; 3] = [255, 255, 0];
+const GRID_COLOR: [u8; 3] = [0, 0, 130];
+const PLAYER_COLOR: [u8; 3] = [255, 255, 255];
+
+/// The player's arrow, pointing forward along `x`, in units of `PLAYER_RADIUS`.
+const ARROW: [((f64, f64), (f64, f64)); 5] = [
+    ((-1.0, 0.0), (1.0, 0.0)),
+    ((1.0, 0.0), (0.5, -0.5)),
+    ((1.0, 0.0), (0.5, 0.5)),
+    ((-1.0, 0.0), (-1.25, -0.5)),
+    ((-1.0, 0.0), (-1.25, 0.5)),
+];
+
+pub struct Automap {
+    /// Whether the automap is shown.
+    pub active: bool,
+    /// Keep the player in the middle of the screen.
+    pub follow: bool,
+    pub grid: bool,
+    /// Pixels per map unit.
+    pub scale: f64,
+    /// The map point in the middle of the screen while not following.
+    pub center: Vec2,
+    /// Per wall, whether the player has seen it.
+    pub seen: Vec<bool>,
+}
+
+impl Automap {
+    /// An automap of `walls` walls, none of them seen yet.
+    pub fn new(walls: usize) -> Self {
+        Automap {
+            active: false,
+            follow: true,
+            grid: false,
+            scale: DEFAULT_SCALE,
+            center: Vec2::ZERO,
+            seen: vec![false; walls],
+        }
+    }
+
+    /// Whether the movement actions pan the map rather than move the player.
+    pub fn panning(&self) -> bool {
+        self.active && !self.follow
+    }
+
+    /// Advance by one tick: toggle the map and its modes, zoom and pan.
+    /// `player` is where the player stands.
+    pub fn update(&mut self, actions: &Actions, player: Vec2) {
+        if actions.pressed(Action::Automap) {
+            self.active = !self.active;
+        }
+        if !self.active {
+            return;
+        }
+        if actions.pressed(Action::Follow) {
+            self.follow = !self.follow;
+            // Panning starts from where the map was following.
+            self.center = player;
+        }
+        if actions.pressed(Action::Grid) {
+            self.grid = !self.grid;
+        }
+        if actions.held(Action::ZoomIn) {
+            self.scale = (self.scale * ZOOM_SPEED).min(MAX_SCALE);
+        }
+        if actions.held(Action::ZoomOut) {
+            self.scale = (self.scale / ZOOM_SPEED).max(MIN_SCALE);
+        }
+        if self.panning() {
+            let pan = PAN_SPEED / self.scale;
+            for (action, direction) in [
+                (Action::Forward, Vec2::new(0.0, 1.0)),
+                (Action::Backward, Vec2::new(0.0, -1.0)),
+                (Action::TurnLeft, Vec2::new(-1.0, 0.0)),
+                (Action::StrafeLeft, Vec2::new(-1.0, 0.0)),
+                (Action::TurnRight, Vec2::new(1.0, 0.0)),
+                (Action::StrafeRight, Vec2::new(1.0, 0.0)),
+            ] {
+                if actions.held(action) {
+                    self.center += direction * pan;
+                }
+            }
+        }
+    }
+
+    /// The screen position of map point `point`, with `center` in the middle.
+    fn to_screen(&self, center: Vec2, point: Vec2) -> (f64, f64) {
+        (
+            WIDTH as f64 / 2.0 + (point.x - center.x) * self.scale,
+            HEIGHT as f64 / 2.0 - (point.y - center.y) * self.scale,
+        )
+    }
+}
+
+impl World {
+    /// Draw the automap over the palette indices of a rendered view,
+    /// `alpha` of the way from the previous tick to the last one.
+    pub fn draw_automap(&self, frame: &mut [u8], alpha: f64) {
+        let map = &self.automap;
+        let palette = &self.palettes[0];
+        for color in frame.iter_mut() {
+            *color = self.colormap.shade(*color, DIM);
+        }
+
+        let camera = self.camera(alpha);
+        let center = if map.follow {
+            camera.position.xy()
+        } else {
+            map.center
+        };
+        let map_line = |frame: &mut [u8], a: Vec2, b: Vec2, color: u8| {
+            line(
+                frame,
+                map.to_screen(center, a),
+                map.to_screen(center, b),
+                color,
+            )
+        };
+
+        if map.grid && GRID_SIZE as f64 * map.scale >= MIN_GRID_SPACING {
+            let color = palette.nearest(GRID_COLOR);
+            let half = Vec2::new(WIDTH as f64, HEIGHT as f64) * (0.5 / map.scale);
+            let (low, high) = (center - half, center + half);
+            let lines = |from: f64, to: f64| {
+                (from as i32).div_euclid(GRID_SIZE)..=(to as i32).div_euclid(GRID_SIZE) + 1
+            };
+            for n in lines(low.x, high.x) {
+                let x = (n * GRID_SIZE) as f64;
+                let (a, b) = (Vec2::new(x, low.y), Vec2::new(x, high.y));
+                map_line(frame, a, b, color);
+            }
+            for n in lines(low.y, high.y) {
+                let y = (n * GRID_SIZE) as f64;
+                let (a, b) = (Vec2::new(low.x, y), Vec2::new(high.x, y));
+                map_line(frame, a, b, color);
+            }
+        }
+
+        let (solid, portal) = (palette.nearest(SOLID_COLOR), palette.nearest(PORTAL_COLOR));
+        for (wall, _) in self.walls.iter().zip(&map.seen).filter(|(_, &seen)| seen) {
+            let a = Vec2::new(wall.x1 as f64, wall.y1 as f64);
+            let b = Vec2::new(wall.x2 as f64, wall.y2 as f64);
+            let color = if wall.neighbour.is_some() {
+                portal
+            } else {
+                solid
+            };
+            map_line(frame, a, b, color);
+        }
+
+        let color = palette.nearest(PLAYER_COLOR);
+        let forward = Vec2::from_angle(camera.angle) * PLAYER_RADIUS;
+        let right = forward.perp();
+        let at = |(f, r): (f64, f64)| camera.position.xy() + forward * f + right * r;
+        for (a, b) in ARROW {
+            map_line(frame, at(a), at(b), color);
+        }
+    }
+}
+
+/// Draw the line between screen positions `a` and `b`, clipped to the
+/// screen, in palette index `color`.
+pub(crate) fn line(frame: &mut [u8], a: (f64, f64), b: (f64, f64), color: u8) {
+    // Clip the line's parameter range against each screen edge.
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0, 1.0);
+    for (p, q) in [
+        (-dx, a.0),
+        (dx, WIDTH as f64 - 1.0 - a.0),
+        (-dy, a.1),
+        (dy, HEIGHT as f64 - 1.0 - a.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+        } else if p < 0.0 {
+            t0 = f64::max(t0, q / p);
+        } else {
+            t1 = f64::min(t1, q / p);
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let (x0, y0) = (a.0 + dx * t0, a.1 + dy * t0);
+    let steps = (dx.abs().max(dy.abs()) * (t1 - t0)).ceil().max(1.0);
+    for i in 0..=steps as usize {
+        let t = i as f64 / steps;
+        let x = (x0 + dx * (t1 - t0) * t).round() as usize;
+        let y = (y0 + dy * (t1 - t0) * t).round() as usize;
+        frame[y.min(HEIGHT as usize - 1) * WIDTH as usize + x.min(WIDTH as usize - 1)] = color;
+    }
+}
@@ -0,0 +1,304 @@
+//! Binary space partition of a level's walls.
+//!
+//! Like Doom's node builder, `Bsp::build` cuts the map in two along the line
+//! of one of its walls, splitting the walls that cross that line, and goes on
+//! cutting both halves until every piece is convex: a `Subsector` of a single
+//! sector whose segs cannot hide one another. Walking the tree from the side
+//! of each partition the eye is on then visits the subsectors strictly front
+//! to back, whatever the shape of the sectors.
+
+use std::ops::Range;
+
+use crate::math::Vec2;
+use crate::world::{Sector, Wall};
+
+/// Distance from a partition line within which a point counts as on it.
+const EPSILON: f64 = 1e-6;
+
+/// Most partition lines tried per node. Beyond that, walls are sampled evenly.
+const MAX_CANDIDATES: usize = 64;
+
+/// How many segs more on one side than on the other a split is worth when
+/// choosing a partition.
+const SPLIT_COST: usize = 8;
+
+/// A piece of a wall, lying within one subsector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seg {
+    /// Index into `World::walls`.
+    pub wall: usize,
+    /// The sector the wall belongs to.
+    pub sector: usize,
+    pub start: Vec2,
+    pub end: Vec2,
+    /// Where the seg starts and ends along its wall, from 0 at the wall's
+    /// start to 1 at its end.
+    pub s1: f64,
+    pub s2: f64,
+}
+
+/// A convex piece of a sector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subsector {
+    pub sector: usize,
+    /// Indices into `Bsp::segs`.
+    pub segs: Range<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Child {
+    Node(usize),
+    Subsector(usize),
+}
+
+/// A partition line through `origin` heading `direction`. As for walls, its
+/// front lies on its right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub origin: Vec2,
+    pub direction: Vec2,
+    pub front: Child,
+    pub back: Child,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bsp {
+    pub nodes: Vec<Node>,
+    pub subsectors: Vec<Subsector>,
+    pub segs: Vec<Seg>,
+    /// `None` for a level without walls.
+    pub root: Option<Child>,
+}
+
+/// Where something lies relative to a partition line.
+enum Side {
+    Front,
+    Back,
+    /// Across the line, cut at this fraction of the way from start to end.
+    Split(f64),
+}
+
+impl Node {
+    /// Signed distance of `point` from the line, positive in front.
+    fn distance(&self, point: Vec2) -> f64 {
+        (point - self.origin).dot(self.direction.perp())
+    }
+
+    /// Whether `point` lies in front of the line or on it.
+    pub fn in_front(&self, point: Vec2) -> bool {
+        self.distance(point) >= 0.0
+    }
+
+    fn side(&self, seg: &Seg) -> Side {
+        let (a, b) = (self.distance(seg.start), self.distance(seg.end));
+        if a.abs() < EPSILON && b.abs() < EPSILON {
+            // Along the line: the side the seg faces.
+            if (seg.end - seg.start).dot(self.direction) > 0.0 {
+                Side::Front
+            } else {
+                Side::Back
+            }
+        } else if a > -EPSILON && b > -EPSILON {
+            Side::Front
+        } else if a < EPSILON && b < EPSILON {
+            Side::Back
+        } else {
+            Side::Split(a / (a - b))
+        }
+    }
+}
+
+impl Seg {
+    /// The line along the seg, with nothing on either side yet.
+    fn partition(&self) -> Node {
+        let leaf = Child::Subsector(0);
+        Node {
+            origin: self.start,
+            // Unit length, so that distances from it are in map units.
+            direction: (self.end - self.start).normalize().unwrap_or(Vec2::ZERO),
+            front: leaf,
+            back: leaf,
+        }
+    }
+
+    /// The seg cut in two at `t` of the way from its start to its end.
+    fn split(&self, t: f64) -> (Seg, Seg) {
+        let point = self.start + (self.end - self.start) * t;
+        let s = self.s1 + (self.s2 - self.s1) * t;
+        (
+            Seg {
+                end: point,
+                s2: s,
+                ..*self
+            },
+            Seg {
+                start: point,
+                s1: s,
+                ..*self
+            },
+        )
+    }
+}
+
+impl Bsp {
+    /// Partition the walls of `sectors` into convex subsectors.
+    pub fn build(sectors: &[Sector], walls: &[Wall]) -> Bsp {
+        let mut segs = Vec::new();
+        for (s, sector) in sectors.iter().enumerate() {
+            for (w, wall) in walls
+                .iter()
+                .enumerate()
+                .take(sector.wall_end)
+                .skip(sector.wall_start)
+            {
+                let start = Vec2::new(wall.x1 as f64, wall.y1 as f64);
+                let end = Vec2::new(wall.x2 as f64, wall.y2 as f64);
+                if start != end {
+                    segs.push(Seg {
+                        wall: w,
+                        sector: s,
+                        start,
+                        end,
+                        s1: 0.0,
+                        s2: 1.0,
+                    });
+                }
+            }
+        }
+        let mut bsp = Bsp::default();
+        if !segs.is_empty() {
+            bsp.root = Some(bsp.divide(segs));
+        }
+        bsp
+    }
+
+    /// Build the subtree for `segs`, returning its root.
+    fn divide(&mut self, segs: Vec<Seg>) -> Child {
+        let mut node = match choose_partition(&segs) {
+            Some(node) => node,
+            None => {
+                let start = self.segs.len();
+                self.subsectors.push(Subsector {
+                    sector: segs[0].sector,
+                    segs: start..start + segs.len(),
+                });
+                self.segs.extend(segs);
+                return Child::Subsector(self.subsectors.len() - 1);
+            }
+        };
+
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        for seg in segs {
+            match node.side(&seg) {
+                Side::Front => front.push(seg),
+                Side::Back => back.push(seg),
+                Side::Split(t) => {
+                    let (first, second) = seg.split(t);
+                    // The seg runs from one side to the other.
+                    if node.distance(seg.start) > 0.0 {
+                        front.push(first);
+                        back.push(second);
+                    } else {
+                        back.push(first);
+                        front.push(second);
+                    }
+                }
+            }
+        }
+        node.front = self.divide(front);
+        node.back = self.divide(back);
+        self.nodes.push(node);
+        Child::Node(self.nodes.len() - 1)
+    }
+
+    /// The subsectors in order from the one containing `eye` outwards: each
+    /// one visited can only be hidden by those visited before it.
+    pub fn front_to_back(&self, eye: Vec2) -> FrontToBack<'_> {
+        FrontToBack {
+            bsp: self,
+            eye,
+            stack: self.root.into_iter().collect(),
+        }
+    }
+
+    /// The subsector containing `point`; outside of the level, the one
+    /// nearest to it by the partitions.
+    pub fn subsector_at(&self, point: Vec2) -> Option<usize> {
+        let mut child = self.root?;
+        loop {
+            match child {
+                Child::Subsector(s) => return Some(s),
+                Child::Node(n) => {
+                    let node = &self.nodes[n];
+                    child = if node.in_front(point) {
+                        node.front
+                    } else {
+                        node.back
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// The partition splitting `segs` best: few segs cut in two and about as
+/// many on both sides. `None` if the segs are convex already.
+fn choose_partition(segs: &[Seg]) -> Option<Node> {
+    let step = segs.len().div_ceil(MAX_CANDIDATES).max(1);
+    let mut best: Option<(usize, Node)> = None;
+    let mut fallback = None;
+    for (i, candidate) in segs.iter().enumerate() {
+        let node = candidate.partition();
+        let (mut front, mut back, mut splits) = (0, 0, 0);
+        for seg in segs {
+            match node.side(seg) {
+                Side::Front => front += 1,
+                Side::Back => back += 1,
+                Side::Split(_) => splits += 1,
+            }
+        }
+        if back == 0 && splits == 0 {
+            // Everything lies in front of this seg.
+            continue;
+        }
+        if i % step != 0 {
+            fallback.get_or_insert(node);
+            continue;
+        }
+        let cost = splits * SPLIT_COST + (front + splits).abs_diff(back + splits);
+        if best.as_ref().is_none_or(|(best, _)| cost < *best) {
+            best = Some((cost, node));
+        }
+    }
+    best.map(|(_, node)| node).or(fallback)
+}
+
+/// Iterator over subsectors front to back, from `Bsp::front_to_back`.
+pub struct FrontToBack<'a> {
+    bsp: &'a Bsp,
+    eye: Vec2,
+    /// Subtrees still to visit, the next one on top.
+    stack: Vec<Child>,
+}
+
+impl Iterator for FrontToBack<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while let Some(child) = self.stack.pop() {
+            match child {
+                Child::Subsector(s) => return Some(s),
+                Child::Node(n) => {
+                    let node = &self.bsp.nodes[n];
+                    // The side the eye is on goes first.
+                    if node.in_front(self.eye) {
+                        self.stack.extend([node.back, node.front]);
+                    } else {
+                        self.stack.extend([node.front, node.back]);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
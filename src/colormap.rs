@@ -0,0 +1,57 @@
+//! Light and shading.
+//!
+//! As in Doom, light is quantised into `LEVELS` colormaps, from full
+//! brightness (0) to nearly black (`LEVELS - 1`). A colormap gives for every
+//! palette index the index of the same colour, darkened, so shading a pixel
+//! is a single lookup. Which colormap a pixel uses depends on its sector's
+//! light level and on its distance from the eye.
+
+use crate::palette::{Palette, COLORS};
+
+/// Number of colormaps.
+pub const LEVELS: usize = 32;
+
+/// Light level of sectors that don't give one, and the brightest there is.
+pub const FULL_BRIGHT: u8 = 255;
+
+/// Distance over which light diminishes by one colormap.
+const FADE_DISTANCE: f64 = 32.0;
+
+/// Distance up to which surfaces get the sector's light undiminished.
+const FADE_START: f64 = 64.0;
+
+pub struct Colormap {
+    /// `LEVELS` maps from palette index to shaded palette index.
+    maps: Vec<[u8; COLORS]>,
+}
+
+impl Colormap {
+    /// Colormaps fading linearly to black in `palette`.
+    pub fn new(palette: &Palette) -> Self {
+        let maps = (0..LEVELS)
+            .map(|level| {
+                let mut map = [0; COLORS];
+                for (shaded, rgb) in map.iter_mut().zip(palette.colors) {
+                    let dark = rgb.map(|c| (c as usize * (LEVELS - level) / LEVELS) as u8);
+                    *shaded = palette.nearest(dark);
+                }
+                map
+            })
+            .collect();
+        Colormap { maps }
+    }
+
+    /// Palette index `color` in colormap `level`.
+    pub fn shade(&self, color: u8, level: usize) -> u8 {
+        self.maps[level.min(LEVELS - 1)][color as usize]
+    }
+}
+
+/// The colormap for a surface at `depth` in a sector of `light` level.
+/// Every 8 steps of light below `FULL_BRIGHT` darken by one colormap, and
+/// so does every `FADE_DISTANCE` beyond `FADE_START`.
+pub fn level(light: u8, depth: f64) -> usize {
+    let base = (FULL_BRIGHT - light) as usize / 8;
+    let fade = ((depth - FADE_START) / FADE_DISTANCE).max(0.0) as usize;
+    (base + fade).min(LEVELS - 1)
+}
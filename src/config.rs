@@ -0,0 +1,188 @@
+//! Player settings file.
+//!
+//! Like a level, a config file is a list of directives, one per line, with
+//! `#` starting a comment. The first names the format version:
+//!
+//! ```text
+//! doomrust-config 1
+//! ```
+//!
+//! followed by, in any order:
+//!
+//! ```text
+//! bind <input> <action>
+//! sensitivity <degrees per count>
+//! invert-y <true|false>
+//! ```
+//!
+//! An input is a key by its winit `VirtualKeyCode` name (`W`, `Space`,
+//! `LControl`, ...), `Mouse1` to `Mouse3` for the left, right and middle
+//! buttons, or `WheelUp` and `WheelDown`. Actions are listed by
+//! `input::Action::name`. A file with any `bind` replaces all of the default
+//! bindings; one without keeps them.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::input::{Action, Bindings, Input};
+use crate::MouseSettings;
+
+/// The format version this parser reads.
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub bindings: Bindings,
+    pub mouse: MouseSettings,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// A problem with the contents, at a 1-based line.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Parse { line, message } => write!(f, "{}: {}", line, message),
+        }
+    }
+}
+
+impl Config {
+    /// Read and parse the config at `path`.
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let source = fs::read_to_string(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+        Config::parse(&source)
+    }
+
+    /// The config at `path`, or the defaults if there is no file yet, which
+    /// are then written there for the player to edit.
+    pub fn load_or_create(path: &Path) -> Result<Config, Error> {
+        match fs::metadata(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let config = Config::default();
+                config.save(path)?;
+                Ok(config)
+            }
+            _ => Config::load(path),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_string()).map_err(|err| Error::Io(path.to_owned(), err))
+    }
+
+    pub fn parse(source: &str) -> Result<Config, Error> {
+        let mut config = Config::default();
+        let mut bindings = Bindings::new();
+        let mut version = None;
+        for (n, text) in source.lines().enumerate() {
+            let error = |message: String| Error::Parse {
+                line: n + 1,
+                message,
+            };
+            let text = text.split('#').next().unwrap_or("");
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let (directive, args) = match words.split_first() {
+                Some((directive, args)) => (*directive, args),
+                None => continue,
+            };
+            let arg_count = match directive {
+                "doomrust-config" | "sensitivity" | "invert-y" => 1,
+                "bind" => 2,
+                _ => return Err(error(format!("unknown directive `{}`", directive))),
+            };
+            if version.is_none() && directive != "doomrust-config" {
+                return Err(error(
+                    "expected `doomrust-config <version>` header".to_owned(),
+                ));
+            }
+            if args.len() != arg_count {
+                return Err(error(format!(
+                    "`{}` takes {} argument{}",
+                    directive,
+                    arg_count,
+                    if arg_count == 1 { "" } else { "s" }
+                )));
+            }
+
+            match directive {
+                "doomrust-config" => {
+                    if version.is_some() {
+                        return Err(error("header given twice".to_owned()));
+                    }
+                    if args[0] != VERSION.to_string() {
+                        return Err(error(format!(
+                            "unsupported format version `{}`, expected {}",
+                            args[0], VERSION
+                        )));
+                    }
+                    version = Some(VERSION);
+                }
+                "bind" => {
+                    let input = Input::from_name(args[0])
+                        .ok_or_else(|| error(format!("bad input `{}`", args[0])))?;
+                    let action = Action::from_name(args[1])
+                        .ok_or_else(|| error(format!("unknown action `{}`", args[1])))?;
+                    bindings.bind(input, action);
+                }
+                "sensitivity" => {
+                    config.mouse.sensitivity = args[0]
+                        .parse()
+                        .ok()
+                        .filter(|&s: &f64| s > 0.0)
+                        .ok_or_else(|| {
+                            error(format!("expected a positive number, found `{}`", args[0]))
+                        })?;
+                }
+                "invert-y" => {
+                    config.mouse.invert_y = args[0].parse().map_err(|_| {
+                        error(format!("expected `true` or `false`, found `{}`", args[0]))
+                    })?;
+                }
+                _ => unreachable!(),
+            }
+        }
+        if version.is_none() {
+            return Err(Error::Parse {
+                line: source.lines().count() + 1,
+                message: "expected `doomrust-config <version>` header".to_owned(),
+            });
+        }
+        if !bindings.bindings.is_empty() {
+            config.bindings = bindings;
+        }
+        Ok(config)
+    }
+}
+
+/// The config in file format, bindings grouped by action.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "doomrust-config {}", VERSION)?;
+        writeln!(f)?;
+        writeln!(f, "# Degrees turned per count of mouse motion.")?;
+        writeln!(f, "sensitivity {}", self.mouse.sensitivity)?;
+        writeln!(f, "invert-y {}", self.mouse.invert_y)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "# bind <key name | Mouse1-3 | WheelUp | WheelDown> <action>"
+        )?;
+        for action in Action::ALL {
+            for input in self.bindings.inputs(action) {
+                writeln!(f, "bind {} {}", input, action)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,637 @@
+//! The built-in level editor.
+//!
+//! The editor shows the level from above and edits it as a `Map`: vertices,
+//! walls between two of them and sectors the walls are grouped into, so
+//! moving a vertex moves the end of every wall meeting there. Points snap to
+//! a grid. Every edit can be undone and redone. A map becomes a `Level` again
+//! to be saved or previewed; a wall whose twin, the same wall the other way
+//! round, lies in another sector becomes a portal into it.
+
+use crate::automap::line;
+use crate::colormap::FULL_BRIGHT;
+use crate::level::Level;
+use crate::math::Vec2;
+use crate::mover::Special;
+use crate::palette::Palette;
+use crate::trigger::Trigger;
+use crate::world::{sector_at, Sector, TextureMapping, Wall};
+use crate::{HEIGHT, WIDTH};
+
+/// Grid sizes to snap to, in map units.
+pub const GRID_SIZES: [i32; 5] = [4, 8, 16, 32, 64];
+
+/// Pixels per map unit when the editor opens.
+const DEFAULT_SCALE: f64 = 0.75;
+
+/// Closest and furthest zoom, in pixels per map unit.
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 8.0;
+
+/// How near the cursor, in pixels, something must be to be picked.
+const PICK_DISTANCE: f64 = 6.0;
+
+/// Units a sector's floor or ceiling moves per step.
+pub const HEIGHT_STEP: i32 = 8;
+
+/// Colour of new walls.
+const WALL_COLOR: u8 = 10;
+
+const BACKGROUND_COLOR: [u8; 3] = [0, 0, 0];
+const GRID_COLOR: [u8; 3] = [0, 0, 130];
+const LOOSE_COLOR: [u8; 3] = [140, 140, 140];
+const SOLID_COLOR: [u8; 3] = [255, 0, 0];
+const PORTAL_COLOR: [u8; 3] = [255, 255, 0];
+const SELECTED_COLOR: [u8; 3] = [0, 255, 0];
+const VERTEX_COLOR: [u8; 3] = [255, 255, 255];
+const PLAYER_COLOR: [u8; 3] = [0, 255, 255];
+
+/// A wall between two vertices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapWall {
+    /// Indices into `Map::vertices`.
+    pub v1: usize,
+    pub v2: usize,
+    pub color: u8,
+    pub texture: Option<TextureMapping>,
+    /// The sector the wall belongs to, if it is grouped into one yet.
+    pub sector: Option<usize>,
+    /// A portal kept from the level for a wall without a twin.
+    pub portal: Option<usize>,
+    pub trigger: Option<Trigger>,
+}
+
+/// A sector's heights, colours, light, special and tag; its walls say they
+/// belong to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapSector {
+    pub z1: i32,
+    pub z2: i32,
+    pub floor_color: u8,
+    pub ceiling_color: u8,
+    pub light: u8,
+    pub special: Option<Special>,
+    pub tag: Option<u32>,
+}
+
+impl Default for MapSector {
+    fn default() -> Self {
+        MapSector {
+            z1: 0,
+            z2: 72,
+            floor_color: 9,
+            ceiling_color: 8,
+            light: FULL_BRIGHT,
+            special: None,
+            tag: None,
+        }
+    }
+}
+
+/// The geometry of a level, as the editor changes it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Map {
+    pub vertices: Vec<(i32, i32)>,
+    pub walls: Vec<MapWall>,
+    pub sectors: Vec<MapSector>,
+    /// Where the player starts.
+    pub player: (i32, i32),
+}
+
+/// What the editor works on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    Vertex(usize),
+    Wall(usize),
+    Sector(usize),
+}
+
+impl Map {
+    /// The map of `level`'s walls, with end points at the same position
+    /// merged into one vertex.
+    pub fn from_level(level: &Level) -> Map {
+        let mut map = Map {
+            player: (
+                level.player.position.x.round() as i32,
+                level.player.position.y.round() as i32,
+            ),
+            ..Map::default()
+        };
+        for (s, sector) in level.sectors.iter().enumerate() {
+            map.sectors.push(MapSector {
+                z1: sector.z1,
+                z2: sector.z2,
+                floor_color: sector.floor_color,
+                ceiling_color: sector.ceiling_color,
+                light: sector.light,
+                special: sector.special,
+                tag: sector.tag,
+            });
+            for wall in &level.walls[sector.wall_start..sector.wall_end] {
+                let v1 = map.vertex((wall.x1, wall.y1));
+                let v2 = map.vertex((wall.x2, wall.y2));
+                map.walls.push(MapWall {
+                    v1,
+                    v2,
+                    color: wall.color,
+                    texture: wall.texture,
+                    sector: Some(s),
+                    portal: wall.neighbour,
+                    trigger: wall.trigger,
+                });
+            }
+        }
+        map
+    }
+
+    /// The index of the vertex at `point`, added if there is none.
+    fn vertex(&mut self, point: (i32, i32)) -> usize {
+        match self.vertices.iter().position(|&v| v == point) {
+            Some(v) => v,
+            None => {
+                self.vertices.push(point);
+                self.vertices.len() - 1
+            }
+        }
+    }
+
+    /// The sector of the wall from `v2` to `v1`, if there is one.
+    fn twin_sector(&self, wall: &MapWall) -> Option<usize> {
+        self.walls
+            .iter()
+            .filter(|w| w.v1 == wall.v2 && w.v2 == wall.v1)
+            .find_map(|w| w.sector.filter(|&s| Some(s) != wall.sector))
+    }
+
+    /// The sectors and walls of the map, walls in no sector left out.
+    fn geometry(&self) -> (Vec<Sector>, Vec<Wall>) {
+        let mut sectors = Vec::new();
+        let mut walls = Vec::new();
+        for (s, sector) in self.sectors.iter().enumerate() {
+            let wall_start = walls.len();
+            for wall in self.walls.iter().filter(|w| w.sector == Some(s)) {
+                let ((x1, y1), (x2, y2)) = (self.vertices[wall.v1], self.vertices[wall.v2]);
+                walls.push(Wall {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    color: wall.color,
+                    neighbour: self.twin_sector(wall).or(wall.portal),
+                    texture: wall.texture,
+                    trigger: wall.trigger,
+                });
+            }
+            sectors.push(Sector {
+                wall_start,
+                wall_end: walls.len(),
+                z1: sector.z1,
+                z2: sector.z2,
+                floor_color: sector.floor_color,
+                ceiling_color: sector.ceiling_color,
+                light: sector.light,
+                special: sector.special,
+                tag: sector.tag,
+            });
+        }
+        (sectors, walls)
+    }
+
+    /// The map as a level, with the player, things, textures and palettes
+    /// of `rest`. Fails if a wall is in no sector or a sector has no walls.
+    pub fn to_level(&self, rest: &Level) -> Result<Level, String> {
+        if let Some(w) = self.walls.iter().position(|w| w.sector.is_none()) {
+            return Err(format!("wall {} is in no sector", w));
+        }
+        let (sectors, walls) = self.geometry();
+        if sectors.is_empty() {
+            return Err("level has no sectors".to_owned());
+        }
+        if let Some(s) = sectors.iter().position(|s| s.wall_start == s.wall_end) {
+            return Err(format!("sector {} has no walls", s));
+        }
+        let mut player = rest.player.clone();
+        player.position.x = self.player.0 as f64;
+        player.position.y = self.player.1 as f64;
+        Ok(Level {
+            player,
+            sectors,
+            walls,
+            ..rest.clone()
+        })
+    }
+
+    /// Remove vertex `v` and the walls ending at it.
+    fn remove_vertex(&mut self, v: usize) {
+        self.walls.retain(|w| w.v1 != v && w.v2 != v);
+        self.vertices.remove(v);
+        for wall in &mut self.walls {
+            wall.v1 -= (wall.v1 > v) as usize;
+            wall.v2 -= (wall.v2 > v) as usize;
+        }
+    }
+
+    /// Remove sector `s`, leaving its walls in no sector.
+    fn remove_sector(&mut self, s: usize) {
+        self.sectors.remove(s);
+        let renumber = |sector: Option<usize>| match sector {
+            Some(n) if n == s => None,
+            Some(n) if n > s => Some(n - 1),
+            other => other,
+        };
+        for wall in &mut self.walls {
+            wall.sector = renumber(wall.sector);
+            wall.portal = renumber(wall.portal);
+        }
+    }
+
+    /// The walls of the closed loop through wall `start`, following each
+    /// wall's end to the start of the next wall in no sector yet.
+    fn find_loop(&self, start: usize) -> Option<Vec<usize>> {
+        let mut loop_ = vec![start];
+        let mut at = self.walls[start].v2;
+        while at != self.walls[start].v1 {
+            let next = (0..self.walls.len()).find(|&w| {
+                let wall = &self.walls[w];
+                wall.v1 == at && wall.sector.is_none() && !loop_.contains(&w)
+            })?;
+            loop_.push(next);
+            at = self.walls[next].v2;
+        }
+        Some(loop_)
+    }
+}
+
+pub struct Editor {
+    pub map: Map,
+    /// The parts of the level the editor doesn't change: textures, things,
+    /// palettes and the player's height and angle.
+    rest: Level,
+    undo: Vec<Map>,
+    redo: Vec<Map>,
+    pub selection: Option<Selection>,
+    /// Index into `GRID_SIZES`.
+    pub grid: usize,
+    /// The map point in the middle of the screen.
+    pub center: Vec2,
+    /// Pixels per map unit.
+    pub scale: f64,
+    /// The vertex being dragged, if any.
+    dragging: Option<usize>,
+}
+
+impl Editor {
+    pub fn new(level: &Level) -> Self {
+        let map = Map::from_level(level);
+        Editor {
+            center: Vec2::new(map.player.0 as f64, map.player.1 as f64),
+            map,
+            rest: Level {
+                sectors: Vec::new(),
+                walls: Vec::new(),
+                ..level.clone()
+            },
+            undo: Vec::new(),
+            redo: Vec::new(),
+            selection: None,
+            grid: 2,
+            scale: DEFAULT_SCALE,
+            dragging: None,
+        }
+    }
+
+    /// The edited level, or what keeps it from being one.
+    pub fn level(&self) -> Result<Level, String> {
+        self.map.to_level(&self.rest)
+    }
+
+    /// Remember the map as it is, to undo the edit about to be made.
+    fn checkpoint(&mut self) {
+        self.undo.push(self.map.clone());
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(map) => {
+                self.redo.push(std::mem::replace(&mut self.map, map));
+                self.selection = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(map) => {
+                self.undo.push(std::mem::replace(&mut self.map, map));
+                self.selection = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn grid_size(&self) -> i32 {
+        GRID_SIZES[self.grid]
+    }
+
+    /// Use the next grid size, starting over after the largest.
+    pub fn cycle_grid(&mut self) {
+        self.grid = (self.grid + 1) % GRID_SIZES.len();
+    }
+
+    /// `point` moved to the nearest grid point.
+    pub fn snap(&self, point: Vec2) -> (i32, i32) {
+        let grid = self.grid_size() as f64;
+        let snap = |v: f64| ((v / grid).round() * grid) as i32;
+        (snap(point.x), snap(point.y))
+    }
+
+    /// Move the view by `(dx, dy)` pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center += Vec2::new(dx, -dy) * (1.0 / self.scale);
+    }
+
+    /// Zoom the view in by `factor`, or out for factors below 1.
+    pub fn zoom(&mut self, factor: f64) {
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+    }
+
+    /// The map point under screen position `(x, y)`.
+    pub fn to_map(&self, x: f64, y: f64) -> Vec2 {
+        Vec2::new(
+            self.center.x + (x - WIDTH as f64 / 2.0) / self.scale,
+            self.center.y - (y - HEIGHT as f64 / 2.0) / self.scale,
+        )
+    }
+
+    fn to_screen(&self, (x, y): (i32, i32)) -> (f64, f64) {
+        (
+            WIDTH as f64 / 2.0 + (x as f64 - self.center.x) * self.scale,
+            HEIGHT as f64 / 2.0 - (y as f64 - self.center.y) * self.scale,
+        )
+    }
+
+    /// What lies at map point `point`: a vertex near it, else a wall near
+    /// it, else the sector enclosing it.
+    pub fn pick(&self, point: Vec2) -> Option<Selection> {
+        let reach = PICK_DISTANCE / self.scale;
+        let map = &self.map;
+        let at = |v: usize| Vec2::new(map.vertices[v].0 as f64, map.vertices[v].1 as f64);
+        let nearest = |distances: &mut dyn Iterator<Item = (usize, f64)>| {
+            distances
+                .filter(|&(_, d)| d <= reach)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+        };
+        if let Some(v) =
+            nearest(&mut (0..map.vertices.len()).map(|v| (v, (at(v) - point).length())))
+        {
+            return Some(Selection::Vertex(v));
+        }
+        let wall_distance = |wall: &MapWall| {
+            let (start, along) = (at(wall.v1), at(wall.v2) - at(wall.v1));
+            let t = ((point - start).dot(along) / along.dot(along)).clamp(0.0, 1.0);
+            (point - (start + along * t)).length()
+        };
+        if let Some(w) = nearest(&mut map.walls.iter().map(wall_distance).enumerate()) {
+            return Some(Selection::Wall(w));
+        }
+        let (sectors, walls) = map.geometry();
+        sector_at(&sectors, &walls, point).map(Selection::Sector)
+    }
+
+    /// Select what lies at `point`, and start dragging it if it is a vertex.
+    pub fn grab(&mut self, point: Vec2) {
+        self.selection = self.pick(point);
+        if let Some(Selection::Vertex(v)) = self.selection {
+            self.checkpoint();
+            self.dragging = Some(v);
+        }
+    }
+
+    /// Move the vertex being dragged to the grid point nearest `point`.
+    pub fn drag(&mut self, point: Vec2) {
+        if let Some(v) = self.dragging {
+            self.map.vertices[v] = self.snap(point);
+        }
+    }
+
+    /// Stop dragging. A drag that moved nothing is not an edit.
+    pub fn release(&mut self) {
+        if self.dragging.take().is_some() && self.undo.last() == Some(&self.map) {
+            self.undo.pop();
+        }
+    }
+
+    /// Add a vertex at the grid point nearest `point` and select it.
+    pub fn add_vertex(&mut self, point: Vec2) -> usize {
+        self.checkpoint();
+        let v = self.map.vertex(self.snap(point));
+        self.selection = Some(Selection::Vertex(v));
+        v
+    }
+
+    /// Add a wall from the selected vertex to the grid point nearest
+    /// `point`, adding a vertex there if needed, and select its end so that
+    /// walls can be drawn one after another. A wall drawn while a sector is
+    /// selected goes into it.
+    pub fn add_wall(&mut self, point: Vec2) -> Result<usize, String> {
+        let from = match self.selection {
+            Some(Selection::Vertex(v)) => v,
+            _ => return Err("select the vertex to start the wall from".to_owned()),
+        };
+        let to = self.snap(point);
+        if self.map.vertices[from] == to {
+            return Err("a wall needs two different end points".to_owned());
+        }
+        self.checkpoint();
+        let v2 = self.map.vertex(to);
+        self.map.walls.push(MapWall {
+            v1: from,
+            v2,
+            color: WALL_COLOR,
+            texture: None,
+            sector: None,
+            portal: None,
+            trigger: None,
+        });
+        self.selection = Some(Selection::Vertex(v2));
+        Ok(self.map.walls.len() - 1)
+    }
+
+    /// Delete the selection: a vertex with its walls, a wall, or a sector,
+    /// whose walls are then in no sector.
+    pub fn delete(&mut self) {
+        let selection = match self.selection.take() {
+            Some(selection) => selection,
+            None => return,
+        };
+        self.checkpoint();
+        match selection {
+            Selection::Vertex(v) => self.map.remove_vertex(v),
+            Selection::Wall(w) => {
+                self.map.walls.remove(w);
+            }
+            Selection::Sector(s) => self.map.remove_sector(s),
+        }
+    }
+
+    /// Group the closed loop of walls through the selected wall into a new
+    /// sector, with the heights and colours of the last sector, and select it.
+    pub fn make_sector(&mut self) -> Result<usize, String> {
+        let wall = match self.selection {
+            Some(Selection::Wall(w)) => w,
+            _ => return Err("select a wall of the loop".to_owned()),
+        };
+        let walls = self
+            .map
+            .find_loop(wall)
+            .ok_or("the walls don't form a closed loop")?;
+        self.checkpoint();
+        let sector = self.map.sectors.last().copied().unwrap_or_default();
+        self.map.sectors.push(sector);
+        let s = self.map.sectors.len() - 1;
+        for w in walls {
+            self.map.walls[w].sector = Some(s);
+        }
+        self.selection = Some(Selection::Sector(s));
+        Ok(s)
+    }
+
+    /// Raise the selected sector's floor by `floor` and its ceiling by
+    /// `ceiling` units, keeping the floor at or below the ceiling.
+    pub fn raise(&mut self, floor: i32, ceiling: i32) {
+        if let Some(Selection::Sector(s)) = self.selection {
+            self.checkpoint();
+            let sector = &mut self.map.sectors[s];
+            sector.z2 += ceiling;
+            sector.z1 = (sector.z1 + floor).min(sector.z2);
+        }
+    }
+
+    /// Make the selected sector a door, or a sector with a special an
+    /// ordinary one.
+    pub fn toggle_door(&mut self) {
+        if let Some(Selection::Sector(s)) = self.selection {
+            self.checkpoint();
+            let sector = &mut self.map.sectors[s];
+            sector.special = match sector.special {
+                Some(_) => None,
+                None => Some(Special::Door),
+            };
+        }
+    }
+
+    /// Step the selected wall's colour, or the selected sector's floor or
+    /// ceiling colour, through the palette by `step`.
+    pub fn change_color(&mut self, step: i32, ceiling: bool) {
+        if !matches!(
+            self.selection,
+            Some(Selection::Wall(_) | Selection::Sector(_))
+        ) {
+            return;
+        }
+        self.checkpoint();
+        let color = match self.selection {
+            Some(Selection::Wall(w)) => &mut self.map.walls[w].color,
+            Some(Selection::Sector(s)) if ceiling => &mut self.map.sectors[s].ceiling_color,
+            Some(Selection::Sector(s)) => &mut self.map.sectors[s].floor_color,
+            _ => unreachable!(),
+        };
+        *color = (*color as i32 + step).rem_euclid(256) as u8;
+    }
+
+    /// Move the player start to the grid point nearest `point`.
+    pub fn place_player(&mut self, point: Vec2) {
+        self.checkpoint();
+        self.map.player = self.snap(point);
+    }
+
+    /// Draw the map from above into `frame`, `WIDTH * HEIGHT` RGBA pixels.
+    pub fn draw(&self, frame: &mut [u8]) {
+        let palette = self.rest.palettes.first().cloned().unwrap_or_default();
+        let mut indexed = vec![palette.nearest(BACKGROUND_COLOR); (WIDTH * HEIGHT) as usize];
+        self.draw_indexed(&palette, &mut indexed);
+        for (pixel, &color) in frame.chunks_exact_mut(4).zip(&indexed) {
+            let [r, g, b] = palette.colors[color as usize];
+            pixel.copy_from_slice(&[r, g, b, 0xff]);
+        }
+    }
+
+    fn draw_indexed(&self, palette: &Palette, frame: &mut [u8]) {
+        let map = &self.map;
+        let grid = self.grid_size();
+        if grid as f64 * self.scale >= 4.0 {
+            let color = palette.nearest(GRID_COLOR);
+            let low = self.snap(self.to_map(0.0, HEIGHT as f64));
+            let high = self.snap(self.to_map(WIDTH as f64, 0.0));
+            for x in (low.0..=high.0).step_by(grid as usize) {
+                line(
+                    frame,
+                    self.to_screen((x, low.1)),
+                    self.to_screen((x, high.1)),
+                    color,
+                );
+            }
+            for y in (low.1..=high.1).step_by(grid as usize) {
+                line(
+                    frame,
+                    self.to_screen((low.0, y)),
+                    self.to_screen((high.0, y)),
+                    color,
+                );
+            }
+        }
+
+        let selected = palette.nearest(SELECTED_COLOR);
+        for (w, wall) in map.walls.iter().enumerate() {
+            let is_selected = match self.selection {
+                Some(Selection::Wall(s)) => s == w,
+                Some(Selection::Sector(s)) => wall.sector == Some(s),
+                _ => false,
+            };
+            let color = if is_selected {
+                selected
+            } else if wall.sector.is_none() {
+                palette.nearest(LOOSE_COLOR)
+            } else if map.twin_sector(wall).or(wall.portal).is_some() {
+                palette.nearest(PORTAL_COLOR)
+            } else {
+                palette.nearest(SOLID_COLOR)
+            };
+            let (a, b) = (
+                self.to_screen(map.vertices[wall.v1]),
+                self.to_screen(map.vertices[wall.v2]),
+            );
+            line(frame, a, b, color);
+            // A tick on the inside, to show which way the wall faces.
+            let middle = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+            if let Some(inside) = Vec2::new(b.0 - a.0, a.1 - b.1).normalize() {
+                let tick = inside.perp() * 4.0;
+                line(frame, middle, (middle.0 + tick.x, middle.1 - tick.y), color);
+            }
+        }
+
+        for (v, &vertex) in map.vertices.iter().enumerate() {
+            let color = if self.selection == Some(Selection::Vertex(v)) {
+                selected
+            } else {
+                palette.nearest(VERTEX_COLOR)
+            };
+            let (x, y) = self.to_screen(vertex);
+            line(frame, (x - 1.0, y - 1.0), (x + 1.0, y - 1.0), color);
+            line(frame, (x - 1.0, y), (x + 1.0, y), color);
+            line(frame, (x - 1.0, y + 1.0), (x + 1.0, y + 1.0), color);
+        }
+
+        // The player start: a cross with a line the way they face.
+        let color = palette.nearest(PLAYER_COLOR);
+        let (x, y) = self.to_screen(map.player);
+        let facing = Vec2::from_angle(self.rest.player.angle) * 8.0;
+        line(frame, (x - 3.0, y), (x + 3.0, y), color);
+        line(frame, (x, y - 3.0), (x, y + 3.0), color);
+        line(frame, (x, y), (x + facing.x, y - facing.y), color);
+    }
+}
@@ -0,0 +1,280 @@
+//! Player actions and the inputs bound to them.
+//!
+//! The game only ever asks whether an `Action` is held or was pressed; which
+//! key, mouse button or wheel direction triggers it is up to the `Bindings`,
+//! which a `Config` loads from a file.
+
+use std::fmt;
+
+/// Something the player can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Forward,
+    Backward,
+    TurnLeft,
+    TurnRight,
+    StrafeLeft,
+    StrafeRight,
+    /// Held to make the turn actions look up and down and, while flying, the
+    /// forward and backward actions rise and sink.
+    Look,
+    Jump,
+    Crouch,
+    /// Toggles flying.
+    Fly,
+    Use,
+    Fire,
+    /// Switch to the next weapon.
+    NextWeapon,
+    Automap,
+    /// Zoom the automap in and out while held.
+    ZoomIn,
+    ZoomOut,
+    /// Toggles whether the automap follows the player; while it doesn't,
+    /// the movement actions pan it instead of moving the player.
+    Follow,
+    /// Toggles the automap's grid.
+    Grid,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::Forward,
+        Action::Backward,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Look,
+        Action::Jump,
+        Action::Crouch,
+        Action::Fly,
+        Action::Use,
+        Action::Fire,
+        Action::NextWeapon,
+        Action::Automap,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Follow,
+        Action::Grid,
+    ];
+
+    /// The name used in config files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Backward => "backward",
+            Action::TurnLeft => "turn-left",
+            Action::TurnRight => "turn-right",
+            Action::StrafeLeft => "strafe-left",
+            Action::StrafeRight => "strafe-right",
+            Action::Look => "look",
+            Action::Jump => "jump",
+            Action::Crouch => "crouch",
+            Action::Fly => "fly",
+            Action::Use => "use",
+            Action::Fire => "fire",
+            Action::NextWeapon => "next-weapon",
+            Action::Automap => "automap",
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::Follow => "follow",
+            Action::Grid => "grid",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A physical input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    /// A keyboard key by its winit `VirtualKeyCode` name, such as `W`,
+    /// `Space` or `LControl`.
+    Key(String),
+    /// A mouse button: 1 is the left button, 2 the right, 3 the middle.
+    Mouse(u16),
+    WheelUp,
+    WheelDown,
+}
+
+impl Input {
+    /// Parse the name used in config files: `Mouse1`, `WheelUp`, `WheelDown`
+    /// or a key name. Names are case insensitive.
+    pub fn from_name(name: &str) -> Option<Input> {
+        let lower = name.to_ascii_lowercase();
+        if lower == "wheelup" {
+            return Some(Input::WheelUp);
+        }
+        if lower == "wheeldown" {
+            return Some(Input::WheelDown);
+        }
+        if let Some(button) = lower.strip_prefix("mouse") {
+            return button.parse().ok().filter(|&b| b > 0).map(Input::Mouse);
+        }
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Some(Input::Key(key_name(name)))
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Key(name) => f.write_str(name),
+            Input::Mouse(button) => write!(f, "Mouse{}", button),
+            Input::WheelUp => f.write_str("WheelUp"),
+            Input::WheelDown => f.write_str("WheelDown"),
+        }
+    }
+}
+
+/// Key names as winit spells them: `w` and `W` are both `W`, longer names
+/// keep the case they were given in.
+fn key_name(name: &str) -> String {
+    if name.len() == 1 {
+        name.to_ascii_uppercase()
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Which inputs trigger which actions. An input may trigger several actions
+/// and an action may have several inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    pub bindings: Vec<(Input, Action)>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Bindings {
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn bind(&mut self, input: Input, action: Action) {
+        if !self.bindings.contains(&(input.clone(), action)) {
+            self.bindings.push((input, action));
+        }
+    }
+
+    /// The actions `input` triggers.
+    pub fn actions<'a>(&'a self, input: &'a Input) -> impl Iterator<Item = Action> + 'a {
+        self.bindings.iter().filter_map(move |(bound, action)| {
+            let matches = match (bound, input) {
+                (Input::Key(a), Input::Key(b)) => a.eq_ignore_ascii_case(b),
+                (a, b) => a == b,
+            };
+            matches.then_some(*action)
+        })
+    }
+
+    /// The inputs bound to `action`.
+    pub fn inputs(&self, action: Action) -> impl Iterator<Item = &Input> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(input, _)| input)
+    }
+}
+
+impl Default for Bindings {
+    /// The classic layout: W/S and the arrow keys move and turn, A/D turn,
+    /// comma and period strafe. Control fires and Q switches weapons. Tab
+    /// shows the automap, `=` and `-` zoom it.
+    fn default() -> Self {
+        let mut bindings = Bindings::new();
+        let key = |name: &str| Input::Key(name.to_owned());
+        for (input, action) in [
+            (key("W"), Action::Forward),
+            (key("Up"), Action::Forward),
+            (key("S"), Action::Backward),
+            (key("Down"), Action::Backward),
+            (key("A"), Action::TurnLeft),
+            (key("Left"), Action::TurnLeft),
+            (key("D"), Action::TurnRight),
+            (key("Right"), Action::TurnRight),
+            (key("Comma"), Action::StrafeLeft),
+            (key("Period"), Action::StrafeRight),
+            (key("M"), Action::Look),
+            (key("Space"), Action::Jump),
+            (key("C"), Action::Crouch),
+            (key("F"), Action::Fly),
+            (key("E"), Action::Use),
+            (key("LControl"), Action::Fire),
+            (Input::Mouse(1), Action::Fire),
+            (key("Q"), Action::NextWeapon),
+            (key("Tab"), Action::Automap),
+            (key("Equals"), Action::ZoomIn),
+            (key("Minus"), Action::ZoomOut),
+            (key("L"), Action::Follow),
+            (key("G"), Action::Grid),
+        ] {
+            bindings.bind(input, action);
+        }
+        bindings
+    }
+}
+
+/// The state of every action.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Actions {
+    held: u32,
+    /// Actions pressed since the last tick, even if already released again.
+    pressed: u32,
+}
+
+impl Actions {
+    /// Record `action` being pressed or released. Repeated presses while
+    /// held, as from key repeat, are not new presses.
+    pub fn set(&mut self, action: Action, down: bool) {
+        if down {
+            if self.held & action.bit() == 0 {
+                self.pressed |= action.bit();
+            }
+            self.held |= action.bit();
+        } else {
+            self.held &= !action.bit();
+        }
+    }
+
+    /// Record a press without a release, such as a turn of the wheel: it
+    /// counts as held for one tick.
+    pub fn tap(&mut self, action: Action) {
+        self.pressed |= action.bit();
+    }
+
+    /// Whether `action` is held, or was pressed since the last tick.
+    pub fn held(&self, action: Action) -> bool {
+        (self.held | self.pressed) & action.bit() != 0
+    }
+
+    /// Whether `action` was pressed since the last tick.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed & action.bit() != 0
+    }
+
+    /// Forget the presses of the tick that just ended.
+    pub fn end_tick(&mut self) {
+        self.pressed = 0;
+    }
+
+    /// Release everything, as when the window loses focus.
+    pub fn release_all(&mut self) {
+        *self = Actions::default();
+    }
+}
@@ -0,0 +1,752 @@
+//! Text level format.
+//!
+//! A level file is a list of directives, one per line. Tokens are separated by
+//! whitespace and everything after a `#` is a comment. The first directive
+//! names the format version:
+//!
+//! ```text
+//! doomrust-level 1
+//! ```
+//!
+//! followed by, in any order:
+//!
+//! ```text
+//! player <x> <y> <z> <angle>
+//! texture <name> <source>
+//! sprite <name> <source>
+//! thing <x> <y> <z> <angle> <sprite> [health <health>]
+//! palette <file>
+//! sector <floor> <ceiling> <floor-color> <ceiling-color> [light <level>] [<special>] [tag <tag>]
+//! wall <x1> <y1> <x2> <y2> <color> [portal <sector>] [texture <name> [<u-offset> <v-offset> <u-scale> <v-scale>]] [trigger <trigger>]
+//! ```
+//!
+//! `player` is required and appears once; `z` is the height of the player's
+//! feet, who falls to the floor if it is above it, and `angle` is in whole
+//! degrees.
+//!
+//! `palette` replaces the engine's palette with a `PLAYPAL` file or the
+//! `PLAYPAL` lump of a WAD, relative to the level file. Colors then index it,
+//! and textures are drawn in its closest colours.
+//!
+//! A texture `source` is either the name of a built-in generator (`bricks`,
+//! `slabs`) or the path of a binary PPM image, relative to the level file.
+//! Sprites are given the same way, with the generators `barrel` and `lamp`;
+//! their cyan (`0 255 255`) texels are transparent.
+//!
+//! A `thing` stands with the bottom of its sprite at `z`, facing `angle`
+//! degrees. Sprites must be defined before the things using them. Shots
+//! stop at every thing, and destroy those with health once they have done
+//! that much damage, see `weapon`.
+//!
+//! Sectors are numbered from 0 in the order they appear. Their light level
+//! runs from 0 (dark) to 255, the default. A special makes the sector move
+//! when the player uses it, see `mover`:
+//!
+//! ```text
+//! door                 ceiling opens up, usually from shut on the floor
+//! lift <low>           floor lowers to <low> and returns
+//! platform <low>       floor goes between <low> and its height for ever
+//! crusher              ceiling comes down to the floor and back for ever
+//! stairs <step>        floor rises by <step>, the stairs beyond a step more each
+//! ```
+//!
+//! A sector's tag is a number naming it for triggers, which act on every
+//! sector with their tag.
+//!
+//! Every `wall` belongs to the sector declared last; a sector's walls run
+//! clockwise, seen from above, and must close its outline. A wall with
+//! `portal` opens onto the given sector. Textured walls default to no offset
+//! and one texel per unit.
+//!
+//! A trigger, see `trigger`, is `<activation> [once] <effect>`: it fires
+//! when the player crosses the wall (`walk`), uses it (`use`) or shoots it
+//! (`shoot`), only the first time if `once`, and then does one of:
+//!
+//! ```text
+//! start <tag>          start the movers of the sectors tagged <tag>
+//! light <tag> <level>  set the light level of the sectors tagged <tag>
+//! exit                 end the level
+//! script <n>           run the script handlers of `on trigger <n>`
+//! ```
+//!
+//! Script handlers, `on <event>` up to their `end`, may come anywhere
+//! between directives; `script` describes them.
+//!
+//! Colors are indices into the palette; 8 is the sky.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::colormap::FULL_BRIGHT;
+use crate::math::{Angle, Vec3};
+use crate::mover::Special;
+use crate::palette::{self, Palette};
+use crate::script::{self, Script};
+use crate::texture::Texture;
+use crate::trigger::{Activation, Effect, Trigger};
+use crate::{Player, Sector, TextureMapping, Thing, Wall};
+
+/// The format version this parser reads.
+pub const VERSION: u32 = 1;
+
+/// Sector options naming a special.
+const SPECIALS: [&str; 5] = ["door", "lift", "platform", "crusher", "stairs"];
+
+/// Everything needed to build a `World`.
+#[derive(Clone)]
+pub struct Level {
+    pub player: Player,
+    pub sectors: Vec<Sector>,
+    pub walls: Vec<Wall>,
+    pub textures: Vec<Texture>,
+    pub things: Vec<Thing>,
+    pub sprites: Vec<Texture>,
+    /// The palette set, or empty for the engine's own.
+    pub palettes: Vec<Palette>,
+    /// Name and source of every texture, as given in the level file.
+    pub texture_sources: Vec<(String, String)>,
+    /// Name and source of every sprite, as given in the level file.
+    pub sprite_sources: Vec<(String, String)>,
+    /// The palette file, as given in the level file.
+    pub palette_file: Option<String>,
+    /// The script handlers, see `script`.
+    pub script: Script,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// A problem with the contents, at a 1-based line and column.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}: {}", line, column, message),
+        }
+    }
+}
+
+impl From<script::Error> for Error {
+    fn from(err: script::Error) -> Self {
+        Error::Parse {
+            line: err.line,
+            column: err.column,
+            message: err.message,
+        }
+    }
+}
+
+/// The level in file format. Positions and angles are rounded to whole
+/// units and degrees, as the format has them.
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let degrees = |angle: Angle| angle.degrees().round() as i32 % 360;
+        let position = |p: Vec3| format!("{} {} {}", p.x.round(), p.y.round(), p.z.round());
+        writeln!(f, "doomrust-level {}", VERSION)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "player {} {}",
+            position(self.player.position),
+            degrees(self.player.angle)
+        )?;
+        if let Some(palette) = &self.palette_file {
+            writeln!(f, "palette {}", palette)?;
+        }
+        for (name, source) in &self.texture_sources {
+            writeln!(f, "texture {} {}", name, source)?;
+        }
+        for (name, source) in &self.sprite_sources {
+            writeln!(f, "sprite {} {}", name, source)?;
+        }
+        for thing in &self.things {
+            write!(
+                f,
+                "thing {} {} {}",
+                position(thing.position),
+                degrees(thing.angle),
+                self.sprite_sources[thing.sprite].0
+            )?;
+            if let Some(health) = thing.health {
+                write!(f, " health {}", health)?;
+            }
+            writeln!(f)?;
+        }
+        for (n, sector) in self.sectors.iter().enumerate() {
+            writeln!(f)?;
+            writeln!(f, "# sector {}", n)?;
+            write!(
+                f,
+                "sector {} {} {} {}",
+                sector.z1, sector.z2, sector.floor_color, sector.ceiling_color
+            )?;
+            if sector.light != FULL_BRIGHT {
+                write!(f, " light {}", sector.light)?;
+            }
+            if let Some(special) = sector.special {
+                write!(f, " {}", special)?;
+            }
+            if let Some(tag) = sector.tag {
+                write!(f, " tag {}", tag)?;
+            }
+            writeln!(f)?;
+            for wall in &self.walls[sector.wall_start..sector.wall_end] {
+                write!(
+                    f,
+                    "wall {} {} {} {} {}",
+                    wall.x1, wall.y1, wall.x2, wall.y2, wall.color
+                )?;
+                if let Some(neighbour) = wall.neighbour {
+                    write!(f, " portal {}", neighbour)?;
+                }
+                if let Some(mapping) = wall.texture {
+                    write!(f, " texture {}", self.texture_sources[mapping.texture].0)?;
+                    let offsets = [
+                        mapping.u_offset,
+                        mapping.v_offset,
+                        mapping.u_scale,
+                        mapping.v_scale,
+                    ];
+                    if offsets != [0.0, 0.0, 1.0, 1.0] {
+                        for value in offsets {
+                            write!(f, " {}", value)?;
+                        }
+                    }
+                }
+                if let Some(trigger) = wall.trigger {
+                    write!(f, " trigger {}", trigger)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        write!(f, "{}", self.script)
+    }
+}
+
+impl Level {
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_string()).map_err(|err| Error::Io(path.to_owned(), err))
+    }
+}
+
+/// Read and parse the level at `path`.
+pub fn load(path: &Path) -> Result<Level, Error> {
+    let source = fs::read_to_string(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+    parse(&source, path.parent().unwrap_or_else(|| Path::new(".")))
+}
+
+/// Parse a level. Image and palette files are looked up relative to `dir`.
+pub fn parse(source: &str, dir: &Path) -> Result<Level, Error> {
+    let mut parser = Parser {
+        dir,
+        version: None,
+        player: None,
+        sectors: Vec::new(),
+        walls: Vec::new(),
+        textures: Vec::new(),
+        texture_sources: Vec::new(),
+        things: Vec::new(),
+        sprites: Vec::new(),
+        sprite_sources: Vec::new(),
+        palettes: Vec::new(),
+        palette_file: None,
+        portals: Vec::new(),
+        script: Script::default(),
+    };
+    let mut lines = source.lines().enumerate().map(|(n, text)| (n + 1, text));
+    while let Some((number, text)) = lines.next() {
+        let mut line = Line::new(number, text.split('#').next().unwrap_or(""));
+        match line.next() {
+            // A script handler, whose lines are its own language up to its `end`.
+            Some(directive) if directive.text == "on" => {
+                let handler = script::Handler::parse(number, text, &mut lines)?;
+                parser.script.handlers.push(handler);
+            }
+            Some(directive) => {
+                parser.directive(directive, &mut line)?;
+                line.end()?;
+            }
+            None => {}
+        }
+    }
+    parser.finish(source.lines().count() + 1)
+}
+
+/// Whitespace separated tokens of one line, with their positions.
+#[derive(Clone)]
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+    /// Byte offset of the next unread character.
+    offset: usize,
+}
+
+/// A token and the 1-based position it starts at.
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    fn int(&self) -> Result<i32, Error> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("expected an integer, found `{}`", self.text)))
+    }
+
+    fn float(&self) -> Result<f64, Error> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("expected a number, found `{}`", self.text)))
+    }
+
+    fn color(&self) -> Result<u8, Error> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("expected a color 0-255, found `{}`", self.text)))
+    }
+
+    fn light(&self) -> Result<u8, Error> {
+        self.text.parse().map_err(|_| {
+            self.error(format!(
+                "expected a light level 0-255, found `{}`",
+                self.text
+            ))
+        })
+    }
+
+    fn tag(&self) -> Result<u32, Error> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("expected a tag, found `{}`", self.text)))
+    }
+
+    fn health(&self) -> Result<u32, Error> {
+        self.text
+            .parse()
+            .ok()
+            .filter(|&health| health > 0)
+            .ok_or_else(|| self.error(format!("expected a health above 0, found `{}`", self.text)))
+    }
+
+    fn index(&self) -> Result<usize, Error> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("expected a sector number, found `{}`", self.text)))
+    }
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Self {
+        Line {
+            number,
+            text,
+            offset: 0,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.text[self.offset..];
+        let start = self.offset + (rest.len() - rest.trim_start().len());
+        let rest = &self.text[start..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.offset = start + len;
+        Some(Token {
+            text: &rest[..len],
+            line: self.number,
+            column: self.text[..start].chars().count() + 1,
+        })
+    }
+
+    /// Position just past the end of the line, for "missing token" errors.
+    fn end_error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line: self.number,
+            column: self.text.trim_end().chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    /// The next token, without reading it.
+    fn peek(&self) -> Option<Token<'a>> {
+        self.clone().next()
+    }
+
+    fn expect(&mut self, what: &str) -> Result<Token<'a>, Error> {
+        self.next()
+            .ok_or_else(|| self.end_error(format!("missing {}", what)))
+    }
+
+    fn end(&mut self) -> Result<(), Error> {
+        match self.next() {
+            Some(token) => Err(token.error(format!("unexpected `{}`", token.text))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The rest of a `trigger` wall option: `<activation> [once] <effect>`.
+fn trigger(line: &mut Line) -> Result<Trigger, Error> {
+    let activation = line.expect("trigger activation")?;
+    let activation = match activation.text {
+        "walk" => Activation::Walk,
+        "use" => Activation::Use,
+        "shoot" => Activation::Shoot,
+        other => return Err(activation.error(format!("unknown activation `{}`", other))),
+    };
+    let mut effect = line.expect("trigger effect")?;
+    let once = effect.text == "once";
+    if once {
+        effect = line.expect("trigger effect")?;
+    }
+    let effect = match effect.text {
+        "start" => Effect::Start {
+            tag: line.expect("sector tag")?.tag()?,
+        },
+        "light" => Effect::Light {
+            tag: line.expect("sector tag")?.tag()?,
+            level: line.expect("light level")?.light()?,
+        },
+        "exit" => Effect::Exit,
+        "script" => Effect::Script {
+            event: line.expect("script number")?.tag()?,
+        },
+        other => return Err(effect.error(format!("unknown effect `{}`", other))),
+    };
+    Ok(Trigger {
+        activation,
+        effect,
+        once,
+    })
+}
+
+struct Parser<'a> {
+    dir: &'a Path,
+    version: Option<u32>,
+    player: Option<Player>,
+    sectors: Vec<Sector>,
+    walls: Vec<Wall>,
+    textures: Vec<Texture>,
+    texture_sources: Vec<(String, String)>,
+    things: Vec<Thing>,
+    sprites: Vec<Texture>,
+    sprite_sources: Vec<(String, String)>,
+    palettes: Vec<Palette>,
+    palette_file: Option<String>,
+    /// Portal targets and where they were given, checked once all sectors are known.
+    portals: Vec<(usize, usize, usize)>,
+    script: Script,
+}
+
+impl<'a> Parser<'a> {
+    fn directive(&mut self, directive: Token, line: &mut Line) -> Result<(), Error> {
+        if self.version.is_none() {
+            if directive.text != "doomrust-level" {
+                return Err(directive.error("expected `doomrust-level <version>` header"));
+            }
+            let version = line.expect("format version")?;
+            if version.text != VERSION.to_string() {
+                return Err(version.error(format!(
+                    "unsupported format version `{}`, expected {}",
+                    version.text, VERSION
+                )));
+            }
+            self.version = Some(VERSION);
+            return Ok(());
+        }
+
+        match directive.text {
+            "player" => {
+                if self.player.is_some() {
+                    return Err(directive.error("player start given twice"));
+                }
+                let x = line.expect("player x")?.int()? as f64;
+                let y = line.expect("player y")?.int()? as f64;
+                let z = line.expect("player z")?.int()? as f64;
+                let angle = line.expect("player angle")?.int()?;
+                self.player = Some(Player::new(
+                    Vec3::new(x, y, z),
+                    Angle::from_degrees(angle as f64),
+                ));
+            }
+            "palette" => {
+                if !self.palettes.is_empty() {
+                    return Err(directive.error("palette given twice"));
+                }
+                let source = line.expect("palette file")?;
+                let path = self.dir.join(source.text);
+                self.palettes = palette::load(&path).map_err(|err| {
+                    source.error(format!("bad palette {}: {}", path.display(), err))
+                })?;
+                self.palette_file = Some(source.text.to_owned());
+            }
+            "texture" => {
+                let name = line.expect("texture name")?;
+                if self.texture_sources.iter().any(|(n, _)| n == name.text) {
+                    return Err(name.error(format!("texture `{}` defined twice", name.text)));
+                }
+                let source = line.expect("texture source")?;
+                let texture = self.image(source, |generator| match generator {
+                    "bricks" => Some(Texture::bricks([150, 70, 50], [120, 120, 110])),
+                    "slabs" => Some(Texture::slabs([120, 120, 130])),
+                    _ => None,
+                })?;
+                self.texture_sources
+                    .push((name.text.to_owned(), source.text.to_owned()));
+                self.textures.push(texture);
+            }
+            "sprite" => {
+                let name = line.expect("sprite name")?;
+                if self.sprite_sources.iter().any(|(n, _)| n == name.text) {
+                    return Err(name.error(format!("sprite `{}` defined twice", name.text)));
+                }
+                let source = line.expect("sprite source")?;
+                let sprite = self.image(source, |generator| match generator {
+                    "barrel" => Some(Texture::barrel([60, 140, 60])),
+                    "lamp" => Some(Texture::lamp([255, 230, 150])),
+                    _ => None,
+                })?;
+                self.sprite_sources
+                    .push((name.text.to_owned(), source.text.to_owned()));
+                self.sprites.push(sprite);
+            }
+            "thing" => {
+                let x = line.expect("thing x")?.int()? as f64;
+                let y = line.expect("thing y")?.int()? as f64;
+                let z = line.expect("thing z")?.int()? as f64;
+                let angle = line.expect("thing angle")?.int()?;
+                let name = line.expect("thing sprite")?;
+                let sprite = self
+                    .sprite_sources
+                    .iter()
+                    .position(|(n, _)| n == name.text)
+                    .ok_or_else(|| name.error(format!("unknown sprite `{}`", name.text)))?;
+                let mut health = None;
+                while let Some(option) = line.next() {
+                    match option.text {
+                        "health" if health.is_none() => {
+                            health = Some(line.expect("thing health")?.health()?);
+                        }
+                        _ => return Err(option.error(format!("unexpected `{}`", option.text))),
+                    }
+                }
+                self.things.push(Thing {
+                    position: Vec3::new(x, y, z),
+                    angle: Angle::from_degrees(angle as f64),
+                    sprite,
+                    health,
+                });
+            }
+            "sector" => {
+                let z1 = line.expect("floor height")?.int()?;
+                let z2 = line.expect("ceiling height")?.int()?;
+                let floor_color = line.expect("floor color")?.color()?;
+                let ceiling_color = line.expect("ceiling color")?.color()?;
+                let mut light = None;
+                let mut special = None;
+                let mut tag = None;
+                while let Some(option) = line.next() {
+                    if special.is_some() && SPECIALS.contains(&option.text) {
+                        return Err(option.error("sector has two specials"));
+                    }
+                    match option.text {
+                        "light" if light.is_none() => {
+                            light = Some(line.expect("light level")?.light()?);
+                        }
+                        "tag" if tag.is_none() => tag = Some(line.expect("sector tag")?.tag()?),
+                        "door" => special = Some(Special::Door),
+                        "lift" => {
+                            let low = line.expect("lift bottom")?.int()?;
+                            special = Some(Special::Lift { low });
+                        }
+                        "platform" => {
+                            let low = line.expect("platform bottom")?.int()?;
+                            special = Some(Special::Platform { low });
+                        }
+                        "crusher" => special = Some(Special::Crusher),
+                        "stairs" => {
+                            let step = line.expect("stair height")?.int()?;
+                            special = Some(Special::Stairs { step });
+                        }
+                        _ => return Err(option.error(format!("unexpected `{}`", option.text))),
+                    }
+                }
+                self.close_sector(directive)?;
+                self.sectors.push(Sector {
+                    wall_start: self.walls.len(),
+                    wall_end: self.walls.len(),
+                    z1,
+                    z2,
+                    floor_color,
+                    ceiling_color,
+                    light: light.unwrap_or(FULL_BRIGHT),
+                    special,
+                    tag,
+                });
+            }
+            "wall" => {
+                if self.sectors.is_empty() {
+                    return Err(directive.error("wall outside of a sector"));
+                }
+                let x1 = line.expect("wall x1")?.int()?;
+                let y1 = line.expect("wall y1")?.int()?;
+                let x2 = line.expect("wall x2")?.int()?;
+                let y2 = line.expect("wall y2")?.int()?;
+                let color = line.expect("wall color")?.color()?;
+                let mut wall = Wall {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    color,
+                    neighbour: None,
+                    texture: None,
+                    trigger: None,
+                };
+                while let Some(option) = line.next() {
+                    match option.text {
+                        "portal" if wall.neighbour.is_none() => {
+                            let sector = line.expect("portal sector")?;
+                            let n = sector.index()?;
+                            self.portals.push((n, sector.line, sector.column));
+                            wall.neighbour = Some(n);
+                        }
+                        "texture" if wall.texture.is_none() => {
+                            wall.texture = Some(self.texture_mapping(line)?);
+                        }
+                        "trigger" if wall.trigger.is_none() => {
+                            wall.trigger = Some(trigger(line)?);
+                        }
+                        _ => return Err(option.error(format!("unexpected `{}`", option.text))),
+                    }
+                }
+                if x1 == x2 && y1 == y2 {
+                    return Err(directive.error("wall has zero length"));
+                }
+                self.walls.push(wall);
+                self.sectors.last_mut().unwrap().wall_end = self.walls.len();
+            }
+            other => return Err(directive.error(format!("unknown directive `{}`", other))),
+        }
+        Ok(())
+    }
+
+    /// The image `source` names: a built-in one from `generator`, or else a
+    /// PPM file relative to the level.
+    fn image(
+        &self,
+        source: Token,
+        generator: impl Fn(&str) -> Option<Texture>,
+    ) -> Result<Texture, Error> {
+        if let Some(texture) = generator(source.text) {
+            return Ok(texture);
+        }
+        let path = self.dir.join(source.text);
+        let bytes = fs::read(&path)
+            .map_err(|err| source.error(format!("cannot read {}: {}", path.display(), err)))?;
+        Texture::from_ppm(&bytes)
+            .map_err(|err| source.error(format!("bad image {}: {}", path.display(), err)))
+    }
+
+    fn texture_mapping(&self, line: &mut Line) -> Result<TextureMapping, Error> {
+        let name = line.expect("texture name")?;
+        let texture = self
+            .texture_sources
+            .iter()
+            .position(|(n, _)| n == name.text)
+            .ok_or_else(|| name.error(format!("unknown texture `{}`", name.text)))?;
+        let mut mapping = TextureMapping {
+            texture,
+            u_offset: 0.0,
+            v_offset: 0.0,
+            u_scale: 1.0,
+            v_scale: 1.0,
+        };
+        // The offsets and scales are optional, and other wall options may
+        // follow the name instead.
+        if line.peek().is_some_and(|token| token.float().is_ok()) {
+            mapping.u_offset = line.expect("texture u offset")?.float()?;
+            mapping.v_offset = line.expect("texture v offset")?.float()?;
+            mapping.u_scale = line.expect("texture u scale")?.float()?;
+            mapping.v_scale = line.expect("texture v scale")?.float()?;
+        }
+        Ok(mapping)
+    }
+
+    /// Check that the previous sector got at least one wall before starting a new one.
+    fn close_sector(&self, at: Token) -> Result<(), Error> {
+        match self.sectors.last() {
+            Some(sector) if sector.wall_start == sector.wall_end => {
+                Err(at.error(format!("sector {} has no walls", self.sectors.len() - 1)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn finish(self, end_line: usize) -> Result<Level, Error> {
+        let at_end = |message: &str| Error::Parse {
+            line: end_line,
+            column: 1,
+            message: message.to_owned(),
+        };
+        if self.version.is_none() {
+            return Err(at_end("expected `doomrust-level <version>` header"));
+        }
+        let end = Token {
+            text: "",
+            line: end_line,
+            column: 1,
+        };
+        self.close_sector(end)?;
+        if self.sectors.is_empty() {
+            return Err(at_end("level has no sectors"));
+        }
+        if let Some(&(n, line, column)) = self.portals.iter().find(|p| p.0 >= self.sectors.len()) {
+            return Err(Error::Parse {
+                line,
+                column,
+                message: format!("portal to missing sector {}", n),
+            });
+        }
+        let player = self.player.ok_or_else(|| at_end("missing player start"))?;
+        Ok(Level {
+            player,
+            sectors: self.sectors,
+            walls: self.walls,
+            textures: self.textures,
+            things: self.things,
+            sprites: self.sprites,
+            palettes: self.palettes,
+            texture_sources: self.texture_sources,
+            sprite_sources: self.sprite_sources,
+            palette_file: self.palette_file,
+            script: self.script,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+//! A small sector-based 3D engine in the style of Doom.
+//!
+//! The engine renders into any RGBA buffer of `WIDTH * HEIGHT` pixels, so it
+//! runs just as well without a window: build a `World` from a `Level` and call
+//! `World::render` with a `Camera`.
+//!
+//! `World::update` advances the game by one tick of `timestep::TICK`; a
+//! `timestep::Timestep` turns real time into ticks.
+
+pub mod automap;
+pub mod bsp;
+pub mod colormap;
+pub mod config;
+pub mod editor;
+pub mod input;
+pub mod level;
+pub mod math;
+pub mod mover;
+pub mod palette;
+pub mod render;
+pub mod script;
+pub mod texture;
+pub mod timestep;
+pub mod trigger;
+pub mod validate;
+pub mod wad;
+pub mod weapon;
+pub mod world;
+
+pub use config::Config;
+pub use input::{Action, Actions};
+pub use level::Level;
+pub use render::{Camera, DepthBuffer};
+pub use world::{MouseSettings, Player, Sector, TextureMapping, Thing, Wall, World};
+
+pub const WIDTH: u32 = 320;
+pub const HEIGHT: u32 = 240;
+
+/// Palette index of the background visible outside of the level.
+pub const SKY: u8 = 8;
+
+/// Level played when no other level is given.
+pub const DEFAULT_LEVEL: &str = include_str!("../levels/default.lvl");
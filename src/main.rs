@@ -1,66 +1,346 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use doomrust::editor::{Editor, HEIGHT_STEP};
+use doomrust::input::Input;
+use doomrust::timestep::Timestep;
+use doomrust::wad::{self, Wad};
+use doomrust::{level, Config, Level, World, DEFAULT_LEVEL, HEIGHT, WIDTH};
 use pixels::{Error, Pixels, SurfaceTexture};
 use winit::dpi::LogicalSize;
-use winit::event::{Event, VirtualKeyCode};
+use winit::event::{
+    DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
+    WindowEvent,
+};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::WindowBuilder;
+use winit::window::{Window, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 
-const WIDTH: u32 = 320;
-const HEIGHT: u32 = 240;
+const ZOOM: f64 = 3.0;
 
-const SH2: i32 = HEIGHT as i32 / 2;
-const SW2: i32 = WIDTH as i32 / 2;
+/// Settings file used unless `--config` names another; created with the
+/// defaults if missing.
+const CONFIG_FILE: &str = "doomrust.cfg";
+
+/// Where the editor saves levels that didn't come from a level file.
+const EDITED_LEVEL: &str = "edited.lvl";
+
+/// Editor zoom per step of the mouse wheel.
+const EDITOR_ZOOM: f64 = 1.25;
+
+/// Pixels the editor view pans per arrow key press.
+const EDITOR_PAN: f64 = 32.0;
+
+const USAGE: &str = "usage: doomrust [--config <file>] [--sensitivity <degrees>] [--invert-y] \
+                     [--show-depth] [--edit] [<level-file> | <wad-file> [<map>]]
+       doomrust validate [<level-file> | <wad-file> [<map>]]";
+
+const EDITOR_HELP: &str = "\
+editor: F1 switches between the editor and the 3D view
+  left button   select, drag vertices        wheel, arrows  zoom and pan
+  V             add a vertex                 W              add a wall from the selected vertex
+  S             make a sector of the loop through the selected wall
+  Delete        delete the selection         G              change the grid size
+  PageUp/Down   raise or lower the ceiling, with Shift the floor
+  [ ]           change the colour of a wall or floor, with Shift the ceiling
+  D             make the selected sector a door, or clear its special
+  P             place the player start       Ctrl+Z, Ctrl+Y undo and redo
+  Ctrl+S        save";
+
+/// What the command line asks for.
+struct Args {
+    config: Config,
+    /// Show the depth buffer instead of the view, for debugging.
+    show_depth: bool,
+    /// Start in the level editor.
+    edit: bool,
+    /// `[<level-file> | <wad-file> [<map>]]`.
+    level: Vec<OsString>,
+}
 
-const ZOOM: f64 = 3.0;
+/// Read the config and apply the command line options on top of it.
+fn parse_args() -> Result<Args, String> {
+    let mut config_path = PathBuf::from(CONFIG_FILE);
+    let mut sensitivity = None;
+    let mut invert_y = false;
+    let mut show_depth = false;
+    let mut edit = false;
+    let mut level = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--config") => match args.next() {
+                Some(path) => config_path = PathBuf::from(path),
+                None => return Err(format!("--config: missing file\n{}", USAGE)),
+            },
+            Some("--sensitivity") => {
+                let value = args.next().unwrap_or_default();
+                sensitivity = value
+                    .to_str()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&s: &f64| s > 0.0)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        format!(
+                            "--sensitivity: expected a positive number, found `{}`",
+                            value.to_string_lossy()
+                        )
+                    })?;
+            }
+            Some("--invert-y") => invert_y = true,
+            Some("--show-depth") => show_depth = true,
+            Some("--edit") => edit = true,
+            Some(option) if option.starts_with("--") => {
+                return Err(format!("unknown option `{}`\n{}", option, USAGE))
+            }
+            _ => level.push(arg),
+        }
+    }
+
+    let mut config = Config::load_or_create(&config_path).map_err(|err| match err {
+        doomrust::config::Error::Io(..) => err.to_string(),
+        doomrust::config::Error::Parse { .. } => format!("{}:{}", config_path.display(), err),
+    })?;
+    if let Some(sensitivity) = sensitivity {
+        config.mouse.sensitivity = sensitivity;
+    }
+    config.mouse.invert_y |= invert_y;
+    Ok(Args {
+        config,
+        show_depth,
+        edit,
+        level,
+    })
+}
 
-mod math;
+/// Load the level named by `args`: `[<level-file> | <wad-file> [<map>]]`.
+fn load_level(args: &[OsString]) -> Result<Level, String> {
+    let mut args = args.iter();
+    let path = match args.next() {
+        Some(path) => PathBuf::from(path),
+        None => {
+            return level::parse(DEFAULT_LEVEL, Path::new("."))
+                .map_err(|err| format!("default level:{}", err))
+        }
+    };
 
-/// Representation of the application state. In this example, a box will bounce around the screen.
-struct World {
-    keys: Keys,
-    tick: u32,
-    player: Player,
-    sectors: Vec<Sector>,
-    walls: Vec<Wall>,
+    let is_wad = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wad"));
+    if is_wad {
+        let wad = Wad::load(&path).map_err(|err| match err {
+            wad::Error::Io(..) => err.to_string(),
+            _ => format!("{}: {}", path.display(), err),
+        })?;
+        let map = match args.next() {
+            Some(map) => map.to_string_lossy().into_owned(),
+            None => match wad.maps().first() {
+                Some(map) => map.to_string(),
+                None => return Err(format!("{}: no maps in WAD", path.display())),
+            },
+        };
+        return wad::load_map(&wad, &map).map_err(|err| format!("{}: {}", path.display(), err));
+    }
+
+    level::load(&path).map_err(|err| match err {
+        level::Error::Io(..) => err.to_string(),
+        level::Error::Parse { .. } => format!("{}:{}", path.display(), err),
+    })
 }
 
-struct Keys {
-    up: bool,
-    down: bool,
-    left: bool,
-    right: bool,
-    strafe_left: bool,
-    strafe_right: bool,
-    look: bool,
+/// The `validate` subcommand: print the problems of the level named by
+/// `args`, or of every map of a WAD when no map is named. Returns whether
+/// there were none.
+fn validate(args: &[OsString]) -> Result<bool, String> {
+    let is_wad = args.first().is_some_and(|path| {
+        Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wad"))
+    });
+    let levels = if is_wad && args.len() == 1 {
+        let path = Path::new(&args[0]);
+        let wad = Wad::load(path).map_err(|err| match err {
+            wad::Error::Io(..) => err.to_string(),
+            _ => format!("{}: {}", path.display(), err),
+        })?;
+        let mut levels = Vec::new();
+        for map in wad.maps() {
+            let name = format!("{} {}", path.display(), map);
+            let level = wad::load_map(&wad, map).map_err(|err| format!("{}: {}", name, err))?;
+            levels.push((name, level));
+        }
+        levels
+    } else {
+        let name = match args {
+            [] => "default level".to_owned(),
+            [path] => Path::new(path).display().to_string(),
+            [path, map, ..] => format!("{} {}", Path::new(path).display(), map.to_string_lossy()),
+        };
+        vec![(name, load_level(args)?)]
+    };
+
+    let mut sound = true;
+    for (name, level) in levels {
+        let problems = level.validate();
+        for problem in &problems {
+            println!("{}: {}", name, problem);
+        }
+        match problems.len() {
+            0 => println!("{}: ok", name),
+            1 => println!("{}: 1 problem", name),
+            n => println!("{}: {} problems", name, n),
+        }
+        sound &= problems.is_empty();
+    }
+    Ok(sound)
 }
 
-struct Player {
-    x: i32,
-    y: i32,
-    z: i32,
-    angle: i32,
-    look: i32,
+/// The file the editor saves the level named by `args` to: the level file
+/// itself, or `EDITED_LEVEL` for the default level and maps from WADs.
+fn save_path(args: &[OsString]) -> PathBuf {
+    match args.first().map(PathBuf::from) {
+        Some(path)
+            if !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wad")) =>
+        {
+            path
+        }
+        _ => PathBuf::from(EDITED_LEVEL),
+    }
 }
 
-struct Wall {
-    x1: i32,
-    y1: i32,
-    x2: i32,
-    y2: i32,
-    color: u8,
+/// Apply the editor's keys and mouse buttons of the frame in `input`.
+fn edit(editor: &mut Editor, input: &WinitInputHelper, pixels: &Pixels, save_path: &Path) {
+    let report = |result: Result<usize, String>| {
+        if let Err(message) = result {
+            eprintln!("{}", message);
+        }
+    };
+    let (ctrl, shift) = (input.held_control(), input.held_shift());
+    let cursor = input
+        .mouse()
+        .and_then(|position| pixels.window_pos_to_pixel(position).ok())
+        .map(|(x, y)| editor.to_map(x as f64 + 0.5, y as f64 + 0.5));
+    if let Some(point) = cursor {
+        if input.mouse_pressed(0) {
+            editor.grab(point);
+        } else if input.mouse_held(0) {
+            editor.drag(point);
+        }
+        if input.key_pressed(VirtualKeyCode::V) {
+            editor.add_vertex(point);
+        }
+        if input.key_pressed(VirtualKeyCode::W) {
+            report(editor.add_wall(point));
+        }
+        if input.key_pressed(VirtualKeyCode::P) {
+            editor.place_player(point);
+        }
+    }
+    if input.mouse_released(0) {
+        editor.release();
+    }
+
+    let scroll = input.scroll_diff();
+    if scroll != 0.0 {
+        editor.zoom(EDITOR_ZOOM.powf(scroll as f64));
+    }
+    for (key, dx, dy) in [
+        (VirtualKeyCode::Left, -EDITOR_PAN, 0.0),
+        (VirtualKeyCode::Right, EDITOR_PAN, 0.0),
+        (VirtualKeyCode::Up, 0.0, -EDITOR_PAN),
+        (VirtualKeyCode::Down, 0.0, EDITOR_PAN),
+    ] {
+        if input.key_pressed(key) {
+            editor.pan(dx, dy);
+        }
+    }
+
+    if input.key_pressed(VirtualKeyCode::Delete) || input.key_pressed(VirtualKeyCode::Back) {
+        editor.delete();
+    }
+    if input.key_pressed(VirtualKeyCode::G) {
+        editor.cycle_grid();
+    }
+    if input.key_pressed(VirtualKeyCode::D) {
+        editor.toggle_door();
+    }
+    for (key, step) in [
+        (VirtualKeyCode::PageUp, HEIGHT_STEP),
+        (VirtualKeyCode::PageDown, -HEIGHT_STEP),
+    ] {
+        if input.key_pressed(key) {
+            if shift {
+                editor.raise(step, 0);
+            } else {
+                editor.raise(0, step);
+            }
+        }
+    }
+    for (key, step) in [
+        (VirtualKeyCode::LBracket, -1),
+        (VirtualKeyCode::RBracket, 1),
+    ] {
+        if input.key_pressed(key) {
+            editor.change_color(step, shift);
+        }
+    }
+
+    if ctrl {
+        if input.key_pressed(VirtualKeyCode::Z) {
+            editor.undo();
+        }
+        if input.key_pressed(VirtualKeyCode::Y) {
+            editor.redo();
+        }
+        if input.key_pressed(VirtualKeyCode::S) {
+            match editor.level() {
+                Ok(level) => match level.save(save_path) {
+                    Ok(()) => println!("saved {}", save_path.display()),
+                    Err(err) => eprintln!("{}", err),
+                },
+                Err(message) => eprintln!("cannot save: {}", message),
+            }
+        }
+    } else if input.key_pressed(VirtualKeyCode::S) {
+        report(editor.make_sector());
+    }
 }
 
-struct Sector {
-    wall_start: usize,
-    wall_end: usize,
-    z1: i32,
-    z2: i32,
-    x: i32,
-    y: i32,
-    distance: i32,
+/// Capture the mouse for turning, or give it back.
+fn grab_cursor(window: &Window, grab: bool) {
+    // Not every platform can grab the cursor; the game still works without.
+    let _ = window.set_cursor_grab(grab);
+    window.set_cursor_visible(!grab);
 }
 
 fn main() -> Result<(), Error> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next().is_some_and(|arg| arg == "validate") {
+        let args: Vec<_> = args.collect();
+        match validate(&args) {
+            Ok(sound) => std::process::exit(if sound { 0 } else { 1 }),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let parsed = parse_args().and_then(|args| {
+        let level = load_level(&args.level)?;
+        Ok((args, level))
+    });
+    let (args, level) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    };
+
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
     let window = {
@@ -78,12 +358,32 @@ fn main() -> Result<(), Error> {
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
         Pixels::new(WIDTH, HEIGHT, surface_texture)?
     };
-    let mut world = World::new();
+    let save_path = save_path(&args.level);
+    let show_depth = args.show_depth;
+    let mut editor = Editor::new(&level);
+    let mut editing = args.edit;
+    if editing {
+        println!("{}", EDITOR_HELP);
+    }
+    let mut world = World::new(level);
+    let mouse = args.config.mouse;
+    world.mouse = mouse;
+    let bindings = args.config.bindings;
+    grab_cursor(&window, !editing);
+    let mut grabbed = !editing;
+    let mut timestep = Timestep::new();
+    let mut last_frame = Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
         // Draw the current frame
         if let Event::RedrawRequested(_) = event {
-            world.draw(pixels.get_frame());
+            if editing {
+                editor.draw(pixels.get_frame());
+            } else if show_depth {
+                world.draw_depth(pixels.get_frame(), timestep.alpha());
+            } else {
+                world.draw(pixels.get_frame(), timestep.alpha());
+            }
             if pixels
                 .render()
                 .map_err(|e| println!("pixels.render() failed: {}", e))
@@ -94,6 +394,65 @@ fn main() -> Result<(), Error> {
             }
         }
 
+        // Turn with raw mouse motion while the cursor is captured, and turn
+        // keys, buttons and the wheel into the actions bound to them
+        let mut trigger = |input: Input, down: Option<bool>| {
+            for action in bindings.actions(&input) {
+                match down {
+                    Some(down) => world.actions.set(action, down),
+                    None => world.actions.tap(action),
+                }
+            }
+        };
+        match &event {
+            _ if editing => {}
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } if grabbed => world.mouse_moved(*dx, *dy),
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(key),
+                            state,
+                            ..
+                        },
+                    ..
+                } => trigger(
+                    Input::Key(format!("{:?}", key)),
+                    Some(*state == ElementState::Pressed),
+                ),
+                WindowEvent::MouseInput { state, button, .. } if grabbed => {
+                    let button = match button {
+                        MouseButton::Left => 1,
+                        MouseButton::Right => 2,
+                        MouseButton::Middle => 3,
+                        MouseButton::Other(n) => *n,
+                    };
+                    trigger(Input::Mouse(button), Some(*state == ElementState::Pressed));
+                }
+                WindowEvent::MouseWheel { delta, .. } if grabbed => {
+                    let y = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                        MouseScrollDelta::PixelDelta(position) => position.y,
+                    };
+                    if y > 0.0 {
+                        trigger(Input::WheelUp, None);
+                    } else if y < 0.0 {
+                        trigger(Input::WheelDown, None);
+                    }
+                }
+                WindowEvent::Focused(false) => {
+                    grab_cursor(&window, false);
+                    grabbed = false;
+                    world.actions.release_all();
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
         // Handle input events
         if input.update(&event) {
             // Close events
@@ -102,317 +461,60 @@ fn main() -> Result<(), Error> {
                 return;
             }
 
-            world.keys.down = input.key_held(VirtualKeyCode::S);
-            world.keys.up = input.key_held(VirtualKeyCode::W);
-            world.keys.left = input.key_held(VirtualKeyCode::A);
-            world.keys.right = input.key_held(VirtualKeyCode::D);
-            world.keys.strafe_left = input.key_held(VirtualKeyCode::Comma);
-            world.keys.strafe_right = input.key_held(VirtualKeyCode::Period);
-            world.keys.look = input.key_held(VirtualKeyCode::M);
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
+            // Switch between the editor and the 3D view of its level
+            if input.key_pressed(VirtualKeyCode::F1) {
+                if editing {
+                    match editor.level() {
+                        Ok(level) => {
+                            world = World::new(level);
+                            world.mouse = mouse;
+                            editing = false;
+                        }
+                        Err(message) => eprintln!("cannot leave the editor: {}", message),
+                    }
+                } else {
+                    println!("{}", EDITOR_HELP);
+                    editing = true;
+                    grab_cursor(&window, false);
+                    grabbed = false;
+                    world.actions.release_all();
+                }
             }
 
-            // Update internal state and request a redraw
-            world.update();
-            window.request_redraw();
-        }
-    });
-}
-
-impl World {
-    /// Create a new `World` instance that can draw the level.
-    fn new() -> Self {
-        let keys = Keys {
-            up: false,
-            down: false,
-            left: false,
-            right: false,
-            strafe_left: false,
-            strafe_right: false,
-            look: false,
-        };
-        let player = Player {
-            x: 70,
-            y: -110,
-            z: 20,
-            angle: 0,
-            look: 0,
-        };
-        let mut walls = Vec::new();
-        let mut sectors = Vec::new();
-        let init_sectors = [0, 4, 0, 40, 4, 8, 0, 40, 8, 12, 0, 40, 12, 16, 0, 40];
-
-        let init_walls = [
-            0, 0, 32, 0, 0, 32, 0, 32, 32, 1, 32, 32, 0, 32, 0, 0, 32, 0, 0, 1, 64, 0, 96, 0, 2,
-            96, 0, 96, 32, 3, 96, 32, 64, 32, 2, 64, 32, 64, 0, 3, 64, 64, 96, 64, 4, 96, 64, 96,
-            96, 5, 96, 96, 64, 96, 4, 64, 96, 64, 64, 5, 0, 64, 32, 64, 6, 32, 64, 32, 96, 7, 32,
-            96, 0, 96, 6, 0, 96, 0, 64, 7,
-        ];
-
-        for n in 0..4 {
-            sectors.push(Sector {
-                wall_start: init_sectors[n * 4],
-                wall_end: init_sectors[n * 4 + 1],
-                x: 0,
-                y: 0,
-                distance: 0,
-                z1: init_sectors[n * 4 + 2] as i32,
-                z2: init_sectors[n * 4 + 3] as i32,
-            });
-        }
-
-        for n in 0..16 {
-            walls.push(Wall {
-                x1: init_walls[n * 5],
-                y1: init_walls[n * 5 + 1],
-                x2: init_walls[n * 5 + 2],
-                y2: init_walls[n * 5 + 3],
-                color: init_walls[n * 5 + 4] as u8,
-            });
-        }
-
-        Self {
-            keys,
-            tick: 0,
-            player,
-            sectors,
-            walls,
-        }
-    }
-
-    /// Update the `World` internal state;
-    fn update(&mut self) {
-        self.tick += 1;
-        if self.tick < 4 {
-            return;
-        }
-        self.tick = 0;
-        if self.keys.left && !self.keys.look {
-            self.player.angle -= 4;
-            if self.player.angle < 0 {
-                self.player.angle += 360;
+            if editing {
+                edit(&mut editor, &input, &pixels, &save_path);
+            } else if input.mouse_pressed(0) && !grabbed {
+                grab_cursor(&window, true);
+                grabbed = true;
             }
-        }
-        if self.keys.right && !self.keys.look {
-            self.player.angle += 4;
-            if self.player.angle > 359 {
-                self.player.angle -= 360;
-            }
-        }
 
-        let dx = math::SIN[self.player.angle as usize] * 10.0;
-        let dy = math::COS[self.player.angle as usize] * 10.0;
-        if self.keys.up && !self.keys.look {
-            self.player.x += dx as i32;
-            self.player.y += dy as i32;
-        }
-        if self.keys.down && !self.keys.look {
-            self.player.x -= dx as i32;
-            self.player.y -= dy as i32;
-        }
-        if self.keys.strafe_left {
-            self.player.x -= dy as i32;
-            self.player.y += dx as i32;
-        }
-        if self.keys.strafe_right {
-            self.player.x += dy as i32;
-            self.player.y -= dx as i32;
-        }
-
-        if self.keys.left && self.keys.look {
-            self.player.look -= 1;
-        }
-        if self.keys.right && self.keys.look {
-            self.player.look += 1;
-        }
-        if self.keys.up && self.keys.look {
-            self.player.z -= 4;
-        }
-        if self.keys.down && self.keys.look {
-            self.player.z += 4;
-        }
-    }
+            // Resize the window
+            if let Some(size) = input.window_resized() {
+                pixels.resize_surface(size.width, size.height);
+            }
 
-    /// Draw the `World` state to the frame buffer.
-    ///
-    /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
-    fn draw(&mut self, frame: &mut [u8]) {
-        self.clear(frame);
-        let cs = math::COS[self.player.angle as usize];
-        let sn = math::SIN[self.player.angle as usize];
-
-        self.sectors.sort_by(|a, b| b.distance.cmp(&a.distance));
-
-        for s in 0..self.sectors.len() {
-            self.sectors[s].distance = 0;
-            for w in self.sectors[s].wall_start..self.sectors[s].wall_end {
-                let x1 = self.walls[w].x1 - self.player.x;
-                let y1 = self.walls[w].y1 - self.player.y;
-
-                let x2 = self.walls[w].x2 - self.player.x;
-                let y2 = self.walls[w].y2 - self.player.y;
-
-                let mut wx0 = x1 as f64 * cs - y1 as f64 * sn;
-                let mut wx1 = x2 as f64 * cs - y2 as f64 * sn;
-                let mut wx2 = wx0;
-                let mut wx3 = wx1;
-
-                let mut wy0 = y1 as f64 * cs + x1 as f64 * sn;
-                let mut wy1 = y2 as f64 * cs + x2 as f64 * sn;
-                let mut wy2 = wy0;
-                let mut wy3 = wy1;
-
-                self.sectors[s].distance +=
-                    World::distance(0, 0, (wx0 + wx1) as i32 / 2, (wy0 + wy1) as i32 / 2);
-
-                let mut wz0 = self.sectors[s].z1 as f64 - self.player.z as f64
-                    + (self.player.look as f64 * wy0 / 32.0);
-                let mut wz1 = self.sectors[s].z1 as f64 - self.player.z as f64
-                    + (self.player.look as f64 * wy1 / 32.0);
-                let mut wz2 = wz0 + self.sectors[s].z2 as f64;
-                let mut wz3 = wz1 + self.sectors[s].z2 as f64;
-
-                if wy0 < 1.0 && wy1 < 1.0 {
-                    continue;
+            // Run the simulation ticks due since the last frame, unless
+            // editing, and request a redraw
+            let now = Instant::now();
+            let ticks = timestep.advance(now - last_frame);
+            if !editing {
+                for _ in 0..ticks {
+                    world.update();
                 }
-                if wy0 < 1.0 {
-                    World::clip_behind_player(&mut wx0, &mut wy0, &mut wz0, wx1, wy1, wz1);
-                    World::clip_behind_player(&mut wx2, &mut wy2, &mut wz2, wx3, wy3, wz3);
+                for message in world.messages.drain(..) {
+                    println!("{}", message);
                 }
-                if wy1 < 1.0 {
-                    World::clip_behind_player(&mut wx1, &mut wy1, &mut wz1, wx0, wy0, wz0);
-                    World::clip_behind_player(&mut wx3, &mut wy3, &mut wz3, wx2, wy2, wz2);
+                for error in world.script_errors.drain(..) {
+                    eprintln!("script error: {}", error);
+                }
+                if world.exited {
+                    println!("level complete");
+                    *control_flow = ControlFlow::Exit;
+                    return;
                 }
-                /*
-                let sx0 = (wx0 * 200.0 / wy0) as i32 + SW2;
-                let sy0 = (wz0 * 200.0 / wy0) as i32 + SH2;
-
-                let sx1 = (wx1 * 200.0 / wy1) as i32 + SW2;
-                let sy1 = (wz1 * 200.0 / wy1) as i32 + SH2;
-
-                let sx2 = (wx2 * 200.0 / wy2) as i32 + SW2;
-                let sy2 = (wz2 * 200.0 / wy2) as i32 + SH2;
-
-                let sx3 = (wx3 * 200.0 / wy3) as i32 + SW2;
-                let sy3 = (wz3 * 200.0 / wy3) as i32 + SH2;
-                */
-
-                let sx0 = ((wx0 * 200.0 / wy0) as i32).saturating_add(SW2);
-                let sy0 = ((wz0 * 200.0 / wy0) as i32).saturating_add(SH2);
-
-                let sx1 = ((wx1 * 200.0 / wy1) as i32).saturating_add(SW2);
-                let sy1 = ((wz1 * 200.0 / wy1) as i32).saturating_add(SH2);
-
-                let sx2 = ((wx2 * 200.0 / wy2) as i32).saturating_add(SW2);
-                let sy2 = ((wz2 * 200.0 / wy2) as i32).saturating_add(SH2);
-
-                let sx3 = ((wx3 * 200.0 / wy3) as i32).saturating_add(SW2);
-                let sy3 = ((wz3 * 200.0 / wy3) as i32).saturating_add(SH2);
-
-                self.draw_wall(frame, sx0, sx1, sy0, sy1, sy2, sy3, self.walls[w].color);
-            }
-            let num_wall = (self.sectors[s].wall_end - self.sectors[s].wall_start) as i32;
-            self.sectors[s].distance /= num_wall;
-        }
-    }
-
-    fn draw_wall(
-        &self,
-        frame: &mut [u8],
-        mut x1: i32,
-        mut x2: i32,
-        b1: i32,
-        b2: i32,
-        t1: i32,
-        t2: i32,
-        color: u8,
-    ) {
-        //let dyb = b2 - b1;
-        let dyb = b2.saturating_sub(b1);
-        let dyt = t2 - t1;
-        let mut dx = x2 - x1;
-        if dx == 0 {
-            dx = 1;
-        }
-        let xs = x1;
-        if x1 < 1 {
-            x1 = 1;
-        }
-        if x2 < 1 {
-            x2 = 1;
-        }
-        if x1 > WIDTH as i32 - 1 {
-            x1 = WIDTH as i32 - 1;
-        }
-        if x2 > WIDTH as i32 - 1 {
-            x2 = WIDTH as i32 - 1;
-        }
-        for x in x1..x2 {
-            let mut y1 = (dyb as f64 * ((x - xs) as f64 + 0.5) / (dx as f64)) as i32 + b1;
-            let mut y2 = (dyt as f64 * ((x - xs) as f64 + 0.5) / (dx as f64)) as i32 + t1;
-            if y1 < 1 {
-                y1 = 1;
-            }
-            if y2 < 1 {
-                y2 = 1;
-            }
-            if y1 > HEIGHT as i32 - 1 {
-                y1 = HEIGHT as i32 - 1;
-            }
-            if y2 > HEIGHT as i32 - 1 {
-                y2 = HEIGHT as i32 - 1;
-            }
-            for y in y1..y2 {
-                self.pixel(frame, x as u32, y as u32, color);
             }
+            last_frame = now;
+            window.request_redraw();
         }
-    }
-
-    fn clip_behind_player(x1: &mut f64, y1: &mut f64, z1: &mut f64, x2: f64, y2: f64, z2: f64) {
-        let da = *y1;
-        let db = y2;
-        let mut d = da - db;
-        if d == 0.0 {
-            d = 1.0;
-        }
-        let s = da / d;
-        *x1 = *x1 + (s * (x2 - *x1));
-        *y1 = *y1 + (s * (y2 - *y1));
-        if *y1 == 0.0 {
-            *y1 = 1.0;
-        }
-        *z1 = *z1 + (s * (z2 - *z1));
-    }
-
-    fn distance(x1: i32, y1: i32, x2: i32, y2: i32) -> i32 {
-        (x2 - x1) * (x2 - x1) + (y2 - y1) * (y2 - y1)
-    }
-
-    fn clear(&self, frame: &mut [u8]) {
-        for pixel in frame.chunks_exact_mut(4) {
-            pixel[0] = 0; // R
-            pixel[1] = 60; // G
-            pixel[2] = 130; // B
-            pixel[3] = 0xff; // A
-        }
-    }
-    fn pixel(&self, frame: &mut [u8], x: u32, y: u32, c: u8) {
-        let rgb = match c {
-            0 => [255, 255, 0],
-            1 => [160, 160, 0],
-            2 => [0, 255, 0],
-            3 => [0, 160, 0],
-            4 => [0, 255, 255],
-            5 => [0, 160, 160],
-            6 => [160, 100, 0],
-            7 => [110, 50, 160],
-            _ => [0, 60, 130],
-        };
-        let i = ((y * WIDTH + x) * 4) as usize;
-        frame[i] = rgb[0];
-        frame[i + 1] = rgb[1];
-        frame[i + 2] = rgb[2];
-    }
+    });
 }
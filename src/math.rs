@@ -1,725 +0,0 @@
-
-pub const COS: [f64; 360] = [
-    1.0,
-    0.9998476951563913,
-    0.9993908270190958,
-    0.9986295347545738,
-    0.9975640502598242,
-    0.9961946980917455,
-    0.9945218953682733,
-    0.992546151641322,
-    0.9902680687415704,
-    0.9876883405951378,
-    0.984807753012208,
-    0.981627183447664,
-    0.9781476007338057,
-    0.9743700647852352,
-    0.9702957262759965,
-    0.9659258262890683,
-    0.9612616959383189,
-    0.9563047559630354,
-    0.9510565162951535,
-    0.9455185755993168,
-    0.9396926207859084,
-    0.9335804264972017,
-    0.9271838545667874,
-    0.9205048534524404,
-    0.9135454576426009,
-    0.9063077870366499,
-    0.898794046299167,
-    0.8910065241883679,
-    0.882947592858927,
-    0.8746197071393957,
-    0.8660254037844387,
-    0.8571673007021123,
-    0.848048096156426,
-    0.838670567945424,
-    0.8290375725550416,
-    0.8191520442889918,
-    0.8090169943749475,
-    0.7986355100472928,
-    0.788010753606722,
-    0.7771459614569709,
-    0.766044443118978,
-    0.754709580222772,
-    0.7431448254773942,
-    0.7313537016191706,
-    0.7193398003386512,
-    0.7071067811865476,
-    0.6946583704589974,
-    0.6819983600624985,
-    0.6691306063588582,
-    0.6560590289905073,
-    0.6427876096865394,
-    0.6293203910498375,
-    0.6156614753256583,
-    0.6018150231520483,
-    0.5877852522924731,
-    0.573576436351046,
-    0.5591929034707468,
-    0.5446390350150272,
-    0.5299192642332049,
-    0.5150380749100544,
-    0.5000000000000001,
-    0.4848096202463371,
-    0.46947156278589086,
-    0.45399049973954686,
-    0.43837114678907746,
-    0.42261826174069944,
-    0.4067366430758002,
-    0.39073112848927394,
-    0.37460659341591196,
-    0.3583679495453004,
-    0.3420201433256688,
-    0.32556815445715676,
-    0.30901699437494745,
-    0.29237170472273677,
-    0.27563735581699916,
-    0.25881904510252074,
-    0.2419218955996679,
-    0.22495105434386492,
-    0.20791169081775945,
-    0.19080899537654492,
-    0.17364817766693041,
-    0.15643446504023092,
-    0.1391731009600657,
-    0.12186934340514749,
-    0.10452846326765346,
-    0.08715574274765814,
-    0.06975647374412546,
-    0.052335956242943966,
-    0.03489949670250108,
-    0.017452406437283376,
-    6.123233995736766e-17,
-    -0.017452406437283477,
-    -0.03489949670250073,
-    -0.05233595624294362,
-    -0.06975647374412533,
-    -0.08715574274765824,
-    -0.10452846326765333,
-    -0.12186934340514737,
-    -0.13917310096006535,
-    -0.15643446504023104,
-    -0.1736481776669303,
-    -0.1908089953765448,
-    -0.20791169081775912,
-    -0.2249510543438648,
-    -0.24192189559966779,
-    -0.25881904510252085,
-    -0.27563735581699905,
-    -0.29237170472273666,
-    -0.30901699437494734,
-    -0.3255681544571564,
-    -0.3420201433256687,
-    -0.35836794954530027,
-    -0.37460659341591207,
-    -0.3907311284892736,
-    -0.4067366430758001,
-    -0.42261826174069933,
-    -0.4383711467890775,
-    -0.45399049973954675,
-    -0.46947156278589053,
-    -0.484809620246337,
-    -0.4999999999999998,
-    -0.5150380749100543,
-    -0.5299192642332048,
-    -0.5446390350150271,
-    -0.5591929034707467,
-    -0.5735764363510458,
-    -0.587785252292473,
-    -0.6018150231520484,
-    -0.6156614753256583,
-    -0.6293203910498373,
-    -0.6427876096865394,
-    -0.6560590289905075,
-    -0.6691306063588582,
-    -0.6819983600624984,
-    -0.694658370458997,
-    -0.7071067811865475,
-    -0.7193398003386513,
-    -0.7313537016191705,
-    -0.7431448254773941,
-    -0.754709580222772,
-    -0.7660444431189779,
-    -0.7771459614569707,
-    -0.7880107536067219,
-    -0.7986355100472929,
-    -0.8090169943749473,
-    -0.8191520442889916,
-    -0.8290375725550416,
-    -0.8386705679454242,
-    -0.848048096156426,
-    -0.8571673007021122,
-    -0.8660254037844387,
-    -0.8746197071393957,
-    -0.8829475928589268,
-    -0.8910065241883678,
-    -0.898794046299167,
-    -0.9063077870366499,
-    -0.9135454576426008,
-    -0.9205048534524402,
-    -0.9271838545667873,
-    -0.9335804264972017,
-    -0.9396926207859083,
-    -0.9455185755993167,
-    -0.9510565162951535,
-    -0.9563047559630354,
-    -0.9612616959383187,
-    -0.9659258262890682,
-    -0.9702957262759965,
-    -0.9743700647852352,
-    -0.9781476007338057,
-    -0.981627183447664,
-    -0.984807753012208,
-    -0.9876883405951377,
-    -0.9902680687415703,
-    -0.992546151641322,
-    -0.9945218953682733,
-    -0.9961946980917455,
-    -0.9975640502598242,
-    -0.9986295347545738,
-    -0.9993908270190958,
-    -0.9998476951563913,
-    -1.0,
-    -0.9998476951563913,
-    -0.9993908270190958,
-    -0.9986295347545738,
-    -0.9975640502598243,
-    -0.9961946980917455,
-    -0.9945218953682734,
-    -0.992546151641322,
-    -0.9902680687415703,
-    -0.9876883405951378,
-    -0.984807753012208,
-    -0.981627183447664,
-    -0.9781476007338057,
-    -0.9743700647852352,
-    -0.9702957262759966,
-    -0.9659258262890684,
-    -0.9612616959383189,
-    -0.9563047559630355,
-    -0.9510565162951535,
-    -0.9455185755993167,
-    -0.9396926207859084,
-    -0.9335804264972017,
-    -0.9271838545667874,
-    -0.9205048534524404,
-    -0.9135454576426011,
-    -0.90630778703665,
-    -0.8987940462991671,
-    -0.8910065241883681,
-    -0.8829475928589269,
-    -0.8746197071393959,
-    -0.8660254037844386,
-    -0.8571673007021123,
-    -0.8480480961564261,
-    -0.838670567945424,
-    -0.8290375725550418,
-    -0.819152044288992,
-    -0.8090169943749475,
-    -0.798635510047293,
-    -0.7880107536067222,
-    -0.7771459614569708,
-    -0.7660444431189781,
-    -0.7547095802227719,
-    -0.7431448254773942,
-    -0.7313537016191706,
-    -0.7193398003386511,
-    -0.7071067811865477,
-    -0.6946583704589975,
-    -0.6819983600624989,
-    -0.6691306063588585,
-    -0.6560590289905077,
-    -0.6427876096865395,
-    -0.6293203910498371,
-    -0.6156614753256581,
-    -0.6018150231520483,
-    -0.5877852522924732,
-    -0.5735764363510464,
-    -0.5591929034707472,
-    -0.544639035015027,
-    -0.529919264233205,
-    -0.5150380749100545,
-    -0.5000000000000004,
-    -0.48480962024633684,
-    -0.46947156278589075,
-    -0.4539904997395469,
-    -0.43837114678907774,
-    -0.42261826174069994,
-    -0.4067366430758001,
-    -0.3907311284892738,
-    -0.3746065934159123,
-    -0.3583679495453007,
-    -0.3420201433256694,
-    -0.32556815445715664,
-    -0.30901699437494756,
-    -0.2923717047227371,
-    -0.2756373558169989,
-    -0.25881904510252063,
-    -0.24192189559966779,
-    -0.22495105434386525,
-    -0.2079116908177598,
-    -0.19080899537654547,
-    -0.17364817766693033,
-    -0.15643446504023104,
-    -0.13917310096006494,
-    -0.12186934340514717,
-    -0.10452846326765336,
-    -0.08715574274765825,
-    -0.06975647374412558,
-    -0.052335956242944306,
-    -0.03489949670250165,
-    -0.017452406437283498,
-    -1.8369701987210297e-16,
-    0.01745240643728313,
-    0.03489949670250128,
-    0.052335956242943946,
-    0.06975647374412522,
-    0.08715574274765789,
-    0.10452846326765299,
-    0.12186934340514768,
-    0.13917310096006547,
-    0.15643446504023067,
-    0.17364817766692997,
-    0.19080899537654425,
-    0.20791169081775857,
-    0.22495105434386492,
-    0.24192189559966745,
-    0.25881904510252113,
-    0.2756373558169994,
-    0.2923717047227367,
-    0.30901699437494723,
-    0.3255681544571563,
-    0.34202014332566816,
-    0.35836794954529955,
-    0.37460659341591196,
-    0.3907311284892735,
-    0.40673664307580054,
-    0.4226182617406996,
-    0.4383711467890774,
-    0.45399049973954664,
-    0.4694715627858904,
-    0.4848096202463365,
-    0.5000000000000001,
-    0.5150380749100542,
-    0.5299192642332047,
-    0.5446390350150266,
-    0.5591929034707462,
-    0.573576436351046,
-    0.5877852522924729,
-    0.6018150231520479,
-    0.6156614753256585,
-    0.6293203910498375,
-    0.6427876096865393,
-    0.656059028990507,
-    0.6691306063588578,
-    0.6819983600624979,
-    0.6946583704589966,
-    0.7071067811865474,
-    0.7193398003386509,
-    0.7313537016191707,
-    0.7431448254773942,
-    0.7547095802227719,
-    0.7660444431189778,
-    0.7771459614569706,
-    0.7880107536067216,
-    0.7986355100472928,
-    0.8090169943749473,
-    0.8191520442889916,
-    0.8290375725550414,
-    0.838670567945424,
-    0.8480480961564254,
-    0.8571673007021121,
-    0.8660254037844384,
-    0.8746197071393959,
-    0.882947592858927,
-    0.8910065241883678,
-    0.8987940462991671,
-    0.9063077870366497,
-    0.913545457642601,
-    0.9205048534524399,
-    0.9271838545667873,
-    0.9335804264972015,
-    0.9396926207859084,
-    0.9455185755993165,
-    0.9510565162951535,
-    0.9563047559630357,
-    0.9612616959383187,
-    0.9659258262890683,
-    0.9702957262759965,
-    0.9743700647852351,
-    0.9781476007338056,
-    0.981627183447664,
-    0.9848077530122079,
-    0.9876883405951377,
-    0.9902680687415703,
-    0.992546151641322,
-    0.9945218953682733,
-    0.9961946980917455,
-    0.9975640502598243,
-    0.9986295347545738,
-    0.9993908270190958,
-    0.9998476951563913,
-];
-pub const SIN: [f64; 360] = [
-    0.0,
-    0.01745240643728351,
-    0.03489949670250097,
-    0.05233595624294383,
-    0.0697564737441253,
-    0.08715574274765817,
-    0.10452846326765346,
-    0.12186934340514748,
-    0.13917310096006544,
-    0.15643446504023087,
-    0.17364817766693033,
-    0.1908089953765448,
-    0.20791169081775931,
-    0.224951054343865,
-    0.24192189559966773,
-    0.25881904510252074,
-    0.27563735581699916,
-    0.29237170472273677,
-    0.3090169943749474,
-    0.32556815445715664,
-    0.3420201433256687,
-    0.35836794954530027,
-    0.374606593415912,
-    0.3907311284892737,
-    0.40673664307580015,
-    0.42261826174069944,
-    0.4383711467890774,
-    0.45399049973954675,
-    0.4694715627858908,
-    0.48480962024633706,
-    0.49999999999999994,
-    0.5150380749100542,
-    0.5299192642332049,
-    0.5446390350150271,
-    0.5591929034707469,
-    0.573576436351046,
-    0.5877852522924731,
-    0.6018150231520483,
-    0.6156614753256582,
-    0.6293203910498374,
-    0.6427876096865393,
-    0.6560590289905072,
-    0.6691306063588582,
-    0.6819983600624985,
-    0.6946583704589973,
-    0.7071067811865475,
-    0.7193398003386511,
-    0.7313537016191705,
-    0.7431448254773942,
-    0.754709580222772,
-    0.766044443118978,
-    0.7771459614569708,
-    0.788010753606722,
-    0.7986355100472928,
-    0.8090169943749475,
-    0.8191520442889918,
-    0.8290375725550417,
-    0.8386705679454239,
-    0.8480480961564261,
-    0.8571673007021122,
-    0.8660254037844386,
-    0.8746197071393957,
-    0.8829475928589269,
-    0.8910065241883678,
-    0.898794046299167,
-    0.9063077870366499,
-    0.9135454576426009,
-    0.9205048534524403,
-    0.9271838545667874,
-    0.9335804264972017,
-    0.9396926207859083,
-    0.9455185755993167,
-    0.9510565162951535,
-    0.9563047559630354,
-    0.9612616959383189,
-    0.9659258262890683,
-    0.9702957262759965,
-    0.9743700647852352,
-    0.9781476007338056,
-    0.981627183447664,
-    0.984807753012208,
-    0.9876883405951378,
-    0.9902680687415703,
-    0.992546151641322,
-    0.9945218953682733,
-    0.9961946980917455,
-    0.9975640502598242,
-    0.9986295347545738,
-    0.9993908270190958,
-    0.9998476951563913,
-    1.0,
-    0.9998476951563913,
-    0.9993908270190958,
-    0.9986295347545738,
-    0.9975640502598242,
-    0.9961946980917455,
-    0.9945218953682734,
-    0.9925461516413221,
-    0.9902680687415704,
-    0.9876883405951377,
-    0.984807753012208,
-    0.981627183447664,
-    0.9781476007338057,
-    0.9743700647852352,
-    0.9702957262759965,
-    0.9659258262890683,
-    0.9612616959383189,
-    0.9563047559630355,
-    0.9510565162951536,
-    0.9455185755993168,
-    0.9396926207859084,
-    0.9335804264972017,
-    0.9271838545667874,
-    0.9205048534524404,
-    0.913545457642601,
-    0.90630778703665,
-    0.8987940462991669,
-    0.8910065241883679,
-    0.8829475928589271,
-    0.8746197071393959,
-    0.8660254037844387,
-    0.8571673007021123,
-    0.8480480961564261,
-    0.838670567945424,
-    0.8290375725550417,
-    0.819152044288992,
-    0.8090169943749475,
-    0.7986355100472927,
-    0.788010753606722,
-    0.777145961456971,
-    0.766044443118978,
-    0.7547095802227718,
-    0.7431448254773942,
-    0.7313537016191706,
-    0.7193398003386514,
-    0.7071067811865476,
-    0.6946583704589971,
-    0.6819983600624986,
-    0.6691306063588583,
-    0.6560590289905073,
-    0.6427876096865395,
-    0.6293203910498377,
-    0.6156614753256584,
-    0.6018150231520482,
-    0.5877852522924732,
-    0.5735764363510464,
-    0.5591929034707469,
-    0.5446390350150269,
-    0.5299192642332049,
-    0.5150380749100544,
-    0.49999999999999994,
-    0.48480962024633717,
-    0.4694715627858911,
-    0.45399049973954686,
-    0.4383711467890773,
-    0.4226182617406995,
-    0.40673664307580043,
-    0.39073112848927416,
-    0.37460659341591224,
-    0.3583679495453002,
-    0.3420201433256689,
-    0.325568154457157,
-    0.3090169943749475,
-    0.29237170472273705,
-    0.27563735581699966,
-    0.258819045102521,
-    0.24192189559966773,
-    0.22495105434386478,
-    0.20791169081775931,
-    0.19080899537654497,
-    0.17364817766693028,
-    0.15643446504023098,
-    0.13917310096006574,
-    0.12186934340514755,
-    0.10452846326765373,
-    0.08715574274765864,
-    0.06975647374412552,
-    0.05233595624294381,
-    0.0348994967025007,
-    0.01745240643728344,
-    1.2246467991473532e-16,
-    -0.017452406437283192,
-    -0.0348994967025009,
-    -0.052335956242943564,
-    -0.06975647374412483,
-    -0.08715574274765794,
-    -0.10452846326765305,
-    -0.12186934340514775,
-    -0.13917310096006552,
-    -0.15643446504023073,
-    -0.17364817766693047,
-    -0.19080899537654472,
-    -0.20791169081775907,
-    -0.22495105434386498,
-    -0.2419218955996675,
-    -0.25881904510252035,
-    -0.275637355816999,
-    -0.2923717047227364,
-    -0.30901699437494773,
-    -0.32556815445715676,
-    -0.34202014332566866,
-    -0.35836794954530043,
-    -0.374606593415912,
-    -0.39073112848927355,
-    -0.4067366430757998,
-    -0.4226182617406993,
-    -0.43837114678907707,
-    -0.45399049973954625,
-    -0.46947156278589086,
-    -0.48480962024633695,
-    -0.5000000000000001,
-    -0.5150380749100542,
-    -0.5299192642332048,
-    -0.5446390350150271,
-    -0.5591929034707467,
-    -0.5735764363510458,
-    -0.587785252292473,
-    -0.601815023152048,
-    -0.6156614753256578,
-    -0.6293203910498376,
-    -0.6427876096865393,
-    -0.6560590289905074,
-    -0.6691306063588582,
-    -0.6819983600624984,
-    -0.6946583704589974,
-    -0.7071067811865475,
-    -0.7193398003386509,
-    -0.7313537016191701,
-    -0.743144825477394,
-    -0.7547095802227717,
-    -0.7660444431189779,
-    -0.7771459614569711,
-    -0.7880107536067221,
-    -0.7986355100472928,
-    -0.8090169943749473,
-    -0.8191520442889916,
-    -0.8290375725550414,
-    -0.838670567945424,
-    -0.848048096156426,
-    -0.8571673007021121,
-    -0.8660254037844385,
-    -0.8746197071393959,
-    -0.882947592858927,
-    -0.8910065241883678,
-    -0.8987940462991668,
-    -0.9063077870366497,
-    -0.913545457642601,
-    -0.9205048534524403,
-    -0.9271838545667873,
-    -0.9335804264972016,
-    -0.9396926207859082,
-    -0.9455185755993168,
-    -0.9510565162951535,
-    -0.9563047559630353,
-    -0.9612616959383189,
-    -0.9659258262890683,
-    -0.9702957262759965,
-    -0.9743700647852351,
-    -0.9781476007338056,
-    -0.9816271834476639,
-    -0.984807753012208,
-    -0.9876883405951377,
-    -0.9902680687415704,
-    -0.9925461516413221,
-    -0.9945218953682734,
-    -0.9961946980917455,
-    -0.9975640502598242,
-    -0.9986295347545738,
-    -0.9993908270190957,
-    -0.9998476951563913,
-    -1.0,
-    -0.9998476951563913,
-    -0.9993908270190958,
-    -0.9986295347545738,
-    -0.9975640502598243,
-    -0.9961946980917455,
-    -0.9945218953682734,
-    -0.992546151641322,
-    -0.9902680687415704,
-    -0.9876883405951378,
-    -0.9848077530122081,
-    -0.9816271834476641,
-    -0.9781476007338058,
-    -0.9743700647852352,
-    -0.9702957262759966,
-    -0.9659258262890682,
-    -0.9612616959383188,
-    -0.9563047559630354,
-    -0.9510565162951536,
-    -0.945518575599317,
-    -0.9396926207859085,
-    -0.9335804264972021,
-    -0.9271838545667874,
-    -0.9205048534524405,
-    -0.9135454576426008,
-    -0.9063077870366498,
-    -0.898794046299167,
-    -0.891006524188368,
-    -0.8829475928589271,
-    -0.8746197071393961,
-    -0.8660254037844386,
-    -0.8571673007021123,
-    -0.8480480961564261,
-    -0.8386705679454243,
-    -0.8290375725550421,
-    -0.8191520442889918,
-    -0.8090169943749476,
-    -0.798635510047293,
-    -0.7880107536067218,
-    -0.7771459614569708,
-    -0.7660444431189781,
-    -0.7547095802227721,
-    -0.7431448254773946,
-    -0.731353701619171,
-    -0.7193398003386517,
-    -0.7071067811865477,
-    -0.6946583704589976,
-    -0.6819983600624983,
-    -0.6691306063588581,
-    -0.6560590289905074,
-    -0.6427876096865396,
-    -0.6293203910498378,
-    -0.6156614753256588,
-    -0.6018150231520483,
-    -0.5877852522924734,
-    -0.5735764363510465,
-    -0.5591929034707473,
-    -0.544639035015027,
-    -0.5299192642332058,
-    -0.5150380749100545,
-    -0.5000000000000004,
-    -0.4848096202463369,
-    -0.4694715627858908,
-    -0.45399049973954697,
-    -0.438371146789077,
-    -0.4226182617407,
-    -0.40673664307580015,
-    -0.3907311284892747,
-    -0.37460659341591235,
-    -0.35836794954530077,
-    -0.3420201433256686,
-    -0.32556815445715753,
-    -0.3090169943749476,
-    -0.29237170472273627,
-    -0.2756373558169998,
-    -0.2588190451025207,
-    -0.24192189559966787,
-    -0.22495105434386534,
-    -0.20791169081775987,
-    -0.19080899537654467,
-    -0.17364817766693127,
-    -0.15643446504023112,
-    -0.13917310096006588,
-    -0.12186934340514811,
-    -0.10452846326765342,
-    -0.08715574274765832,
-    -0.06975647374412476,
-    -0.05233595624294437,
-    -0.034899496702500823,
-    -0.01745240643728445,
-];
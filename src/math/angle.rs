@@ -0,0 +1,128 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::sync::OnceLock;
+
+/// Number of entries in the sine table, a power of two.
+pub const FINE_ANGLES: usize = 8192;
+
+/// Bits of an angle that select the table entry.
+const FINE_SHIFT: u32 = 32 - FINE_ANGLES.trailing_zeros();
+
+/// An angle in binary angle measurement: a full turn is 2^32.
+///
+/// The player's angles run clockwise from north, so `sin` is the east and
+/// `cos` the north component of a heading.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Angle(pub u32);
+
+impl Angle {
+    pub const ZERO: Angle = Angle(0);
+    pub const DEG45: Angle = Angle(1 << 29);
+    pub const DEG90: Angle = Angle(1 << 30);
+    pub const DEG180: Angle = Angle(1 << 31);
+    pub const DEG270: Angle = Angle(3 << 30);
+
+    /// The angle of `degrees`, any value wrapping around.
+    pub fn from_degrees(degrees: f64) -> Angle {
+        Angle::from_turns(degrees / 360.0)
+    }
+
+    pub fn from_radians(radians: f64) -> Angle {
+        Angle::from_turns(radians / std::f64::consts::TAU)
+    }
+
+    /// The angle of `turns` full turns, any value wrapping around.
+    fn from_turns(turns: f64) -> Angle {
+        let fraction = turns - turns.floor();
+        // `fraction` may round up to 1.0, which wraps to 0.
+        Angle((fraction * 4_294_967_296.0) as u64 as u32)
+    }
+
+    /// Degrees in `0..360`.
+    pub fn degrees(self) -> f64 {
+        self.0 as f64 * (360.0 / 4_294_967_296.0)
+    }
+
+    pub fn radians(self) -> f64 {
+        self.0 as f64 * (std::f64::consts::TAU / 4_294_967_296.0)
+    }
+
+    /// The angle as a signed turn from zero, in `-180..180` degrees. Useful
+    /// for differences of angles.
+    pub fn signed_degrees(self) -> f64 {
+        self.0 as i32 as f64 * (360.0 / 4_294_967_296.0)
+    }
+
+    /// Sine from the table, linearly interpolated between its entries.
+    pub fn sin(self) -> f64 {
+        let table = sine_table();
+        let index = (self.0 >> FINE_SHIFT) as usize;
+        let fraction = (self.0 & ((1 << FINE_SHIFT) - 1)) as f64 / (1u32 << FINE_SHIFT) as f64;
+        let (a, b) = (table[index], table[(index + 1) % FINE_ANGLES]);
+        a + (b - a) * fraction
+    }
+
+    pub fn cos(self) -> f64 {
+        (self + Angle::DEG90).sin()
+    }
+
+    /// `self` turned `fraction` of the shorter way towards `other`.
+    pub fn lerp(self, other: Angle, fraction: f64) -> Angle {
+        let turn = (other - self).0 as i32;
+        self + Angle((turn as f64 * fraction).round() as i32 as u32)
+    }
+}
+
+/// One full period of sine, `FINE_ANGLES` entries.
+fn sine_table() -> &'static [f64; FINE_ANGLES] {
+    static TABLE: OnceLock<Box<[f64; FINE_ANGLES]>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = Box::new([0.0; FINE_ANGLES]);
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = (i as f64 * std::f64::consts::TAU / FINE_ANGLES as f64).sin();
+        }
+        table
+    })
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, rhs: Angle) -> Angle {
+        Angle(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, rhs: Angle) -> Angle {
+        Angle(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle(self.0.wrapping_neg())
+    }
+}
+
+impl AddAssign for Angle {
+    fn add_assign(&mut self, rhs: Angle) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Angle {
+    fn sub_assign(&mut self, rhs: Angle) {
+        *self = *self - rhs;
+    }
+}
+
+impl fmt::Debug for Angle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Angle({}°)", self.degrees())
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Bits after the binary point.
+const FRAC_BITS: u32 = 16;
+
+/// A 16.16 fixed-point number.
+///
+/// The `checked_*` methods return `None` on overflow or division by zero;
+/// the operators panic in those cases, like integer arithmetic in debug
+/// builds.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const MIN: Fixed = Fixed(i32::MIN);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+
+    /// `n`, or `None` if it is outside of `-32768..32768`.
+    pub fn from_int(n: i32) -> Option<Fixed> {
+        n.checked_mul(1 << FRAC_BITS).map(Fixed)
+    }
+
+    /// The nearest fixed-point number to `x`, or `None` if it is out of range.
+    pub fn from_f64(x: f64) -> Option<Fixed> {
+        let raw = (x * Fixed::ONE.0 as f64).round();
+        if raw >= i32::MIN as f64 && raw <= i32::MAX as f64 {
+            Some(Fixed(raw as i32))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Fixed::ONE.0 as f64
+    }
+
+    /// The integer part, rounded towards negative infinity.
+    pub fn floor(self) -> i32 {
+        self.0 >> FRAC_BITS
+    }
+
+    pub fn checked_add(self, rhs: Fixed) -> Option<Fixed> {
+        self.0.checked_add(rhs.0).map(Fixed)
+    }
+
+    pub fn checked_sub(self, rhs: Fixed) -> Option<Fixed> {
+        self.0.checked_sub(rhs.0).map(Fixed)
+    }
+
+    pub fn checked_mul(self, rhs: Fixed) -> Option<Fixed> {
+        let product = (self.0 as i64 * rhs.0 as i64) >> FRAC_BITS;
+        i32::try_from(product).ok().map(Fixed)
+    }
+
+    pub fn checked_div(self, rhs: Fixed) -> Option<Fixed> {
+        if rhs.0 == 0 {
+            return None;
+        }
+        let quotient = ((self.0 as i64) << FRAC_BITS) / rhs.0 as i64;
+        i32::try_from(quotient).ok().map(Fixed)
+    }
+
+    pub fn checked_neg(self) -> Option<Fixed> {
+        self.0.checked_neg().map(Fixed)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Fixed) -> Fixed {
+        self.checked_add(rhs).expect("fixed-point overflow")
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Fixed) -> Fixed {
+        self.checked_sub(rhs).expect("fixed-point overflow")
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: Fixed) -> Fixed {
+        self.checked_mul(rhs).expect("fixed-point overflow")
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    fn div(self, rhs: Fixed) -> Fixed {
+        self.checked_div(rhs)
+            .expect("fixed-point overflow or division by zero")
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        self.checked_neg().expect("fixed-point overflow")
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
@@ -0,0 +1,15 @@
+//! Angles, fixed-point numbers and vectors.
+//!
+//! Angles are binary angle measurements: a `u32` where a full turn is 2^32,
+//! so they wrap around for free and any angle can be represented, not only
+//! whole degrees. Their sine and cosine come from a lookup table. `Fixed` is a
+//! 16.16 fixed-point number for code that wants exact, platform independent
+//! arithmetic. `Vec2` and `Vec3` are the `f64` vectors the world is built from.
+
+mod angle;
+mod fixed;
+mod vector;
+
+pub use angle::{Angle, FINE_ANGLES};
+pub use fixed::Fixed;
+pub use vector::{Vec2, Vec3};
@@ -0,0 +1,162 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use super::Angle;
+
+/// A point or direction on the map, seen from above.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A point or direction in the world; `z` is up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    /// The unit vector heading `angle`, clockwise from north (+y).
+    pub fn from_angle(angle: Angle) -> Vec2 {
+        Vec2::new(angle.sin(), angle.cos())
+    }
+
+    pub fn dot(self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product: positive if `other` lies
+    /// counterclockwise of `self`.
+    pub fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// `self` scaled to length 1, or `None` if it is too short to have a direction.
+    pub fn normalize(self) -> Option<Vec2> {
+        let length = self.length();
+        if length > f64::EPSILON {
+            Some(self * (1.0 / length))
+        } else {
+            None
+        }
+    }
+
+    /// `self` turned 90 degrees clockwise.
+    pub fn perp(self) -> Vec2 {
+        Vec2::new(self.y, -self.x)
+    }
+
+    pub fn extend(self, z: f64) -> Vec3 {
+        Vec3::new(self.x, self.y, z)
+    }
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    pub fn dot(self, other: Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Option<Vec3> {
+        let length = self.length();
+        if length > f64::EPSILON {
+            Some(self * (1.0 / length))
+        } else {
+            None
+        }
+    }
+
+    /// The position on the map, dropping the height.
+    pub fn xy(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
+    /// `self` at `alpha` of the way to `other`.
+    pub fn lerp(self, other: Vec3, alpha: f64) -> Vec3 {
+        self + (other - self) * alpha
+    }
+}
+
+macro_rules! vector_ops {
+    ($vec:ident { $($field:ident),+ }) => {
+        impl Add for $vec {
+            type Output = $vec;
+
+            fn add(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl Sub for $vec {
+            type Output = $vec;
+
+            fn sub(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl Mul<f64> for $vec {
+            type Output = $vec;
+
+            fn mul(self, rhs: f64) -> $vec {
+                $vec { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl Neg for $vec {
+            type Output = $vec;
+
+            fn neg(self) -> $vec {
+                $vec { $($field: -self.$field),+ }
+            }
+        }
+
+        impl AddAssign for $vec {
+            fn add_assign(&mut self, rhs: $vec) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $vec {
+            fn sub_assign(&mut self, rhs: $vec) {
+                *self = *self - rhs;
+            }
+        }
+    };
+}
+
+vector_ops!(Vec2 { x, y });
+vector_ops!(Vec3 { x, y, z });
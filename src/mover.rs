@@ -0,0 +1,505 @@
+//! Sector movers: floors and ceilings that move over ticks.
+//!
+//! Like Doom's thinkers, every sector with a `Special` gets a `Mover` that
+//! `World::update` advances once per tick, moving the sector's floor or
+//! ceiling between a low and a high height at the special's speed:
+//!
+//! - a `Door` opens its ceiling up to a little below the lowest ceiling
+//!   around it, waits and closes again, and goes back up rather than close
+//!   onto the player;
+//! - a `Lift` lowers its floor, waits and comes back up;
+//! - a `Platform` goes down and up for ever once started;
+//! - a `Crusher` brings its ceiling down to just above the floor and back up
+//!   for ever, hurting the player caught under it;
+//! - `Stairs` rise by a step each, every stair after the first a step more.
+//!
+//! Movers start when the player uses them or a `trigger` starts them. Their
+//! state saves to and loads from text, one line per mover, for save games.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::math::Vec2;
+use crate::world::{push_out, Sector, Wall, World};
+
+/// How far in front of the player movers can be used.
+pub const USE_RANGE: f64 = 40.0;
+
+/// Ticks an open door waits before closing, a little over four seconds.
+pub const DOOR_WAIT: u32 = 150;
+
+/// Ticks a lift or platform waits at each end, three seconds.
+pub const LIFT_WAIT: u32 = 105;
+
+/// How far below the lowest ceiling around it a door stops opening.
+const DOOR_LIP: i32 = 4;
+
+/// How far above the floor a crusher comes down to.
+const CRUSH_GAP: i32 = 8;
+
+/// Damage a crusher does per tick to the player caught under it.
+pub const CRUSH_DAMAGE: u32 = 2;
+
+/// How a sector moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Special {
+    Door,
+    /// A floor that lowers to `low`, waits and returns.
+    Lift {
+        low: i32,
+    },
+    /// A floor moving between `low` and its height for ever once started.
+    Platform {
+        low: i32,
+    },
+    Crusher,
+    /// A floor that rises by `step`, and the stairs it leads to by another
+    /// step each.
+    Stairs {
+        step: i32,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surface {
+    Floor,
+    Ceiling,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// Not moving, and not going to until used.
+    Idle,
+    Rising,
+    Lowering,
+    /// At one end, moving again towards the other after this many more ticks.
+    Waiting(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mover {
+    /// Index into `World::sectors`.
+    pub sector: usize,
+    pub special: Special,
+    pub state: State,
+    /// The heights the surface moves between.
+    pub low: i32,
+    pub high: i32,
+}
+
+impl Special {
+    /// The surface the special moves.
+    pub fn surface(self) -> Surface {
+        match self {
+            Special::Door | Special::Crusher => Surface::Ceiling,
+            _ => Surface::Floor,
+        }
+    }
+
+    /// Units moved per tick.
+    pub fn speed(self) -> i32 {
+        match self {
+            Special::Door => 2,
+            Special::Lift { .. } | Special::Platform { .. } => 4,
+            Special::Crusher | Special::Stairs { .. } => 1,
+        }
+    }
+}
+
+/// As written after `sector` in a level file.
+impl fmt::Display for Special {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Special::Door => write!(f, "door"),
+            Special::Lift { low } => write!(f, "lift {}", low),
+            Special::Platform { low } => write!(f, "platform {}", low),
+            Special::Crusher => write!(f, "crusher"),
+            Special::Stairs { step } => write!(f, "stairs {}", step),
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            State::Idle => write!(f, "idle"),
+            State::Rising => write!(f, "rising"),
+            State::Lowering => write!(f, "lowering"),
+            State::Waiting(ticks) => write!(f, "waiting {}", ticks),
+        }
+    }
+}
+
+impl Mover {
+    /// The mover of `sectors[s]`, idle, if it has a special.
+    pub fn new(sectors: &[Sector], walls: &[Wall], s: usize) -> Option<Mover> {
+        let sector = &sectors[s];
+        let special = sector.special?;
+        let (low, high) = match special {
+            Special::Door => {
+                let lowest = walls[sector.wall_start..sector.wall_end]
+                    .iter()
+                    .filter_map(|wall| wall.neighbour)
+                    .map(|n| sectors[n].z2)
+                    .min();
+                let top = lowest.map_or(sector.z2, |z| z - DOOR_LIP);
+                (sector.z1, top.max(sector.z1))
+            }
+            Special::Lift { low } | Special::Platform { low } => (low.min(sector.z1), sector.z1),
+            Special::Crusher => ((sector.z1 + CRUSH_GAP).min(sector.z2), sector.z2),
+            Special::Stairs { step } => (sector.z1, sector.z1 + step),
+        };
+        Some(Mover {
+            sector: s,
+            special,
+            state: State::Idle,
+            low,
+            high,
+        })
+    }
+
+    /// What the mover does once its surface reaches `height`, one end.
+    fn arrive(&self, height: i32) -> State {
+        let at_top = height == self.high;
+        match self.special {
+            Special::Door if at_top => State::Waiting(DOOR_WAIT),
+            Special::Lift { .. } if !at_top => State::Waiting(LIFT_WAIT),
+            Special::Platform { .. } => State::Waiting(LIFT_WAIT),
+            Special::Crusher if at_top => State::Lowering,
+            Special::Crusher => State::Rising,
+            _ => State::Idle,
+        }
+    }
+}
+
+/// A mover as saved, with the heights of its sector as it has moved them:
+/// `<sector> <floor> <ceiling> <state> <low> <high>`. Its special is the
+/// sector's, from the level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SavedMover {
+    pub sector: usize,
+    pub z1: i32,
+    pub z2: i32,
+    pub state: State,
+    pub low: i32,
+    pub high: i32,
+}
+
+impl fmt::Display for SavedMover {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.sector, self.z1, self.z2, self.state, self.low, self.high
+        )
+    }
+}
+
+impl FromStr for SavedMover {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, String> {
+        let mut tokens = line.split_whitespace();
+        let sector = field(&mut tokens, "a sector number")?;
+        let z1 = field(&mut tokens, "a floor height")?;
+        let z2 = field(&mut tokens, "a ceiling height")?;
+        let state = match tokens.next() {
+            Some("idle") => State::Idle,
+            Some("rising") => State::Rising,
+            Some("lowering") => State::Lowering,
+            Some("waiting") => State::Waiting(field(&mut tokens, "a number of ticks")?),
+            Some(other) => return Err(format!("unknown state `{}`", other)),
+            None => return Err("missing state".to_owned()),
+        };
+        let low = field(&mut tokens, "a low height")?;
+        let high = field(&mut tokens, "a high height")?;
+        if let Some(token) = tokens.next() {
+            return Err(format!("unexpected `{}`", token));
+        }
+        Ok(SavedMover {
+            sector,
+            z1,
+            z2,
+            state,
+            low,
+            high,
+        })
+    }
+}
+
+/// The next of `tokens`, parsed as `what`.
+fn field<'a, T: FromStr>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    what: &str,
+) -> Result<T, String> {
+    let token = tokens.next().ok_or_else(|| format!("missing {}", what))?;
+    token
+        .parse()
+        .map_err(|_| format!("expected {}, found `{}`", what, token))
+}
+
+/// A mover for every sector of a level with a special.
+pub fn movers(sectors: &[Sector], walls: &[Wall]) -> Vec<Mover> {
+    (0..sectors.len())
+        .filter_map(|s| Mover::new(sectors, walls, s))
+        .collect()
+}
+
+impl World {
+    /// The mover of sector `sector`, if it has one.
+    pub fn mover_of(&self, sector: usize) -> Option<usize> {
+        self.movers.iter().position(|mover| mover.sector == sector)
+    }
+
+    /// Start mover `m` as if the player used it. A door opens if it is
+    /// shut or closing, else closes; other movers only start when idle.
+    pub fn activate(&mut self, m: usize) {
+        let mover = self.movers[m];
+        let height = self.sectors[mover.sector].z1;
+        self.movers[m].state = match (mover.special, mover.state) {
+            (Special::Door, State::Idle | State::Lowering) => State::Rising,
+            (Special::Door, _) => State::Lowering,
+            (Special::Stairs { .. }, State::Idle) if height == mover.low => {
+                return self.build_stairs(m);
+            }
+            (Special::Stairs { .. }, state) => state,
+            (_, State::Idle) => State::Lowering,
+            (_, state) => state,
+        };
+    }
+
+    /// Start mover `m` from a trigger: as `activate`, except that a door
+    /// only ever opens.
+    pub fn start(&mut self, m: usize) {
+        let mover = self.movers[m];
+        if mover.special == Special::Door
+            && matches!(mover.state, State::Rising | State::Waiting(_))
+        {
+            return;
+        }
+        self.activate(m);
+    }
+
+    /// Raise the stairs starting at mover `m`: each stair leads on to the
+    /// first idle stairs beyond its walls, a step higher.
+    fn build_stairs(&mut self, m: usize) {
+        let step = match self.movers[m].special {
+            Special::Stairs { step } => step,
+            _ => return,
+        };
+        let mut next = Some(m);
+        let mut height = self.sectors[self.movers[m].sector].z1;
+        while let Some(m) = next {
+            height += step;
+            let mover = &mut self.movers[m];
+            mover.high = height;
+            mover.state = State::Rising;
+            let sector = &self.sectors[mover.sector];
+            next = self.walls[sector.wall_start..sector.wall_end]
+                .iter()
+                .filter_map(|wall| wall.neighbour)
+                .filter_map(|n| self.mover_of(n))
+                .find(|&n| {
+                    let mover = &self.movers[n];
+                    matches!(mover.special, Special::Stairs { .. })
+                        && mover.state == State::Idle
+                        && self.sectors[mover.sector].z1 == mover.low
+                });
+        }
+    }
+
+    /// The walls crossed looking `USE_RANGE` ahead of the player, nearest
+    /// first, with the sectors they leave: `(sector, wall)`.
+    pub(crate) fn walls_in_reach(&self) -> Vec<(usize, usize)> {
+        let origin = self.player.position.xy();
+        let end = origin + Vec2::from_angle(self.player.angle) * USE_RANGE;
+        let mut crossed = Vec::new();
+        for (s, sector) in self.sectors.iter().enumerate() {
+            for w in sector.wall_start..sector.wall_end {
+                if let Some(t) = crossing(&self.walls[w], origin, end) {
+                    crossed.push((t, s, w));
+                }
+            }
+        }
+        crossed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        crossed.into_iter().map(|(_, s, w)| (s, w)).collect()
+    }
+
+    /// The mover the player faces within `USE_RANGE`, if any: that of the
+    /// sector beyond the nearest wall crossed on the way, or of the sector
+    /// the player stands in. Portals are looked through, solid walls are not.
+    pub fn mover_in_reach(&self) -> Option<usize> {
+        for (s, w) in self.walls_in_reach() {
+            let wall = &self.walls[w];
+            let beyond = wall.neighbour.and_then(|n| self.mover_of(n));
+            if let Some(mover) = beyond.or_else(|| self.mover_of(s)) {
+                return Some(mover);
+            }
+            if wall.neighbour.is_none() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Whether the player stands in sector `s`, or close enough to its
+    /// walls to be in the way of its floor and ceiling.
+    fn player_in(&self, s: usize) -> bool {
+        let sector = &self.sectors[s];
+        let point = self.player.position.xy();
+        self.player.sector == Some(s)
+            || self.walls[sector.wall_start..sector.wall_end]
+                .iter()
+                .any(|wall| push_out(wall, point).is_some())
+    }
+
+    /// Move every mover one tick further.
+    pub(crate) fn update_movers(&mut self) {
+        for m in 0..self.movers.len() {
+            let mover = self.movers[m];
+            let sector = &self.sectors[mover.sector];
+            let surface = mover.special.surface();
+            let height = match surface {
+                Surface::Floor => sector.z1,
+                Surface::Ceiling => sector.z2,
+            };
+            let target = match mover.state {
+                State::Idle => continue,
+                State::Waiting(0) if height == mover.low => State::Rising,
+                State::Waiting(0) => State::Lowering,
+                State::Waiting(ticks) => {
+                    self.movers[m].state = State::Waiting(ticks - 1);
+                    continue;
+                }
+                moving => moving,
+            };
+            let next = if target == State::Rising {
+                (height + mover.special.speed()).min(mover.high)
+            } else {
+                (height - mover.special.speed()).max(mover.low)
+            };
+
+            // Floors rising into the player's head and ceilings lowering
+            // onto it are blocked.
+            let player = &self.player;
+            let blocked = self.player_in(mover.sector)
+                && match (surface, target) {
+                    (Surface::Ceiling, State::Lowering) => {
+                        (next as f64) < player.position.z + player.height()
+                    }
+                    (Surface::Floor, State::Rising) => {
+                        next as f64 + player.height() > sector.z2 as f64
+                    }
+                    _ => false,
+                };
+            let (state, height) = match (blocked, mover.special) {
+                (false, _) if next == mover.high || next == mover.low => (mover.arrive(next), next),
+                (false, _) => (target, next),
+                (true, Special::Door) => (State::Rising, height),
+                (true, Special::Lift { .. } | Special::Platform { .. }) => {
+                    (State::Lowering, height)
+                }
+                (true, Special::Crusher) => {
+                    self.hurt(CRUSH_DAMAGE);
+                    (target, height)
+                }
+                (true, Special::Stairs { .. }) => (target, height),
+            };
+
+            if matches!(mover.special, Special::Crusher) && target == State::Lowering {
+                self.crush_things(mover.sector, height);
+            }
+
+            let sector = &mut self.sectors[mover.sector];
+            match surface {
+                Surface::Floor => sector.z1 = height,
+                Surface::Ceiling => sector.z2 = height,
+            }
+            self.movers[m].state = state;
+        }
+    }
+
+    /// Damage the things in `sector` whose tops a crushing ceiling at
+    /// `ceiling` has come down below.
+    fn crush_things(&mut self, sector: usize, ceiling: i32) {
+        for t in 0..self.things.len() {
+            let thing = &self.things[t];
+            let top = thing.position.z + self.sprites[thing.sprite].height as f64;
+            if !thing.destroyed()
+                && top > ceiling as f64
+                && self.sector_at(thing.position.xy()) == Some(sector)
+            {
+                self.damage_thing(t, CRUSH_DAMAGE);
+            }
+        }
+    }
+
+    /// The movers' state, one `SavedMover` per line, to restore with
+    /// `load_movers` in a world of the same level.
+    pub fn save_movers(&self) -> String {
+        let mut saved = String::new();
+        for mover in &self.movers {
+            let sector = &self.sectors[mover.sector];
+            let line = SavedMover {
+                sector: mover.sector,
+                z1: sector.z1,
+                z2: sector.z2,
+                state: mover.state,
+                low: mover.low,
+                high: mover.high,
+            };
+            saved += &format!("{}\n", line);
+        }
+        saved
+    }
+
+    /// Restore the movers saved by `save_movers`, and the heights of their
+    /// sectors. Errors give the 1-based line at fault.
+    pub fn load_movers(&mut self, saved: &str) -> Result<(), String> {
+        let mut lines = Vec::new();
+        for (n, line) in saved.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("{}: {}", n + 1, message);
+            let line: SavedMover = line.parse().map_err(error)?;
+            let special = self
+                .sectors
+                .get(line.sector)
+                .and_then(|sector| sector.special)
+                .ok_or_else(|| error(format!("sector {} has no special", line.sector)))?;
+            lines.push((line, special));
+        }
+        self.movers.clear();
+        for (line, special) in lines {
+            let sector = &mut self.sectors[line.sector];
+            sector.z1 = line.z1;
+            sector.z2 = line.z2;
+            self.movers.push(Mover {
+                sector: line.sector,
+                special,
+                state: line.state,
+                low: line.low,
+                high: line.high,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// How far along the line from `origin` to `end` it leaves `wall`'s
+/// sector through the wall, from 0 to 1, or `None` if it doesn't.
+pub(crate) fn crossing(wall: &Wall, origin: Vec2, end: Vec2) -> Option<f64> {
+    let start = Vec2::new(wall.x1 as f64, wall.y1 as f64);
+    let along = Vec2::new(wall.x2 as f64, wall.y2 as f64) - start;
+    // Distances from the wall's line, positive on its inside.
+    let inside = |point: Vec2| (point - start).dot(along.perp());
+    let (a, b) = (inside(origin), inside(end));
+    if a < 0.0 || b >= 0.0 {
+        return None;
+    }
+    let t = a / (a - b);
+    let hit = origin + (end - origin) * t;
+    let s = (hit - start).dot(along) / along.dot(along);
+    (0.0..=1.0).contains(&s).then_some(t)
+}
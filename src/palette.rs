@@ -0,0 +1,177 @@
+//! 256 colour palettes.
+//!
+//! The renderer draws palette indices; a palette turns them into RGB when the
+//! frame is shown. Like Doom's `PLAYPAL` lump, a world has a set of
+//! `PALETTES`: the normal one first, then ever redder ones for damage,
+//! yellowish ones for picking things up and a green one for radiation suits.
+//! Switching between them flashes the whole screen at no cost.
+//!
+//! A palette file is in `PLAYPAL` format: 768 bytes of RGB triples per
+//! palette. If it holds fewer than `PALETTES`, the missing flashes are tinted
+//! from the first.
+
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::wad::Wad;
+
+/// Number of colours in a palette.
+pub const COLORS: usize = 256;
+
+/// Number of palettes in a full set.
+pub const PALETTES: usize = 14;
+
+/// Palettes shown after taking damage, from lightly to fully red.
+pub const DAMAGE_PALETTES: Range<usize> = 1..9;
+
+/// Palettes shown after picking something up.
+pub const BONUS_PALETTES: Range<usize> = 9..13;
+
+/// The colours of the original renderer, indices 0 to 10 of the default palette.
+const BASE_COLORS: [[u8; 3]; 11] = [
+    [255, 255, 0],
+    [160, 160, 0],
+    [0, 255, 0],
+    [0, 160, 0],
+    [0, 255, 255],
+    [0, 160, 160],
+    [160, 100, 0],
+    [110, 50, 160],
+    [0, 60, 130],
+    [90, 90, 90],
+    [140, 140, 140],
+];
+
+/// Brightest colours of the ramps filling the rest of the default palette.
+const RAMPS: [[u8; 3]; 15] = [
+    [255, 255, 255],
+    [170, 170, 200],
+    [255, 0, 0],
+    [200, 100, 70],
+    [255, 128, 0],
+    [170, 120, 70],
+    [255, 210, 160],
+    [255, 255, 0],
+    [130, 170, 70],
+    [0, 255, 0],
+    [0, 255, 255],
+    [100, 150, 255],
+    [0, 0, 255],
+    [170, 80, 210],
+    [255, 130, 190],
+];
+
+/// Shades per ramp, from full brightness down to nearly black.
+const RAMP_SHADES: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub colors: [[u8; 3]; COLORS],
+}
+
+impl Palette {
+    /// The engine's own palette: the colours levels have always used,
+    /// followed by ramps of darker and darker shades of a few hues, like
+    /// Doom's, to draw textures and shading with. The last entries are black.
+    pub fn new() -> Self {
+        let mut colors = [[0; 3]; COLORS];
+        colors[..BASE_COLORS.len()].copy_from_slice(&BASE_COLORS);
+        let shades = RAMPS.iter().flat_map(|&rgb| {
+            (0..RAMP_SHADES)
+                .map(move |k| rgb.map(|c| (c as usize * (RAMP_SHADES - k) / RAMP_SHADES) as u8))
+        });
+        for (color, shade) in colors[BASE_COLORS.len()..].iter_mut().zip(shades) {
+            *color = shade;
+        }
+        Palette { colors }
+    }
+
+    /// Index of the colour closest to `rgb`.
+    pub fn nearest(&self, rgb: [u8; 3]) -> u8 {
+        let distance = |c: &[u8; 3]| {
+            (0..3)
+                .map(|i| (c[i] as i32 - rgb[i] as i32).pow(2))
+                .sum::<i32>()
+        };
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| distance(c))
+            .map_or(0, |(i, _)| i as u8)
+    }
+
+    /// Every colour moved `amount` of the way towards `tint`.
+    fn tinted(&self, tint: [u8; 3], amount: f64) -> Palette {
+        let mut colors = self.colors;
+        for color in colors.iter_mut() {
+            for (c, t) in color.iter_mut().zip(tint) {
+                *c = (*c as f64 + (t as f64 - *c as f64) * amount).round() as u8;
+            }
+        }
+        Palette { colors }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new()
+    }
+}
+
+/// A full set of palettes based on `base`, with tinted flashes.
+pub fn flashes(base: Palette) -> Vec<Palette> {
+    let mut palettes = vec![base.clone()];
+    for n in 1..=DAMAGE_PALETTES.len() {
+        palettes.push(base.tinted([255, 0, 0], n as f64 / 9.0));
+    }
+    for n in 1..=BONUS_PALETTES.len() {
+        palettes.push(base.tinted([215, 186, 69], n as f64 / 8.0));
+    }
+    // A faint green last palette fills the set out to `PALETTES`, matching
+    // the radiation suit palette that ends `PLAYPAL`.
+    palettes.push(base.tinted([0, 255, 0], 0.125));
+    palettes
+}
+
+/// Decode `PLAYPAL` data into a full set of palettes.
+pub fn parse(bytes: &[u8]) -> Result<Vec<Palette>, String> {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(COLORS * 3) {
+        return Err(format!(
+            "palette data is {} bytes, not a multiple of {}",
+            bytes.len(),
+            COLORS * 3
+        ));
+    }
+    let mut palettes: Vec<Palette> = bytes
+        .chunks_exact(COLORS * 3)
+        .take(PALETTES)
+        .map(|data| {
+            let mut colors = [[0; 3]; COLORS];
+            for (color, rgb) in colors.iter_mut().zip(data.chunks_exact(3)) {
+                *color = [rgb[0], rgb[1], rgb[2]];
+            }
+            Palette { colors }
+        })
+        .collect();
+    if palettes.len() < PALETTES {
+        let full = flashes(palettes[0].clone());
+        palettes.extend_from_slice(&full[palettes.len()..]);
+    }
+    Ok(palettes)
+}
+
+/// Read the palettes at `path`: a `PLAYPAL` file, or a WAD to take the
+/// `PLAYPAL` lump of.
+pub fn load(path: &Path) -> Result<Vec<Palette>, String> {
+    let is_wad = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wad"));
+    if is_wad {
+        let wad = Wad::load(path).map_err(|err| err.to_string())?;
+        let lump = wad.lump("PLAYPAL").ok_or("WAD has no PLAYPAL lump")?;
+        return parse(lump);
+    }
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    parse(&bytes)
+}
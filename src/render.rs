@@ -0,0 +1,656 @@
+use std::ops::Range;
+
+use crate::bsp::Seg;
+use crate::colormap::{self, FULL_BRIGHT};
+use crate::math::{Angle, Vec3};
+use crate::mover::Special;
+use crate::texture::Texture;
+use crate::world::{Player, Sector, Wall, World};
+use crate::{HEIGHT, SKY, WIDTH};
+
+const SH2: i32 = HEIGHT as i32 / 2;
+const SW2: i32 = WIDTH as i32 / 2;
+
+/// Walls closer than this to the camera are clipped.
+const NEAR: f64 = 1.0;
+
+/// Where the world is seen from: the position of the eye, an angle and the
+/// vertical shear `look`, like the player's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+    pub angle: Angle,
+    pub look: f64,
+}
+
+impl Player {
+    /// The camera at the player's eyes.
+    pub fn camera(&self) -> Camera {
+        Camera {
+            position: self.position + Vec3::new(0.0, 0.0, self.view_height),
+            angle: self.angle,
+            look: self.look,
+        }
+    }
+}
+
+/// A wall transformed to view space, clipped against the near plane and
+/// projected to the screen columns `x1..x2`. `s1` and `s2` locate the clipped
+/// end points along the original wall, from 0 at its start to 1 at its end.
+struct ViewWall {
+    x1: i32,
+    x2: i32,
+    depth1: f64,
+    depth2: f64,
+    s1: f64,
+    s2: f64,
+}
+
+/// The part of a wall seen in one screen column.
+#[derive(Clone, Copy)]
+struct WallColumn {
+    depth: f64,
+    /// Horizontal texture coordinate, before the wall's mapping is applied.
+    distance_along: f64,
+    /// Height the vertical texture coordinate is measured from.
+    top: i32,
+    /// Colormap the column is shaded with.
+    light: usize,
+}
+
+/// How far away the surface drawn at every pixel is, and the wall that
+/// closed every column, as view-space depth. Filled in while rendering so
+/// that sprites and effects drawn afterwards can be tested for occlusion.
+/// Nothing drawn, as where the sky shows, is infinitely far.
+pub struct DepthBuffer {
+    /// Depth per pixel, `WIDTH * HEIGHT` row by row.
+    pub pixels: Vec<f64>,
+    /// Depth of the wall closing each column, `WIDTH` of them.
+    pub columns: Vec<f64>,
+}
+
+impl DepthBuffer {
+    pub fn new() -> Self {
+        DepthBuffer {
+            pixels: vec![f64::INFINITY; (WIDTH * HEIGHT) as usize],
+            columns: vec![f64::INFINITY; WIDTH as usize],
+        }
+    }
+
+    /// Forget everything drawn.
+    pub fn clear(&mut self) {
+        self.pixels.fill(f64::INFINITY);
+        self.columns.fill(f64::INFINITY);
+    }
+
+    /// Depth of the surface at pixel `(x, y)`.
+    pub fn at(&self, x: i32, y: i32) -> f64 {
+        self.pixels[(y * WIDTH as i32 + x) as usize]
+    }
+
+    /// Depth of the solid wall closing column `x`; nothing beyond shows in it.
+    pub fn column(&self, x: i32) -> f64 {
+        self.columns[x as usize]
+    }
+
+    /// Whether something at `depth` in pixel `(x, y)` would be in front of
+    /// what is drawn there.
+    pub fn visible(&self, x: i32, y: i32, depth: f64) -> bool {
+        depth < self.at(x, y)
+    }
+}
+
+impl Default for DepthBuffer {
+    fn default() -> Self {
+        DepthBuffer::new()
+    }
+}
+
+/// The palette indices being drawn, the depth buffer alongside them and the
+/// walls that made it on screen.
+struct Frame<'f> {
+    indexed: &'f mut [u8],
+    depth: &'f mut DepthBuffer,
+    /// Per wall, whether any of it has been drawn.
+    seen: &'f mut [bool],
+}
+
+/// A sprite standing in the world, always facing the camera, one texel per
+/// unit: a thing's or a puff's.
+struct Billboard<'a> {
+    /// Position of the bottom centre of the sprite.
+    position: Vec3,
+    sprite: &'a Texture,
+    /// Light level the sprite is shaded with.
+    light: u8,
+}
+
+/// The screen columns closed by solid walls, as sorted, disjoint and
+/// non-adjacent ranges. Nothing further away shows in them.
+struct SolidColumns {
+    ranges: Vec<Range<i32>>,
+}
+
+impl SolidColumns {
+    fn new() -> Self {
+        SolidColumns { ranges: Vec::new() }
+    }
+
+    /// Whether every column is closed.
+    fn full(&self) -> bool {
+        self.ranges.len() == 1 && self.ranges[0] == (0..WIDTH as i32)
+    }
+
+    /// The parts of `columns` still open.
+    fn open(&self, columns: Range<i32>) -> Vec<Range<i32>> {
+        let mut open = Vec::new();
+        let mut x = columns.start;
+        for range in &self.ranges {
+            if range.start >= columns.end {
+                break;
+            }
+            if range.start > x {
+                open.push(x..range.start);
+            }
+            x = x.max(range.end);
+        }
+        if x < columns.end {
+            open.push(x..columns.end);
+        }
+        open
+    }
+
+    /// Close `columns`.
+    fn close(&mut self, columns: Range<i32>) {
+        if columns.is_empty() {
+            return;
+        }
+        // Ranges touching the new one merge with it.
+        let first = self.ranges.partition_point(|r| r.end < columns.start);
+        let last = self.ranges.partition_point(|r| r.start <= columns.end);
+        let merged = match self.ranges[first..last] {
+            [] => columns,
+            ref touching => {
+                touching[0].start.min(columns.start)
+                    ..touching[touching.len() - 1].end.max(columns.end)
+            }
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+}
+
+/// A world seen from a camera.
+struct View<'a> {
+    world: &'a World,
+    camera: Camera,
+    cos: f64,
+    sin: f64,
+}
+
+impl Camera {
+    /// The camera `alpha` of the way from `self` to `next`, turning the short way round.
+    pub fn lerp(&self, next: &Camera, alpha: f64) -> Camera {
+        Camera {
+            position: self.position.lerp(next.position, alpha),
+            angle: self.angle.lerp(next.angle, alpha),
+            look: lerp_f(self.look, next.look, alpha),
+        }
+    }
+}
+
+impl World {
+    /// Draw the `World` as the player sees it to the frame buffer, `alpha`
+    /// of the way from the previous tick to the last one.
+    ///
+    /// The weapon in hand is drawn over the view. The walls drawn are
+    /// marked seen on the automap, which is drawn over both while it is
+    /// active.
+    ///
+    /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
+    pub fn draw(&mut self, frame: &mut [u8], alpha: f64) {
+        let mut indexed = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut seen = std::mem::take(&mut self.automap.seen);
+        self.render_view(
+            &self.camera(alpha),
+            &mut indexed,
+            &mut DepthBuffer::new(),
+            &mut seen,
+        );
+        self.automap.seen = seen;
+        self.draw_weapon(&mut indexed);
+        if self.automap.active {
+            self.draw_automap(&mut indexed, alpha);
+        }
+        self.present(&indexed, frame);
+    }
+
+    /// Draw the `World` seen from `camera` to `frame`, `WIDTH * HEIGHT` RGBA
+    /// pixels, in the current palette.
+    pub fn render(&self, camera: &Camera, frame: &mut [u8]) {
+        let mut indexed = vec![0; (WIDTH * HEIGHT) as usize];
+        self.render_indexed(camera, &mut indexed);
+        self.present(&indexed, frame);
+    }
+
+    /// Draw the `World` seen from `camera` to `frame`, `WIDTH * HEIGHT`
+    /// palette indices.
+    ///
+    /// The subsectors of the BSP are visited front to back, and every seg
+    /// narrows a per-column window of rows that are still free: solid walls
+    /// close their columns, portals keep the part between the neighbour's
+    /// floor and ceiling open for what lies beyond. Closed columns go on a
+    /// list that later segs are clipped against, and drawing stops once the
+    /// whole screen is closed. Each pixel is written once, and its depth
+    /// recorded. Things and puffs are drawn over the result, furthest first,
+    /// only where they are nearer than what is there.
+    pub fn render_indexed(&self, camera: &Camera, frame: &mut [u8]) {
+        self.render_depth(camera, frame, &mut DepthBuffer::new());
+    }
+
+    /// Like `render_indexed`, also filling `depth` with how far away every
+    /// pixel drawn is.
+    pub fn render_depth(&self, camera: &Camera, frame: &mut [u8], depth: &mut DepthBuffer) {
+        let mut seen = vec![false; self.walls.len()];
+        self.render_view(camera, frame, depth, &mut seen);
+    }
+
+    /// Render into `frame` and `depth`, setting the flags in `seen` of the
+    /// walls drawn.
+    fn render_view(
+        &self,
+        camera: &Camera,
+        frame: &mut [u8],
+        depth: &mut DepthBuffer,
+        seen: &mut [bool],
+    ) {
+        depth.clear();
+        View {
+            world: self,
+            camera: *camera,
+            cos: camera.angle.cos(),
+            sin: camera.angle.sin(),
+        }
+        .draw(&mut Frame {
+            indexed: frame,
+            depth,
+            seen,
+        });
+    }
+
+    /// Draw the depth buffer of the player's view instead of the view
+    /// itself, for debugging: white up close, darker with distance the way
+    /// light fades, and the sky where nothing was drawn.
+    pub fn draw_depth(&self, frame: &mut [u8], alpha: f64) {
+        let mut indexed = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut depth = DepthBuffer::new();
+        self.render_depth(&self.camera(alpha), &mut indexed, &mut depth);
+        let white = self.palettes[0].nearest([255, 255, 255]);
+        for (color, &depth) in indexed.iter_mut().zip(&depth.pixels) {
+            *color = if depth == f64::INFINITY {
+                SKY
+            } else {
+                self.colormap
+                    .shade(white, colormap::level(FULL_BRIGHT, depth))
+            };
+        }
+        self.present(&indexed, frame);
+    }
+
+    /// Convert the palette indices of `indexed` to RGBA pixels in `frame`
+    /// through the current palette, which may be flashing.
+    pub fn present(&self, indexed: &[u8], frame: &mut [u8]) {
+        let palette = self.palette();
+        for (pixel, &color) in frame.chunks_exact_mut(4).zip(indexed) {
+            let [r, g, b] = palette.colors[color as usize];
+            pixel.copy_from_slice(&[r, g, b, 0xff]);
+        }
+    }
+
+    /// Render `camera`'s view into a new RGBA buffer.
+    pub fn render_frame(&self, camera: &Camera) -> Vec<u8> {
+        let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+        self.render(camera, &mut frame);
+        frame
+    }
+}
+
+impl<'a> View<'a> {
+    fn draw(&self, frame: &mut Frame) {
+        let world = self.world;
+        if world.sector_at(self.camera.position.xy()).is_none() {
+            frame.indexed.fill(SKY);
+            return;
+        }
+
+        // First free row and one past the last free row, per column.
+        let mut top = vec![0; WIDTH as usize];
+        let mut bottom = vec![HEIGHT as i32; WIDTH as usize];
+        let mut solid = SolidColumns::new();
+
+        for subsector in world.bsp.front_to_back(self.camera.position.xy()) {
+            if solid.full() {
+                break;
+            }
+            let subsector = &world.bsp.subsectors[subsector];
+            let sector = &world.sectors[subsector.sector];
+            for seg in &world.bsp.segs[subsector.segs.clone()] {
+                let view = match self.project(seg) {
+                    Some(view) => view,
+                    None => continue,
+                };
+                let wall = &world.walls[seg.wall];
+                let ceiling = self.rows(&view, sector.z2);
+                let floor = self.rows(&view, sector.z1);
+                let wall_length =
+                    (((wall.x2 - wall.x1).pow(2) + (wall.y2 - wall.y1).pow(2)) as f64).sqrt();
+                let neighbour = wall.neighbour.map(|n| {
+                    let n = &world.sectors[n];
+                    (self.rows(&view, n.z2), self.rows(&view, n.z1), n)
+                });
+
+                let columns = view.x1.max(0)..view.x2.min(WIDTH as i32);
+                for open in solid.open(columns) {
+                    for x in open.clone() {
+                        let column = x as usize;
+                        let (y_top, y_bottom) = (top[column], bottom[column]);
+                        if y_top >= y_bottom {
+                            continue;
+                        }
+                        frame.seen[seg.wall] = true;
+                        let t = ((x - view.x1) as f64 + 0.5) / (view.x2 - view.x1) as f64;
+                        let yc = lerp(ceiling, t).clamp(y_top, y_bottom);
+                        let yf = lerp(floor, t).clamp(yc, y_bottom);
+
+                        self.draw_flat(frame, x, y_top, yc, sector, true);
+                        self.draw_flat(frame, x, yf, y_bottom, sector, false);
+
+                        // Screen rows are linear in view-space x over depth, so the
+                        // position along the wall is interpolated divided by depth.
+                        let inv_depth = lerp_f(1.0 / view.depth1, 1.0 / view.depth2, t);
+                        let depth = 1.0 / inv_depth;
+                        let wall_column = WallColumn {
+                            depth,
+                            distance_along: lerp_f(view.s1 / view.depth1, view.s2 / view.depth2, t)
+                                / inv_depth
+                                * wall_length,
+                            top: sector.z2,
+                            light: colormap::level(sector.light, depth),
+                        };
+
+                        match neighbour {
+                            Some((n_ceiling, n_floor, n)) => {
+                                let nc = lerp(n_ceiling, t).clamp(yc, yf);
+                                let nf = lerp(n_floor, t).clamp(nc, yf);
+                                // The face of a door hangs from its ceiling
+                                // and moves with it.
+                                let upper = WallColumn {
+                                    top: if n.special == Some(Special::Door) {
+                                        n.z2
+                                    } else {
+                                        sector.z2
+                                    },
+                                    ..wall_column
+                                };
+                                self.draw_wall(frame, x, yc, nc, wall, &upper);
+                                self.draw_wall(frame, x, nf, yf, wall, &wall_column);
+                                top[column] = nc;
+                                bottom[column] = nf;
+                            }
+                            None => {
+                                self.draw_wall(frame, x, yc, yf, wall, &wall_column);
+                                top[column] = y_bottom;
+                            }
+                        }
+                        if top[column] >= bottom[column] {
+                            frame.depth.columns[column] = depth;
+                        }
+                    }
+
+                    // Columns the seg closed, whether solid or a portal shut
+                    // by the heights beyond, hide everything further away.
+                    let mut x = open.start;
+                    while x < open.end {
+                        let closed = |x: i32| top[x as usize] >= bottom[x as usize];
+                        if closed(x) {
+                            let start = x;
+                            while x < open.end && closed(x) {
+                                x += 1;
+                            }
+                            solid.close(start..x);
+                        } else {
+                            x += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Whatever is still open looks out of the level.
+        for x in 0..WIDTH as i32 {
+            frame.vline(x, top[x as usize], bottom[x as usize], SKY, f64::INFINITY);
+        }
+
+        self.draw_things(frame);
+    }
+
+    /// Draw every thing and puff in front of the camera, furthest first so
+    /// nearer ones cover them. Things are shaded by their sector's light,
+    /// puffs are full bright; neither is seen outside of the level.
+    fn draw_things(&self, frame: &mut Frame) {
+        let world = self.world;
+        let eye = self.camera.position;
+        let things = world.things.iter().filter_map(|thing| {
+            if thing.destroyed() {
+                return None;
+            }
+            let sector = world.sector_at(thing.position.xy())?;
+            Some(Billboard {
+                position: thing.position,
+                sprite: &world.sprites[thing.sprite],
+                light: world.sectors[sector].light,
+            })
+        });
+        let puffs = world.arsenal.puffs.iter().filter_map(|puff| {
+            world.sector_at(puff.position.xy())?;
+            let sprite = world.arsenal.puff_frame(puff.age);
+            Some(Billboard {
+                position: puff.position - Vec3::new(0.0, 0.0, sprite.height as f64 / 2.0),
+                sprite,
+                light: FULL_BRIGHT,
+            })
+        });
+        let mut visible: Vec<(f64, f64, Billboard)> = things
+            .chain(puffs)
+            .filter_map(|billboard| {
+                let offset = billboard.position.xy() - eye.xy();
+                let x = offset.x * self.cos - offset.y * self.sin;
+                let depth = offset.y * self.cos + offset.x * self.sin;
+                (depth >= NEAR).then_some((depth, x, billboard))
+            })
+            .collect();
+        visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (depth, x, billboard) in visible {
+            self.draw_billboard(frame, &billboard, x, depth);
+        }
+    }
+
+    /// Draw `billboard`'s sprite facing the camera, centred on the
+    /// view-space position `(x, depth)`. Only pixels in front of what the
+    /// depth buffer holds are drawn.
+    fn draw_billboard(&self, frame: &mut Frame, billboard: &Billboard, x: f64, depth: f64) {
+        let sprite = billboard.sprite;
+        let scale = 200.0 / depth;
+        let left = SW2 as f64 + (x - sprite.width as f64 / 2.0) * scale;
+        let top_z = billboard.position.z + sprite.height as f64;
+        let top =
+            SH2 as f64 - (top_z - self.camera.position.z + self.camera.look * depth / 32.0) * scale;
+        let right = left + sprite.width as f64 * scale;
+        let bottom = top + sprite.height as f64 * scale;
+        let light = colormap::level(billboard.light, depth);
+
+        // Pixels whose centres fall on the sprite.
+        let first = |edge: f64| (edge - 0.5).ceil() as i32;
+        for x in first(left).max(0)..first(right).min(WIDTH as i32) {
+            if frame.depth.column(x) <= depth {
+                continue;
+            }
+            let u = ((x as f64 + 0.5 - left) / scale).floor() as i32;
+            for y in first(top).max(0)..first(bottom).min(HEIGHT as i32) {
+                if !frame.depth.visible(x, y, depth) {
+                    continue;
+                }
+                let v = ((y as f64 + 0.5 - top) / scale).floor() as i32;
+                if let Some(color) = sprite.texel(u, v) {
+                    frame.plot(x, y, self.world.colormap.shade(color, light), depth);
+                }
+            }
+        }
+    }
+
+    /// Transform `seg` to view space, clip it against the near plane and
+    /// project its end points. `None` if the seg is behind the camera or
+    /// faces away from it.
+    fn project(&self, seg: &Seg) -> Option<ViewWall> {
+        let (cs, sn) = (self.cos, self.sin);
+
+        let eye = self.camera.position;
+        let x1 = seg.start.x - eye.x;
+        let y1 = seg.start.y - eye.y;
+        let x2 = seg.end.x - eye.x;
+        let y2 = seg.end.y - eye.y;
+
+        let mut wx0 = x1 * cs - y1 * sn;
+        let mut wx1 = x2 * cs - y2 * sn;
+        let mut wy0 = y1 * cs + x1 * sn;
+        let mut wy1 = y2 * cs + x2 * sn;
+        let (mut s0, mut s1) = (0.0, 1.0);
+
+        if wy0 < NEAR && wy1 < NEAR {
+            return None;
+        }
+        if wy0 < NEAR {
+            s0 = clip_behind_player(&mut wx0, &mut wy0, wx1, wy1);
+        }
+        if wy1 < NEAR {
+            s1 = 1.0 - clip_behind_player(&mut wx1, &mut wy1, wx0, wy0);
+        }
+
+        let sx0 = ((wx0 * 200.0 / wy0) as i32).saturating_add(SW2);
+        let sx1 = ((wx1 * 200.0 / wy1) as i32).saturating_add(SW2);
+        if sx0 >= sx1 {
+            return None;
+        }
+        let along = |s: f64| lerp_f(seg.s1, seg.s2, s);
+        Some(ViewWall {
+            x1: sx0,
+            x2: sx1,
+            depth1: wy0,
+            depth2: wy1,
+            s1: along(s0),
+            s2: along(s1),
+        })
+    }
+
+    /// Fill the rows `y1..y2` of column `x` with `wall`, textured if it has a mapping.
+    fn draw_wall(
+        &self,
+        frame: &mut Frame,
+        x: i32,
+        y1: i32,
+        y2: i32,
+        wall: &Wall,
+        column: &WallColumn,
+    ) {
+        let colormap = &self.world.colormap;
+        let mapping = match wall.texture {
+            Some(mapping) => mapping,
+            None => {
+                let color = colormap.shade(wall.color, column.light);
+                return frame.vline(x, y1, y2, color, column.depth);
+            }
+        };
+        let texture = &self.world.textures[mapping.texture];
+        let u = (mapping.u_offset + column.distance_along * mapping.u_scale).floor() as i32;
+        // Height seen at the centre of row `y`; it falls linearly down the column.
+        let eye = self.camera.position.z - self.camera.look * column.depth / 32.0;
+        let z_at = |y: i32| eye + (SH2 as f64 - y as f64 - 0.5) * column.depth / 200.0;
+        for y in y1..y2 {
+            let v = mapping.v_offset + (column.top as f64 - z_at(y)) * mapping.v_scale;
+            let color = texture.sample(u, v.floor() as i32);
+            frame.plot(x, y, colormap.shade(color, column.light), column.depth);
+        }
+    }
+
+    /// Fill the rows `y1..y2` of column `x` with the ceiling or the floor of
+    /// `sector`, darkening with the distance of each row. The sky is not shaded.
+    fn draw_flat(
+        &self,
+        frame: &mut Frame,
+        x: i32,
+        y1: i32,
+        y2: i32,
+        sector: &Sector,
+        ceiling: bool,
+    ) {
+        let (color, z) = if ceiling {
+            (sector.ceiling_color, sector.z2)
+        } else {
+            (sector.floor_color, sector.z1)
+        };
+        if color == SKY {
+            return frame.vline(x, y1, y2, color, f64::INFINITY);
+        }
+        let height = z as f64 - self.camera.position.z;
+        let horizon = SH2 as f64 - self.camera.look * 200.0 / 32.0;
+        for y in y1..y2 {
+            // The inverse of the projection of `rows` for a known height.
+            let depth = height * 200.0 / (horizon - y as f64 - 0.5);
+            let depth = if depth > 0.0 { depth } else { f64::INFINITY };
+            let level = colormap::level(sector.light, depth);
+            frame.plot(x, y, self.world.colormap.shade(color, level), depth);
+        }
+    }
+
+    /// Screen rows of height `z` at both ends of `view`.
+    fn rows(&self, view: &ViewWall, z: i32) -> (i32, i32) {
+        let row = |depth: f64| {
+            let wz = z as f64 - self.camera.position.z + self.camera.look * depth / 32.0;
+            SH2.saturating_sub((wz * 200.0 / depth) as i32)
+        };
+        (row(view.depth1), row(view.depth2))
+    }
+}
+
+fn lerp((a, b): (i32, i32), t: f64) -> i32 {
+    ((b.saturating_sub(a)) as f64 * t) as i32 + a
+}
+
+fn lerp_f(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Move the point `(x1, y1)` along the wall towards `(x2, y2)` until it
+/// lies on the near plane. Returns the fraction of the wall cut off.
+fn clip_behind_player(x1: &mut f64, y1: &mut f64, x2: f64, y2: f64) -> f64 {
+    let d = y2 - *y1;
+    let s = if d == 0.0 { 0.0 } else { (NEAR - *y1) / d };
+    *x1 += s * (x2 - *x1);
+    *y1 = NEAR;
+    s
+}
+
+impl<'f> Frame<'f> {
+    /// Draw `color` at pixel `(x, y)`, at `depth`.
+    fn plot(&mut self, x: i32, y: i32, color: u8, depth: f64) {
+        let i = (y * WIDTH as i32 + x) as usize;
+        self.indexed[i] = color;
+        self.depth.pixels[i] = depth;
+    }
+
+    /// Fill the rows `y1..y2` of column `x`.
+    fn vline(&mut self, x: i32, y1: i32, y2: i32, color: u8, depth: f64) {
+        for y in y1..y2 {
+            self.plot(x, y, color, depth);
+        }
+    }
+}
@@ -0,0 +1,1010 @@
+//! Level scripts: a small language for making levels react to the player.
+//!
+//! A level file holds any number of handlers, each run when its event
+//! happens:
+//!
+//! ```text
+//! on start             when the level begins
+//! on trigger <n>       when a wall trigger with the effect `script <n>` fires
+//! on enter <sector>    when the player walks into the sector
+//! ```
+//!
+//! The lines of a handler, up to the `end` closing it, are statements, one
+//! per line:
+//!
+//! ```text
+//! let <name> = <expression>
+//! if <expression> ... [else ...] end
+//! while <expression> ... end
+//! <function>(<arguments>)
+//! ```
+//!
+//! Values are integers and strings. `true` and `false` are 1 and 0; zero
+//! and the empty string are false, everything else true. Expressions have
+//! `+ - * / %`, the comparisons `== != < <= > >=`, `and`, `or`, `not` and
+//! parentheses, and `+` with a string on either side joins text. Variables
+//! belong to the level: they keep their values from one event to the next.
+//!
+//! Scripts are sandboxed. They reach the world only through these functions,
+//! and a handler is stopped after `MAX_STEPS` steps:
+//!
+//! ```text
+//! floor(s) ceiling(s) light(s)          the heights and light of sector s
+//! set_floor(s, z) set_ceiling(s, z)     move the floor or ceiling of sector s
+//! set_light(s, level)                   set the light of sector s
+//! start(tag)                            start the movers of the sectors tagged <tag>
+//! spawn(sprite, x, y, z, angle)         add a thing, giving its number
+//! message(text)                         show text to the player
+//! player_x() player_y() player_z()      where the player's feet are
+//! player_angle() player_health()
+//! player_sector()                       the player's sector, -1 outside of the level
+//! hurt(damage)                          damage the player, by at most their full health
+//! exit()                                end the level
+//! ```
+//!
+//! Mistakes are reported with the line and column in the level file: those
+//! found when parsing make the level fail to load, those found when running
+//! stop the handler and go to `World::script_errors`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+
+use crate::math::{Angle, Vec3};
+use crate::world::{Thing, World, MAX_HEALTH};
+
+/// Most statements and loop tests a handler runs before it is stopped.
+pub const MAX_STEPS: u32 = 100_000;
+
+/// Longest string a script can build, in characters.
+const MAX_STRING: usize = 1024;
+
+/// Most things a level can have for scripts to spawn another.
+const MAX_THINGS: usize = 1024;
+
+/// The functions scripts can call, with their numbers of arguments.
+const FUNCTIONS: [(&str, usize); 17] = [
+    ("floor", 1),
+    ("ceiling", 1),
+    ("light", 1),
+    ("set_floor", 2),
+    ("set_ceiling", 2),
+    ("set_light", 2),
+    ("start", 1),
+    ("spawn", 5),
+    ("message", 1),
+    ("player_x", 0),
+    ("player_y", 0),
+    ("player_z", 0),
+    ("player_angle", 0),
+    ("player_health", 0),
+    ("player_sector", 0),
+    ("hurt", 1),
+    ("exit", 0),
+];
+
+/// Words that can't name variables.
+const KEYWORDS: [&str; 11] = [
+    "on", "let", "if", "else", "while", "end", "and", "or", "not", "true", "false",
+];
+
+/// Symbols, those of two characters first.
+const SYMBOLS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "<", ">", "=", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+/// A mistake in a script, at a 1-based line and column of the level file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Str(String),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Int(n) => *n != 0,
+            Value::Str(text) => !text.is_empty(),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Int(b as i64)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Str(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// What a handler runs on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Start,
+    /// A trigger with the effect `script <n>` fired.
+    Trigger(u32),
+    /// The player walked into the sector.
+    Enter(usize),
+}
+
+/// As written after `on` in a level file.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Start => write!(f, "start"),
+            Event::Trigger(n) => write!(f, "trigger {}", n),
+            Event::Enter(s) => write!(f, "enter {}", s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Name(String),
+    Symbol(&'static str),
+}
+
+/// The tokens of one line, with the columns they start at.
+struct Line {
+    number: usize,
+    tokens: Vec<(Token, usize)>,
+    /// The column just past the last token, for "missing" errors.
+    end: usize,
+}
+
+impl Line {
+    fn lex(number: usize, text: &str) -> Result<Line, Error> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        let mut end = 1;
+        while i < chars.len() {
+            let c = chars[i];
+            let column = i + 1;
+            let error = |message: String| Error {
+                line: number,
+                column,
+                message,
+            };
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if c == '#' {
+                break;
+            }
+            let token = if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && chars[i].is_alphanumeric() {
+                    i += 1;
+                }
+                let digits: String = chars[start..i].iter().collect();
+                let n = digits
+                    .parse()
+                    .map_err(|_| error(format!("bad number `{}`", digits)))?;
+                Token::Int(n)
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Name(chars[start..i].iter().collect())
+            } else if c == '"' {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error("string has no closing `\"`".to_owned())),
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.get(i + 1) {
+                                Some('n') => text.push('\n'),
+                                Some(&c @ ('"' | '\\')) => text.push(c),
+                                _ => {
+                                    return Err(Error {
+                                        line: number,
+                                        column: i + 1,
+                                        message: "unknown escape, expected `\\n`, `\\\"` or `\\\\`"
+                                            .to_owned(),
+                                    })
+                                }
+                            }
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                Token::Str(text)
+            } else {
+                let rest: String = chars[i..].iter().take(2).collect();
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(*s))
+                    .ok_or_else(|| error(format!("unexpected `{}`", c)))?;
+                i += symbol.len();
+                Token::Symbol(symbol)
+            };
+            tokens.push((token, column));
+            end = i + 1;
+        }
+        Ok(Line {
+            number,
+            tokens,
+            end,
+        })
+    }
+
+    /// The line's first word, if it starts with one.
+    fn keyword(&self) -> Option<&str> {
+        match self.tokens.first() {
+            Some((Token::Name(name), _)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> Error {
+        Error {
+            line: self.number,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Unary {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Binary {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl Binary {
+    fn symbol(self) -> &'static str {
+        match self {
+            Binary::Add => "+",
+            Binary::Sub => "-",
+            Binary::Mul => "*",
+            Binary::Div => "/",
+            Binary::Rem => "%",
+            Binary::Eq => "==",
+            Binary::Ne => "!=",
+            Binary::Lt => "<",
+            Binary::Le => "<=",
+            Binary::Gt => ">",
+            Binary::Ge => ">=",
+            Binary::And => "and",
+            Binary::Or => "or",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Int(i64),
+    Str(String),
+    Var(String),
+    Call(&'static str, Vec<Expr>),
+    Unary(Unary, Box<Expr>),
+    Binary(Binary, Box<Expr>, Box<Expr>),
+}
+
+/// An expression and where it starts.
+#[derive(Clone, Debug, PartialEq)]
+struct Expr {
+    kind: Kind,
+    line: usize,
+    column: usize,
+}
+
+impl Expr {
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Stmt {
+    Let {
+        name: String,
+        value: Expr,
+    },
+    Call(Expr),
+    If {
+        condition: Expr,
+        then: Vec<Stmt>,
+        otherwise: Vec<Stmt>,
+    },
+    While {
+        condition: Expr,
+        body: Vec<Stmt>,
+    },
+}
+
+/// Reads the expressions of one line.
+struct Cursor<'a> {
+    line: &'a Line,
+    at: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.line.tokens.get(self.at).map(|(token, _)| token)
+    }
+
+    /// The column of the next token, or just past the line's end.
+    fn column(&self) -> usize {
+        self.line
+            .tokens
+            .get(self.at)
+            .map_or(self.line.end, |&(_, column)| column)
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        self.line.error(self.column(), message)
+    }
+
+    /// Take the next token if it is the symbol or word `text`.
+    fn eat(&mut self, text: &str) -> bool {
+        let found = match self.peek() {
+            Some(Token::Symbol(symbol)) => *symbol == text,
+            Some(Token::Name(name)) => name == text,
+            _ => false,
+        };
+        self.at += found as usize;
+        found
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), Error> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", text)))
+        }
+    }
+
+    fn name(&mut self, what: &str) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Name(name)) if !KEYWORDS.contains(&name.as_str()) => {
+                self.at += 1;
+                Ok(name.clone())
+            }
+            _ => Err(self.error(format!("expected {}", what))),
+        }
+    }
+
+    fn int(&mut self, what: &str) -> Result<i64, Error> {
+        match self.peek() {
+            Some(&Token::Int(n)) => {
+                self.at += 1;
+                Ok(n)
+            }
+            _ => Err(self.error(format!("expected {}", what))),
+        }
+    }
+
+    fn end(&self) -> Result<(), Error> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        self.binary(0)
+    }
+
+    /// An expression of binary operators binding at least as tightly as
+    /// those of `level` in `LEVELS`.
+    fn binary(&mut self, level: usize) -> Result<Expr, Error> {
+        const LEVELS: [&[(&str, Binary)]; 5] = [
+            &[("or", Binary::Or)],
+            &[("and", Binary::And)],
+            &[
+                ("==", Binary::Eq),
+                ("!=", Binary::Ne),
+                ("<=", Binary::Le),
+                (">=", Binary::Ge),
+                ("<", Binary::Lt),
+                (">", Binary::Gt),
+            ],
+            &[("+", Binary::Add), ("-", Binary::Sub)],
+            &[("*", Binary::Mul), ("/", Binary::Div), ("%", Binary::Rem)],
+        ];
+        let operand = |cursor: &mut Self| {
+            if level + 1 < LEVELS.len() {
+                cursor.binary(level + 1)
+            } else {
+                cursor.unary()
+            }
+        };
+        let mut left = operand(self)?;
+        'more: loop {
+            for &(text, op) in LEVELS[level] {
+                if self.eat(text) {
+                    let right = operand(self)?;
+                    left = Expr {
+                        line: left.line,
+                        column: left.column,
+                        kind: Kind::Binary(op, Box::new(left), Box::new(right)),
+                    };
+                    continue 'more;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        let (line, column) = (self.line.number, self.column());
+        let op = if self.eat("-") {
+            Unary::Neg
+        } else if self.eat("not") {
+            Unary::Not
+        } else {
+            return self.primary();
+        };
+        let operand = self.unary()?;
+        Ok(Expr {
+            kind: Kind::Unary(op, Box::new(operand)),
+            line,
+            column,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let (line, column) = (self.line.number, self.column());
+        let kind = match self.peek() {
+            Some(&Token::Int(n)) => {
+                self.at += 1;
+                Kind::Int(n)
+            }
+            Some(Token::Str(text)) => {
+                self.at += 1;
+                Kind::Str(text.clone())
+            }
+            Some(Token::Symbol("(")) => {
+                self.at += 1;
+                let inner = self.expr()?;
+                self.expect(")")?;
+                return Ok(inner);
+            }
+            Some(Token::Name(name)) if name == "true" || name == "false" => {
+                self.at += 1;
+                Kind::Int((name == "true") as i64)
+            }
+            Some(Token::Name(_)) => {
+                let name = self.name("a value")?;
+                if !self.eat("(") {
+                    return Ok(Expr {
+                        kind: Kind::Var(name),
+                        line,
+                        column,
+                    });
+                }
+                let &(function, arity) =
+                    FUNCTIONS.iter().find(|(f, _)| *f == name).ok_or_else(|| {
+                        self.line
+                            .error(column, format!("unknown function `{}`", name))
+                    })?;
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                if args.len() != arity {
+                    return Err(self.line.error(
+                        column,
+                        format!(
+                            "`{}` takes {} arguments, not {}",
+                            function,
+                            arity,
+                            args.len()
+                        ),
+                    ));
+                }
+                Kind::Call(function, args)
+            }
+            _ => return Err(self.error("expected a value")),
+        };
+        Ok(Expr { kind, line, column })
+    }
+}
+
+/// Parses the statements of a handler, line by line.
+struct Parser<'a> {
+    lines: &'a [Line],
+    at: usize,
+}
+
+impl Parser<'_> {
+    /// Statements up to the next `else` or `end` line, which is left unread.
+    fn block(&mut self) -> Result<Vec<Stmt>, Error> {
+        let lines = self.lines;
+        let mut body = Vec::new();
+        while let Some(line) = lines.get(self.at) {
+            if matches!(line.keyword(), Some("else" | "end")) {
+                break;
+            }
+            self.at += 1;
+            body.push(self.statement(line)?);
+        }
+        Ok(body)
+    }
+
+    /// Read the line `word` closing the block opened by `opener`, if it is next.
+    fn close(&mut self, word: &str, opener: &Line) -> Result<bool, Error> {
+        match self.lines.get(self.at) {
+            Some(line) if line.keyword() == Some(word) => {
+                self.at += 1;
+                Cursor { line, at: 1 }.end()?;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(opener.error(opener.tokens[0].1, "block has no `end`")),
+        }
+    }
+
+    fn statement(&mut self, line: &Line) -> Result<Stmt, Error> {
+        let mut cursor = Cursor { line, at: 1 };
+        let stmt = match line.keyword() {
+            Some("let") => {
+                let name = cursor.name("a variable name")?;
+                cursor.expect("=")?;
+                let value = cursor.expr()?;
+                Stmt::Let { name, value }
+            }
+            Some("if") => {
+                let condition = cursor.expr()?;
+                cursor.end()?;
+                let then = self.block()?;
+                let otherwise = if self.close("else", line)? {
+                    self.block()?
+                } else {
+                    Vec::new()
+                };
+                if !self.close("end", line)? {
+                    return Err(self.stray());
+                }
+                return Ok(Stmt::If {
+                    condition,
+                    then,
+                    otherwise,
+                });
+            }
+            Some("while") => {
+                let condition = cursor.expr()?;
+                cursor.end()?;
+                let body = self.block()?;
+                if !self.close("end", line)? {
+                    return Err(self.stray());
+                }
+                return Ok(Stmt::While { condition, body });
+            }
+            _ => {
+                cursor.at = 0;
+                let expr = cursor.expr()?;
+                if !matches!(expr.kind, Kind::Call(..)) {
+                    return Err(expr.error("expected a statement, such as a function call"));
+                }
+                Stmt::Call(expr)
+            }
+        };
+        cursor.end()?;
+        Ok(stmt)
+    }
+
+    /// The error for an `else` or `end` line where none can be.
+    fn stray(&self) -> Error {
+        let line = &self.lines[self.at];
+        let word = line.keyword().unwrap_or_default();
+        line.error(
+            line.tokens[0].1,
+            format!("`{}` without a block to close", word),
+        )
+    }
+}
+
+/// A handler: what it runs on and the statements it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Handler {
+    pub event: Event,
+    /// The line of its `on` in the level file.
+    pub line: usize,
+    /// Its lines between `on` and `end`, as written.
+    pub source: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+impl Handler {
+    /// Parse the handler starting on line `number` of the level file, whose
+    /// text is `text`, taking its lines up to the `end` closing it from
+    /// `rest`.
+    pub fn parse<'a>(
+        number: usize,
+        text: &str,
+        rest: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Handler, Error> {
+        let header = Line::lex(number, text)?;
+        let mut cursor = Cursor {
+            line: &header,
+            at: 0,
+        };
+        cursor.expect("on")?;
+        let event = if cursor.eat("start") {
+            Event::Start
+        } else if cursor.eat("trigger") {
+            let n = cursor.int("a script number")?;
+            Event::Trigger(
+                u32::try_from(n).map_err(|_| cursor.error("script number out of range"))?,
+            )
+        } else if cursor.eat("enter") {
+            let s = cursor.int("a sector number")?;
+            Event::Enter(
+                usize::try_from(s).map_err(|_| cursor.error("sector number out of range"))?,
+            )
+        } else {
+            return Err(cursor.error("expected an event: `start`, `trigger` or `enter`"));
+        };
+        cursor.end()?;
+
+        // The lines up to the `end` matching `on`.
+        let mut source = Vec::new();
+        let mut lines = Vec::new();
+        let mut depth = 1;
+        loop {
+            let (n, text) = rest
+                .next()
+                .ok_or_else(|| header.error(1, "`on` has no `end`"))?;
+            let line = Line::lex(n, text)?;
+            match line.keyword() {
+                Some("if" | "while") => depth += 1,
+                Some("end") => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                Cursor { line: &line, at: 1 }.end()?;
+                break;
+            }
+            source.push(text.to_owned());
+            lines.push(line);
+        }
+
+        let mut parser = Parser {
+            lines: &lines,
+            at: 0,
+        };
+        let body = parser.block()?;
+        if parser.at < lines.len() {
+            return Err(parser.stray());
+        }
+        Ok(Handler {
+            event,
+            line: number,
+            source,
+            body,
+        })
+    }
+}
+
+/// The handlers of a level.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub handlers: Vec<Handler>,
+}
+
+impl Script {
+    /// Whether any handler runs on `event`.
+    pub fn handles(&self, event: Event) -> bool {
+        self.handlers.iter().any(|handler| handler.event == event)
+    }
+}
+
+/// As written in a level file.
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for handler in &self.handlers {
+            writeln!(f)?;
+            writeln!(f, "on {}", handler.event)?;
+            for line in &handler.source {
+                writeln!(f, "{}", line)?;
+            }
+            writeln!(f, "end")?;
+        }
+        Ok(())
+    }
+}
+
+/// `op` applied to `a` and `b`, other than `and` and `or`.
+fn binary(op: Binary, a: Value, b: Value) -> Result<Value, String> {
+    let join = |a: &Value, b: &Value| {
+        let text = format!("{}{}", a, b);
+        if text.chars().count() > MAX_STRING {
+            Err(format!("string longer than {} characters", MAX_STRING))
+        } else {
+            Ok(Value::Str(text))
+        }
+    };
+    let (x, y) = match (op, &a, &b) {
+        (Binary::Eq, ..) => return Ok((a == b).into()),
+        (Binary::Ne, ..) => return Ok((a != b).into()),
+        (Binary::Add, Value::Str(_), _) | (Binary::Add, _, Value::Str(_)) => return join(&a, &b),
+        (_, &Value::Int(x), &Value::Int(y)) => (x, y),
+        _ => return Err(format!("`{}` needs numbers", op.symbol())),
+    };
+    let overflow = || "number out of range".to_owned();
+    Ok(match op {
+        Binary::Add => Value::Int(x.checked_add(y).ok_or_else(overflow)?),
+        Binary::Sub => Value::Int(x.checked_sub(y).ok_or_else(overflow)?),
+        Binary::Mul => Value::Int(x.checked_mul(y).ok_or_else(overflow)?),
+        Binary::Div | Binary::Rem if y == 0 => return Err("division by zero".to_owned()),
+        Binary::Div => Value::Int(x.checked_div(y).ok_or_else(overflow)?),
+        Binary::Rem => Value::Int(x.checked_rem(y).ok_or_else(overflow)?),
+        Binary::Lt => (x < y).into(),
+        Binary::Le => (x <= y).into(),
+        Binary::Gt => (x > y).into(),
+        Binary::Ge => (x >= y).into(),
+        Binary::Eq | Binary::Ne | Binary::And | Binary::Or => unreachable!(),
+    })
+}
+
+/// One run of a handler.
+struct Run<'a> {
+    world: &'a mut World,
+    vars: &'a mut HashMap<String, Value>,
+    steps: u32,
+}
+
+impl Run<'_> {
+    fn step(&mut self, at: &Expr) -> Result<(), Error> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(at.error(format!("stopped after {} steps", MAX_STEPS)));
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, body: &[Stmt]) -> Result<(), Error> {
+        for stmt in body {
+            match stmt {
+                Stmt::Let { name, value } => {
+                    self.step(value)?;
+                    let value = self.eval(value)?;
+                    self.vars.insert(name.clone(), value);
+                }
+                Stmt::Call(call) => {
+                    self.step(call)?;
+                    self.eval(call)?;
+                }
+                Stmt::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    self.step(condition)?;
+                    if self.eval(condition)?.truthy() {
+                        self.block(then)?;
+                    } else {
+                        self.block(otherwise)?;
+                    }
+                }
+                Stmt::While { condition, body } => loop {
+                    self.step(condition)?;
+                    if !self.eval(condition)?.truthy() {
+                        break;
+                    }
+                    self.block(body)?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value, Error> {
+        match &expr.kind {
+            Kind::Int(n) => Ok(Value::Int(*n)),
+            Kind::Str(text) => Ok(Value::Str(text.clone())),
+            Kind::Var(name) => self
+                .vars
+                .get(name)
+                .cloned()
+                .ok_or_else(|| expr.error(format!("unknown variable `{}`", name))),
+            Kind::Unary(Unary::Not, operand) => Ok((!self.eval(operand)?.truthy()).into()),
+            Kind::Unary(Unary::Neg, operand) => match self.eval(operand)? {
+                Value::Int(n) => n
+                    .checked_neg()
+                    .map(Value::Int)
+                    .ok_or_else(|| expr.error("number out of range")),
+                Value::Str(_) => Err(expr.error("`-` needs a number")),
+            },
+            Kind::Binary(Binary::And, a, b) => {
+                Ok((self.eval(a)?.truthy() && self.eval(b)?.truthy()).into())
+            }
+            Kind::Binary(Binary::Or, a, b) => {
+                Ok((self.eval(a)?.truthy() || self.eval(b)?.truthy()).into())
+            }
+            Kind::Binary(op, a, b) => {
+                let (a, b) = (self.eval(a)?, self.eval(b)?);
+                binary(*op, a, b).map_err(|message| expr.error(message))
+            }
+            Kind::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(function, &args)
+                    .map_err(|message| expr.error(message))
+            }
+        }
+    }
+
+    fn call(&mut self, function: &str, args: &[Value]) -> Result<Value, String> {
+        let int = |i: usize| match &args[i] {
+            Value::Int(n) => Ok(*n),
+            Value::Str(_) => Err(format!(
+                "`{}` needs a number as argument {}",
+                function,
+                i + 1
+            )),
+        };
+        let sectors = self.world.sectors.len();
+        let sector = |i: usize| {
+            let s = int(i)?;
+            usize::try_from(s)
+                .ok()
+                .filter(|&s| s < sectors)
+                .ok_or_else(|| format!("no sector {}", s))
+        };
+        let height = |i: usize| {
+            let z = int(i)?;
+            i32::try_from(z).map_err(|_| format!("height {} out of range", z))
+        };
+        let world = &mut *self.world;
+        let done = Value::Int(0);
+        Ok(match function {
+            "floor" => Value::Int(world.sectors[sector(0)?].z1.into()),
+            "ceiling" => Value::Int(world.sectors[sector(0)?].z2.into()),
+            "light" => Value::Int(world.sectors[sector(0)?].light.into()),
+            "set_floor" => {
+                let (s, z) = (sector(0)?, height(1)?);
+                world.sectors[s].z1 = z;
+                done
+            }
+            "set_ceiling" => {
+                let (s, z) = (sector(0)?, height(1)?);
+                world.sectors[s].z2 = z;
+                done
+            }
+            "set_light" => {
+                let (s, level) = (sector(0)?, int(1)?);
+                world.sectors[s].light = u8::try_from(level)
+                    .map_err(|_| format!("light level {} is not 0-255", level))?;
+                done
+            }
+            "start" => {
+                let tag = int(0)?;
+                world.start_tagged(u32::try_from(tag).map_err(|_| format!("no tag {}", tag))?);
+                done
+            }
+            "spawn" => {
+                let name = match &args[0] {
+                    Value::Str(name) => name,
+                    Value::Int(_) => return Err("`spawn` needs a sprite name".to_owned()),
+                };
+                let sprite = world
+                    .sprite_names
+                    .iter()
+                    .position(|n| n == name)
+                    .ok_or_else(|| format!("unknown sprite `{}`", name))?;
+                if world.things.len() >= MAX_THINGS {
+                    return Err(format!("more than {} things", MAX_THINGS));
+                }
+                let (x, y, z) = (int(1)?, int(2)?, int(3)?);
+                world.things.push(Thing {
+                    position: Vec3::new(x as f64, y as f64, z as f64),
+                    angle: Angle::from_degrees(int(4)? as f64),
+                    sprite,
+                    health: None,
+                });
+                Value::Int(world.things.len() as i64 - 1)
+            }
+            "message" => {
+                world.messages.push(args[0].to_string());
+                done
+            }
+            "player_x" => Value::Int(world.player.position.x.round() as i64),
+            "player_y" => Value::Int(world.player.position.y.round() as i64),
+            "player_z" => Value::Int(world.player.position.z.round() as i64),
+            "player_angle" => Value::Int(world.player.angle.degrees().round() as i64 % 360),
+            "player_health" => Value::Int(world.player.health.into()),
+            "player_sector" => Value::Int(world.player.sector.map_or(-1, |s| s as i64)),
+            "hurt" => {
+                let damage = int(0)?;
+                if damage < 0 {
+                    return Err(format!("bad damage {}", damage));
+                }
+                world.hurt(damage.min(MAX_HEALTH.into()) as u32);
+                done
+            }
+            "exit" => {
+                world.exited = true;
+                done
+            }
+            _ => unreachable!("functions are checked when parsing"),
+        })
+    }
+}
+
+impl World {
+    /// Run the handlers of `event`. Errors stop the handler and are added to
+    /// `script_errors`.
+    pub fn run_script(&mut self, event: Event) {
+        if !self.script.handles(event) {
+            return;
+        }
+        let script = mem::take(&mut self.script);
+        let mut vars = mem::take(&mut self.script_vars);
+        for handler in script.handlers.iter().filter(|h| h.event == event) {
+            let mut run = Run {
+                world: self,
+                vars: &mut vars,
+                steps: 0,
+            };
+            if let Err(error) = run.block(&handler.body) {
+                self.script_errors.push(error);
+            }
+        }
+        self.script = script;
+        self.script_vars = vars;
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use crate::palette::Palette;
+
+/// Texels of this colour are left out when a texture is drawn as a sprite,
+/// as in many Doom editing tools.
+pub const TRANSPARENT: [u8; 3] = [0, 255, 255];
+
+/// The colour of the hands holding the weapons.
+const SKIN: [u8; 3] = [190, 140, 110];
+
+/// A wall texture or sprite: `width * height` RGB texels, row by row.
+#[derive(Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<[u8; 3]>,
+    /// The texels as indices into the world's palette, once `quantize`d.
+    pub indexed: Vec<u8>,
+}
+
+impl Texture {
+    /// Palette index of the texel at `(u, v)`; coordinates outside the
+    /// texture wrap around. The texture must have been `quantize`d.
+    pub fn sample(&self, u: i32, v: i32) -> u8 {
+        let u = u.rem_euclid(self.width as i32) as usize;
+        let v = v.rem_euclid(self.height as i32) as usize;
+        self.indexed[v * self.width + u]
+    }
+
+    /// Palette index of the texel at `(u, v)`, or `None` outside of the
+    /// texture or where it is `TRANSPARENT`. The texture must have been
+    /// `quantize`d.
+    pub fn texel(&self, u: i32, v: i32) -> Option<u8> {
+        if u < 0 || v < 0 || u as usize >= self.width || v as usize >= self.height {
+            return None;
+        }
+        let i = v as usize * self.width + u as usize;
+        (self.texels[i] != TRANSPARENT).then_some(self.indexed[i])
+    }
+
+    /// Map every texel to the closest colour of `palette`.
+    pub fn quantize(&mut self, palette: &Palette) {
+        let mut nearest = HashMap::new();
+        self.indexed = self
+            .texels
+            .iter()
+            .map(|&rgb| *nearest.entry(rgb).or_insert_with(|| palette.nearest(rgb)))
+            .collect();
+    }
+
+    /// Decode a binary PPM (`P6`) image.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0;
+        let mut header = Vec::new();
+        while header.len() < 4 {
+            match bytes.get(pos) {
+                None => return Err("truncated header".to_owned()),
+                Some(b'#') => {
+                    while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                Some(_) => {
+                    let start = pos;
+                    while bytes.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                        pos += 1;
+                    }
+                    header.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+                }
+            }
+        }
+        if header[0] != "P6" {
+            return Err("not a binary PPM (P6) image".to_owned());
+        }
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| format!("bad number `{}`", s))
+        };
+        let (width, height, max) = (
+            number(&header[1])?,
+            number(&header[2])?,
+            number(&header[3])?,
+        );
+        if width == 0 || height == 0 {
+            return Err("empty image".to_owned());
+        }
+        if max == 0 || max > 255 {
+            return Err(format!("unsupported maximum value {}", max));
+        }
+        // A single whitespace character separates the header from the samples.
+        let data = bytes
+            .get(pos + 1..)
+            .ok_or_else(|| "missing pixel data".to_owned())?;
+        let size = width
+            .checked_mul(height)
+            .and_then(|texels| texels.checked_mul(3))
+            .ok_or_else(|| format!("image of {}x{} is too large", width, height))?;
+        if data.len() < size {
+            return Err("truncated pixel data".to_owned());
+        }
+        let texels = data
+            .chunks_exact(3)
+            .take(width * height)
+            .map(|c| [c[0], c[1], c[2]].map(|v| (v as usize * 255 / max) as u8))
+            .collect();
+        Ok(Texture {
+            width,
+            height,
+            texels,
+            indexed: Vec::new(),
+        })
+    }
+
+    /// Staggered bricks of `brick` colour separated by lines of `mortar`.
+    pub fn bricks(brick: [u8; 3], mortar: [u8; 3]) -> Self {
+        Texture::generate(64, 64, |u, v| {
+            let row = v / 16;
+            let shift = if row % 2 == 0 { 0 } else { 16 };
+            if v % 16 < 2 || (u + shift) % 32 < 2 {
+                mortar
+            } else {
+                shade(brick, noise(u / 2, v / 2, row))
+            }
+        })
+    }
+
+    /// Square stone slabs of `stone` colour with a darker seam.
+    pub fn slabs(stone: [u8; 3]) -> Self {
+        Texture::generate(64, 64, |u, v| {
+            if u % 32 == 0 || v % 32 == 0 {
+                shade(stone, -48)
+            } else if u % 32 == 31 || v % 32 == 31 {
+                shade(stone, 24)
+            } else {
+                shade(stone, noise(u, v, 7) / 2)
+            }
+        })
+    }
+
+    /// A barrel sprite: a `color` drum with dark hoops.
+    pub fn barrel(color: [u8; 3]) -> Self {
+        Texture::generate(24, 32, |u, v| {
+            // Lit from the front: brightest in the middle, darker at the sides.
+            let side = (u as i32 * 2 - 23).abs();
+            let rim = !(2..30).contains(&v);
+            if (side > 21 && !rim) || (v == 0 && side > 19) {
+                TRANSPARENT
+            } else if rim || v % 10 == 4 {
+                shade([70, 70, 70], -side * 2)
+            } else {
+                shade(color, 24 - side * 4 + noise(u, v, 3) / 2)
+            }
+        })
+    }
+
+    /// A lamp sprite: a grey post carrying a glowing `color` globe.
+    pub fn lamp(color: [u8; 3]) -> Self {
+        Texture::generate(16, 48, |u, v| {
+            let (dx, dy) = (u as i32 * 2 - 15, v as i32 * 2 - 13);
+            let globe = dx * dx + dy * dy;
+            if globe < 144 {
+                shade(color, 48 - globe / 3)
+            } else if (v > 12 && (6..10).contains(&u)) || (v > 44 && (3..13).contains(&u)) {
+                shade([110, 110, 120], -(dx.abs() * 6))
+            } else {
+                TRANSPARENT
+            }
+        })
+    }
+
+    /// A pistol held in a fist, seen from behind, to draw over the view: a
+    /// `metal` slide with a sight on top.
+    pub fn pistol(metal: [u8; 3]) -> Self {
+        Texture::generate(32, 40, |u, v| {
+            let side = (u as i32 * 2 - 31).abs();
+            let v = v as i32;
+            if v < 2 && side < 3 {
+                shade(metal, -40)
+            } else if (2..26).contains(&v) && side < 9 {
+                shade(metal, 24 - side * 4 - v + noise(u, v as usize, 5) / 4)
+            } else if v >= 22 && side < 13 + (v - 22) / 2 {
+                shade(SKIN, 16 - side * 2 + noise(u, v as usize, 9) / 2)
+            } else {
+                TRANSPARENT
+            }
+        })
+    }
+
+    /// A double-barrelled shotgun held in both hands, seen from behind, to
+    /// draw over the view: `metal` barrels over a `wood` grip.
+    pub fn shotgun(metal: [u8; 3], wood: [u8; 3]) -> Self {
+        Texture::generate(48, 56, |u, v| {
+            let side = (u as i32 * 2 - 47).abs();
+            let barrel = (side - 7).abs();
+            let v = v as i32;
+            if (20..32).contains(&v) && side < 19 {
+                shade(wood, 8 - side * 2 + noise(u / 3, v as usize, 4) / 2)
+            } else if v < 42 && barrel < 6 {
+                shade(metal, 32 - barrel * 8 - v / 2)
+            } else if (38..48).contains(&v) && side < 21 {
+                shade(metal, -24 - side)
+            } else if v >= 44 && side < 27 + (v - 44) {
+                shade(SKIN, 16 - side + noise(u, v as usize, 9) / 2)
+            } else {
+                TRANSPARENT
+            }
+        })
+    }
+
+    /// A muzzle flash: a ragged burst of `color`, white hot in the middle.
+    pub fn flash(color: [u8; 3]) -> Self {
+        Texture::generate(24, 20, |u, v| {
+            let (dx, dy) = (u as i32 * 2 - 23, v as i32 * 2 - 19);
+            let burst = dx * dx + dy * dy * 2;
+            if burst < 24 {
+                shade(color, 160)
+            } else if burst < 300 + noise(u, v, 11) * 12 {
+                shade(color, 48 - burst / 6)
+            } else {
+                TRANSPARENT
+            }
+        })
+    }
+
+    /// Frame `frame` of a bullet puff, from 0 to 3: a spark, then a wisp of
+    /// smoke thinning out.
+    pub fn puff(frame: usize) -> Self {
+        Texture::generate(12, 12, |u, v| {
+            let (dx, dy) = (u as i32 * 2 - 11, v as i32 * 2 - 11);
+            let distance = dx * dx + dy * dy;
+            let radius = [6, 10, 9, 7][frame.min(3)];
+            if distance >= radius * radius || (frame > 1 && noise(u, v, frame) > 4) {
+                TRANSPARENT
+            } else if frame == 0 {
+                shade([255, 230, 140], -distance * 2)
+            } else {
+                shade([150, 150, 150], -(frame as i32) * 24 - distance / 2)
+            }
+        })
+    }
+
+    fn generate(width: usize, height: usize, texel: impl Fn(usize, usize) -> [u8; 3]) -> Self {
+        let mut texels = Vec::with_capacity(width * height);
+        for v in 0..height {
+            for u in 0..width {
+                texels.push(texel(u, v));
+            }
+        }
+        Texture {
+            width,
+            height,
+            texels,
+            indexed: Vec::new(),
+        }
+    }
+}
+
+/// Deterministic value in `-16..16` for the texel `(u, v)`.
+fn noise(u: usize, v: usize, seed: usize) -> i32 {
+    let mut h = (u as u32).wrapping_mul(374_761_393)
+        ^ (v as u32).wrapping_mul(668_265_263)
+        ^ (seed as u32).wrapping_mul(2_246_822_519);
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    (h >> 27) as i32 - 16
+}
+
+fn shade(rgb: [u8; 3], amount: i32) -> [u8; 3] {
+    rgb.map(|c| (c as i32 + amount).clamp(0, 255) as u8)
+}
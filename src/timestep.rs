@@ -0,0 +1,48 @@
+//! Fixed rate simulation.
+//!
+//! The world advances in ticks of `TICK`, whatever the frame rate. Real time
+//! is collected in an accumulator and spent a whole tick at a time; what is
+//! left over, as a fraction of a tick, tells the renderer how far to
+//! interpolate between the last two ticks.
+
+use std::time::Duration;
+
+/// Simulation ticks per second, as in Doom.
+pub const TICK_RATE: u32 = 35;
+
+/// Length of one simulation tick.
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / TICK_RATE as u64);
+
+/// Longest stretch of real time simulated in one frame. After a stall (a
+/// breakpoint, a dragged window) the game slows down instead of running
+/// many ticks at once and falling further behind.
+pub const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+pub struct Timestep {
+    /// Real time not yet simulated, less than `TICK` between frames.
+    accumulator: Duration,
+}
+
+impl Timestep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the real time `elapsed` since the last frame and return how many
+    /// ticks to simulate for it.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed.min(MAX_FRAME_TIME);
+        let mut ticks = 0;
+        while self.accumulator >= TICK {
+            self.accumulator -= TICK;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// How far the present lies between the last tick and the next one, in `0..1`.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / TICK.as_secs_f64()
+    }
+}
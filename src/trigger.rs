@@ -0,0 +1,189 @@
+//! Line triggers: walls that do something when the player crosses, uses or
+//! shoots them.
+//!
+//! A wall's `Trigger` says how it is set off and what it then does, usually
+//! to the sectors whose tag it names:
+//!
+//! - `Walk` fires when the player crosses the wall's line, either way,
+//!   between their position before a tick and after it;
+//! - `Use` fires when the player uses the wall within `USE_RANGE`, through
+//!   any portals in between;
+//! - `Shoot` fires when a shot hits the wall, see `World::shoot_wall`.
+//!
+//! Like Doom's W1, S1 and G1 lines, a trigger marked `once` is spent when it
+//! fires; others fire every time.
+
+use std::fmt;
+
+use crate::math::Vec2;
+use crate::script::Event;
+use crate::world::{Wall, World};
+
+/// How a trigger is set off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activation {
+    Walk,
+    Use,
+    Shoot,
+}
+
+/// What a trigger does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// Start the movers of the sectors tagged `tag`: doors open, lifts
+    /// lower, platforms and crushers get going and stairs rise.
+    Start { tag: u32 },
+    /// Set the light level of the sectors tagged `tag`.
+    Light { tag: u32, level: u8 },
+    /// End the level.
+    Exit,
+    /// Run the script handlers of `on trigger <event>`.
+    Script { event: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trigger {
+    pub activation: Activation,
+    pub effect: Effect,
+    /// Whether the trigger fires only the first time.
+    pub once: bool,
+}
+
+impl Effect {
+    /// The tag of the sectors the effect changes, if it changes any.
+    pub fn tag(self) -> Option<u32> {
+        match self {
+            Effect::Start { tag } | Effect::Light { tag, .. } => Some(tag),
+            Effect::Exit | Effect::Script { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Activation::Walk => write!(f, "walk"),
+            Activation::Use => write!(f, "use"),
+            Activation::Shoot => write!(f, "shoot"),
+        }
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::Start { tag } => write!(f, "start {}", tag),
+            Effect::Light { tag, level } => write!(f, "light {} {}", tag, level),
+            Effect::Exit => write!(f, "exit"),
+            Effect::Script { event } => write!(f, "script {}", event),
+        }
+    }
+}
+
+/// As written after `trigger` in a level file.
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.activation)?;
+        if self.once {
+            write!(f, " once")?;
+        }
+        write!(f, " {}", self.effect)
+    }
+}
+
+/// Whether the move from `from` to `to` crosses `wall`, from either side.
+/// Ending on the wall's line counts as crossing it from the front.
+fn crosses(wall: &Wall, from: Vec2, to: Vec2) -> bool {
+    let start = Vec2::new(wall.x1 as f64, wall.y1 as f64);
+    let along = Vec2::new(wall.x2 as f64, wall.y2 as f64) - start;
+    let side = |point: Vec2| (point - start).dot(along.perp());
+    let (a, b) = (side(from), side(to));
+    if (a >= 0.0) == (b >= 0.0) {
+        return false;
+    }
+    let hit = from + (to - from) * (a / (a - b));
+    let s = (hit - start).dot(along) / along.dot(along);
+    (0.0..=1.0).contains(&s)
+}
+
+impl World {
+    /// Fire the trigger of wall `w`, if it has one, whatever its activation.
+    pub fn fire(&mut self, w: usize) {
+        let trigger = match self.walls[w].trigger {
+            Some(trigger) => trigger,
+            None => return,
+        };
+        if trigger.once {
+            self.walls[w].trigger = None;
+        }
+        match trigger.effect {
+            Effect::Start { tag } => self.start_tagged(tag),
+            Effect::Light { tag, level } => {
+                for s in self.tagged(tag) {
+                    self.sectors[s].light = level;
+                }
+            }
+            Effect::Exit => self.exited = true,
+            Effect::Script { event } => self.run_script(Event::Trigger(event)),
+        }
+    }
+
+    /// The sectors tagged `tag`.
+    pub fn tagged(&self, tag: u32) -> Vec<usize> {
+        (0..self.sectors.len())
+            .filter(|&s| self.sectors[s].tag == Some(tag))
+            .collect()
+    }
+
+    /// Start the movers of the sectors tagged `tag`.
+    pub fn start_tagged(&mut self, tag: u32) {
+        for s in self.tagged(tag) {
+            if let Some(m) = self.mover_of(s) {
+                self.start(m);
+            }
+        }
+    }
+
+    /// Fire the walk triggers of the walls crossed moving from `from` to `to`.
+    pub(crate) fn walk_triggers(&mut self, from: Vec2, to: Vec2) {
+        for w in 0..self.walls.len() {
+            let wall = &self.walls[w];
+            if wall
+                .trigger
+                .is_some_and(|t| t.activation == Activation::Walk)
+                && crosses(wall, from, to)
+            {
+                self.fire(w);
+            }
+        }
+    }
+
+    /// The wall with a use trigger the player faces within `USE_RANGE`, if
+    /// any: the nearest one crossed on the way, through portals.
+    pub fn trigger_in_reach(&self) -> Option<usize> {
+        for (_, w) in self.walls_in_reach() {
+            let wall = &self.walls[w];
+            if wall
+                .trigger
+                .is_some_and(|t| t.activation == Activation::Use)
+            {
+                return Some(w);
+            }
+            if wall.neighbour.is_none() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Fire the trigger of wall `w` if a shot sets it off. Shots call this
+    /// for the wall they hit.
+    pub fn shoot_wall(&mut self, w: usize) {
+        if self.walls[w]
+            .trigger
+            .is_some_and(|t| t.activation == Activation::Shoot)
+        {
+            self.fire(w);
+        }
+    }
+}
@@ -0,0 +1,427 @@
+//! Checks that a level's geometry is sound.
+//!
+//! The renderer and the collision code trust the level they are given: the
+//! walls of every sector should form closed loops running clockwise, walls
+//! should not cross, sectors should not overlap and every index or tag should
+//! point at something. Bad data otherwise just renders strangely or panics, so
+//! `Level::validate` looks for all of it and says exactly where it is.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::level::Level;
+use crate::math::Vec2;
+use crate::script::Event;
+use crate::trigger::Effect;
+use crate::world::Wall;
+
+/// How far from the middle of its walls, into the sector, points of a
+/// sector are tested for lying in other sectors.
+const INSET: f64 = 0.25;
+
+type Point = (i32, i32);
+
+/// Something wrong with a level. Sectors and walls are numbered as in
+/// `Level::sectors` and `Level::walls`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The sector's walls don't lie within the level's walls.
+    WallsOutOfRange {
+        sector: usize,
+        wall_start: usize,
+        wall_end: usize,
+        walls: usize,
+    },
+    NoWalls {
+        sector: usize,
+    },
+    /// The wall starts where it ends.
+    ZeroLength {
+        sector: usize,
+        wall: usize,
+        at: Point,
+    },
+    /// More walls of the sector end at `at` than start there, so its walls
+    /// don't form closed loops.
+    Unclosed {
+        sector: usize,
+        wall: usize,
+        at: Point,
+    },
+    /// The sector's walls run counter-clockwise, or enclose no area.
+    WrongWinding {
+        sector: usize,
+    },
+    /// Two walls of the sector cross or lie along each other.
+    Crossing {
+        sector: usize,
+        walls: (usize, usize),
+    },
+    /// The two sectors share some area.
+    Overlap {
+        sectors: (usize, usize),
+    },
+    /// The portal leads to a sector that doesn't exist.
+    DanglingPortal {
+        sector: usize,
+        wall: usize,
+        neighbour: usize,
+    },
+    /// The portal leads back into the sector it belongs to.
+    PortalToSelf {
+        sector: usize,
+        wall: usize,
+    },
+    /// The sector the portal leads to has no wall leading back.
+    OneWayPortal {
+        sector: usize,
+        wall: usize,
+        neighbour: usize,
+    },
+    MissingTexture {
+        sector: usize,
+        wall: usize,
+        texture: usize,
+    },
+    MissingSprite {
+        thing: usize,
+        sprite: usize,
+    },
+    /// The wall's trigger acts on a tag no sector has.
+    UnknownTag {
+        sector: usize,
+        wall: usize,
+        tag: u32,
+    },
+    /// The wall's trigger runs a script event no handler is for.
+    MissingHandler {
+        sector: usize,
+        wall: usize,
+        event: u32,
+    },
+    /// The script handler on line `line` runs on entering a sector that
+    /// doesn't exist.
+    HandlerSector {
+        line: usize,
+        sector: usize,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Problem::WallsOutOfRange {
+                sector,
+                wall_start,
+                wall_end,
+                walls,
+            } => write!(
+                f,
+                "sector {}: walls {}..{} are out of range, the level has {} walls",
+                sector, wall_start, wall_end, walls
+            ),
+            Problem::NoWalls { sector } => write!(f, "sector {}: has no walls", sector),
+            Problem::ZeroLength { sector, wall, at } => write!(
+                f,
+                "sector {}, wall {}: starts where it ends, at ({}, {})",
+                sector, wall, at.0, at.1
+            ),
+            Problem::Unclosed { sector, wall, at } => write!(
+                f,
+                "sector {}, wall {}: loop is not closed, no wall goes on from ({}, {})",
+                sector, wall, at.0, at.1
+            ),
+            Problem::WrongWinding { sector } => write!(
+                f,
+                "sector {}: walls run counter-clockwise, they must run clockwise \
+                 with the sector on their right",
+                sector
+            ),
+            Problem::Crossing { sector, walls } => write!(
+                f,
+                "sector {}: walls {} and {} cross",
+                sector, walls.0, walls.1
+            ),
+            Problem::Overlap { sectors } => {
+                write!(f, "sectors {} and {} overlap", sectors.0, sectors.1)
+            }
+            Problem::DanglingPortal {
+                sector,
+                wall,
+                neighbour,
+            } => write!(
+                f,
+                "sector {}, wall {}: portal to sector {}, which doesn't exist",
+                sector, wall, neighbour
+            ),
+            Problem::PortalToSelf { sector, wall } => write!(
+                f,
+                "sector {}, wall {}: portal to its own sector",
+                sector, wall
+            ),
+            Problem::OneWayPortal {
+                sector,
+                wall,
+                neighbour,
+            } => write!(
+                f,
+                "sector {}, wall {}: portal to sector {}, which has no wall back along it",
+                sector, wall, neighbour
+            ),
+            Problem::MissingTexture {
+                sector,
+                wall,
+                texture,
+            } => write!(
+                f,
+                "sector {}, wall {}: texture {} doesn't exist",
+                sector, wall, texture
+            ),
+            Problem::MissingSprite { thing, sprite } => {
+                write!(f, "thing {}: sprite {} doesn't exist", thing, sprite)
+            }
+            Problem::UnknownTag { sector, wall, tag } => write!(
+                f,
+                "sector {}, wall {}: trigger acts on tag {}, which no sector has",
+                sector, wall, tag
+            ),
+            Problem::MissingHandler {
+                sector,
+                wall,
+                event,
+            } => write!(
+                f,
+                "sector {}, wall {}: trigger runs script {}, which has no `on trigger {}`",
+                sector, wall, event, event
+            ),
+            Problem::HandlerSector { line, sector } => write!(
+                f,
+                "line {}: handler for entering sector {}, which doesn't exist",
+                line, sector
+            ),
+        }
+    }
+}
+
+fn start(wall: &Wall) -> Point {
+    (wall.x1, wall.y1)
+}
+
+fn end(wall: &Wall) -> Point {
+    (wall.x2, wall.y2)
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`: positive if it
+/// turns counter-clockwise, negative if clockwise, zero along a line.
+fn orientation(a: Point, b: Point, c: Point) -> i64 {
+    let (ax, ay) = (a.0 as i64, a.1 as i64);
+    (b.0 as i64 - ax) * (c.1 as i64 - ay) - (b.1 as i64 - ay) * (c.0 as i64 - ax)
+}
+
+/// Whether walls `a` and `b` cross each other. Walls meeting at their ends,
+/// or where one ends on the other, don't cross.
+fn crosses(a: &Wall, b: &Wall) -> bool {
+    let (a1, a2, b1, b2) = (start(a), end(a), start(b), end(b));
+    let (o1, o2) = (orientation(a1, a2, b1), orientation(a1, a2, b2));
+    let (o3, o4) = (orientation(b1, b2, a1), orientation(b1, b2, a2));
+    o1.signum() * o2.signum() < 0 && o3.signum() * o4.signum() < 0
+}
+
+/// Whether walls `a` and `b` lie along each other for more than a point.
+fn along(a: &Wall, b: &Wall) -> bool {
+    let (a1, a2, b1, b2) = (start(a), end(a), start(b), end(b));
+    if orientation(a1, a2, b1) != 0 || orientation(a1, a2, b2) != 0 {
+        return false;
+    }
+    // On one line: compare their extents along it.
+    let d = (a2.0 as i64 - a1.0 as i64, a2.1 as i64 - a1.1 as i64);
+    let at = |p: Point| (p.0 as i64 - a1.0 as i64) * d.0 + (p.1 as i64 - a1.1 as i64) * d.1;
+    let (a_low, a_high) = (at(a1).min(at(a2)), at(a1).max(at(a2)));
+    let (b_low, b_high) = (at(b1).min(at(b2)), at(b1).max(at(b2)));
+    a_low.max(b_low) < a_high.min(b_high)
+}
+
+impl Level {
+    /// Everything wrong with the level, or nothing if it is sound.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        // The sectors whose walls are sound enough to compare with others.
+        let mut sound = Vec::new();
+
+        for (s, sector) in self.sectors.iter().enumerate() {
+            if sector.wall_start > sector.wall_end || sector.wall_end > self.walls.len() {
+                problems.push(Problem::WallsOutOfRange {
+                    sector: s,
+                    wall_start: sector.wall_start,
+                    wall_end: sector.wall_end,
+                    walls: self.walls.len(),
+                });
+                continue;
+            }
+            if sector.wall_start == sector.wall_end {
+                problems.push(Problem::NoWalls { sector: s });
+                continue;
+            }
+            let found = problems.len();
+            let walls = || (sector.wall_start..sector.wall_end).map(|w| (w, &self.walls[w]));
+
+            // Each point must be left by as many walls as arrive at it.
+            let mut ends: HashMap<Point, i32> = HashMap::new();
+            for (w, wall) in walls() {
+                if start(wall) == end(wall) {
+                    problems.push(Problem::ZeroLength {
+                        sector: s,
+                        wall: w,
+                        at: start(wall),
+                    });
+                } else {
+                    *ends.entry(end(wall)).or_default() += 1;
+                    *ends.entry(start(wall)).or_default() -= 1;
+                }
+            }
+            for (w, wall) in walls() {
+                let at = end(wall);
+                if ends.get(&at).is_some_and(|&open| open > 0) {
+                    ends.remove(&at);
+                    problems.push(Problem::Unclosed {
+                        sector: s,
+                        wall: w,
+                        at,
+                    });
+                }
+            }
+
+            if problems.len() == found {
+                let area: i64 = walls()
+                    .map(|(_, wall)| orientation((0, 0), start(wall), end(wall)))
+                    .sum();
+                if area >= 0 {
+                    problems.push(Problem::WrongWinding { sector: s });
+                }
+            }
+
+            for (w, wall) in walls() {
+                for (v, other) in walls().filter(|&(v, _)| v > w) {
+                    if crosses(wall, other) || along(wall, other) {
+                        problems.push(Problem::Crossing {
+                            sector: s,
+                            walls: (w, v),
+                        });
+                    }
+                }
+            }
+
+            if problems.len() == found {
+                sound.push(s);
+            }
+
+            for (w, wall) in walls() {
+                if let Some(n) = wall.neighbour {
+                    let problem = match self.sectors.get(n) {
+                        None => Some(Problem::DanglingPortal {
+                            sector: s,
+                            wall: w,
+                            neighbour: n,
+                        }),
+                        Some(_) if n == s => Some(Problem::PortalToSelf { sector: s, wall: w }),
+                        Some(neighbour) => {
+                            // Walls out of range are a problem of their own.
+                            let back = self
+                                .walls
+                                .get(neighbour.wall_start..neighbour.wall_end)
+                                .is_none_or(|walls| {
+                                    walls
+                                        .iter()
+                                        .any(|b| start(b) == end(wall) && end(b) == start(wall))
+                                });
+                            (!back).then_some(Problem::OneWayPortal {
+                                sector: s,
+                                wall: w,
+                                neighbour: n,
+                            })
+                        }
+                    };
+                    problems.extend(problem);
+                }
+                if let Some(mapping) = wall.texture {
+                    if mapping.texture >= self.textures.len() {
+                        problems.push(Problem::MissingTexture {
+                            sector: s,
+                            wall: w,
+                            texture: mapping.texture,
+                        });
+                    }
+                }
+                if let Some(tag) = wall.trigger.and_then(|t| t.effect.tag()) {
+                    if !self.sectors.iter().any(|other| other.tag == Some(tag)) {
+                        problems.push(Problem::UnknownTag {
+                            sector: s,
+                            wall: w,
+                            tag,
+                        });
+                    }
+                }
+                if let Some(Effect::Script { event }) = wall.trigger.map(|t| t.effect) {
+                    if !self.script.handles(Event::Trigger(event)) {
+                        problems.push(Problem::MissingHandler {
+                            sector: s,
+                            wall: w,
+                            event,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Sectors overlap where their walls cross, or where a point just
+        // inside one of them lies in the other. Walls along each other are
+        // fine: they are the two sides of a portal or of a solid wall.
+        let mut overlaps = BTreeSet::new();
+        for &a in &sound {
+            let sector = &self.sectors[a];
+            for wall in &self.walls[sector.wall_start..sector.wall_end] {
+                let (p1, p2) = (
+                    Vec2::new(wall.x1 as f64, wall.y1 as f64),
+                    Vec2::new(wall.x2 as f64, wall.y2 as f64),
+                );
+                let inside = (p1 + p2) * 0.5 + (p2 - p1).perp().normalize().unwrap() * INSET;
+                for &b in sound.iter().filter(|&&b| b != a) {
+                    let other = &self.sectors[b];
+                    if other.contains(&self.walls, inside)
+                        || self.walls[other.wall_start..other.wall_end]
+                            .iter()
+                            .any(|o| crosses(wall, o))
+                    {
+                        overlaps.insert((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        problems.extend(
+            overlaps
+                .into_iter()
+                .map(|sectors| Problem::Overlap { sectors }),
+        );
+
+        for handler in &self.script.handlers {
+            if let Event::Enter(sector) = handler.event {
+                if sector >= self.sectors.len() {
+                    problems.push(Problem::HandlerSector {
+                        line: handler.line,
+                        sector,
+                    });
+                }
+            }
+        }
+
+        for (t, thing) in self.things.iter().enumerate() {
+            if thing.sprite >= self.sprites.len() {
+                problems.push(Problem::MissingSprite {
+                    thing: t,
+                    sprite: thing.sprite,
+                });
+            }
+        }
+        problems
+    }
+}
@@ -0,0 +1,436 @@
+//! Doom WAD files and conversion of their maps to levels.
+//!
+//! A WAD is a 12 byte header (`IWAD` or `PWAD`, lump count, directory offset)
+//! and a directory of 16 byte entries (offset, size, 8 byte name). A map is a
+//! marker lump such as `E1M1` or `MAP01` followed by its data lumps; only
+//! `THINGS`, `LINEDEFS`, `SIDEDEFS`, `VERTEXES` and `SECTORS` are read here.
+//! Of the things, only the player start is used: WAD sprites are not decoded.
+//! The common door, lift, light and exit line specials become triggers.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::level::Level;
+use crate::math::{Angle, Vec2};
+use crate::mover::Special;
+use crate::script::Script;
+use crate::trigger::{Activation, Effect, Trigger};
+use crate::{Player, Sector, Wall, SKY};
+
+/// Lumps that may follow a map marker.
+const MAP_LUMPS: [&str; 10] = [
+    "THINGS", "LINEDEFS", "SIDEDEFS", "VERTEXES", "SEGS", "SSECTORS", "NODES", "SECTORS", "REJECT",
+    "BLOCKMAP",
+];
+
+/// Thing type of the first player's start.
+const PLAYER1_START: i16 = 1;
+
+/// Linedef side slot meaning "no sidedef".
+const NO_SIDEDEF: u16 = 0xffff;
+
+/// Linedef specials of the doors a player opens by using them; the sector
+/// behind such a line becomes a door.
+const DOOR_SPECIALS: [u16; 10] = [1, 26, 27, 28, 31, 32, 33, 34, 117, 118];
+
+/// What a linedef special does to the sectors it tags.
+#[derive(Clone, Copy)]
+enum LineEffect {
+    /// The tagged sectors become doors and open.
+    Door,
+    /// The tagged sectors become lifts down to their lowest neighbouring
+    /// floor, and lower.
+    Lift,
+    Light(u8),
+    Exit,
+}
+
+/// The linedef specials turned into triggers: the special, how it is set
+/// off, whether only once, and its effect. Doom's doors that close again
+/// and those that stay open both become doors.
+const LINE_SPECIALS: [(u16, Activation, bool, LineEffect); 25] = [
+    (2, Activation::Walk, true, LineEffect::Door),
+    (4, Activation::Walk, true, LineEffect::Door),
+    (86, Activation::Walk, false, LineEffect::Door),
+    (90, Activation::Walk, false, LineEffect::Door),
+    (29, Activation::Use, true, LineEffect::Door),
+    (103, Activation::Use, true, LineEffect::Door),
+    (61, Activation::Use, false, LineEffect::Door),
+    (63, Activation::Use, false, LineEffect::Door),
+    (46, Activation::Shoot, false, LineEffect::Door),
+    (10, Activation::Walk, true, LineEffect::Lift),
+    (88, Activation::Walk, false, LineEffect::Lift),
+    (21, Activation::Use, true, LineEffect::Lift),
+    (62, Activation::Use, false, LineEffect::Lift),
+    (35, Activation::Walk, true, LineEffect::Light(35)),
+    (13, Activation::Walk, true, LineEffect::Light(255)),
+    (79, Activation::Walk, false, LineEffect::Light(35)),
+    (81, Activation::Walk, false, LineEffect::Light(255)),
+    (139, Activation::Use, false, LineEffect::Light(35)),
+    (138, Activation::Use, false, LineEffect::Light(255)),
+    (11, Activation::Use, true, LineEffect::Exit),
+    (51, Activation::Use, true, LineEffect::Exit),
+    (52, Activation::Walk, true, LineEffect::Exit),
+    (124, Activation::Walk, true, LineEffect::Exit),
+    (197, Activation::Shoot, true, LineEffect::Exit),
+    (198, Activation::Shoot, true, LineEffect::Exit),
+];
+
+/// Palette colours used for walls and flats, picked by texture name.
+const COLORS: [u8; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 9, 10];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A complete game.
+    Iwad,
+    /// A patch adding or replacing lumps.
+    Pwad,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Iwad => "IWAD",
+            Kind::Pwad => "PWAD",
+        })
+    }
+}
+
+pub struct Lump {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+pub struct Wad {
+    pub kind: Kind,
+    pub lumps: Vec<Lump>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// The file is not a well formed WAD.
+    Format(String),
+    /// A map or one of its lumps is missing or inconsistent.
+    Map(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Format(message) => write!(f, "bad WAD: {}", message),
+            Error::Map(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Wad {
+    /// Read and parse the WAD at `path`.
+    pub fn load(path: &Path) -> Result<Wad, Error> {
+        let bytes = fs::read(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+        Wad::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Wad, Error> {
+        let kind = match bytes.get(0..4) {
+            Some(b"IWAD") => Kind::Iwad,
+            Some(b"PWAD") => Kind::Pwad,
+            _ => return Err(Error::Format("missing IWAD/PWAD signature".to_owned())),
+        };
+        let count =
+            read_i32(bytes, 4).ok_or_else(|| Error::Format("truncated header".to_owned()))?;
+        let directory =
+            read_i32(bytes, 8).ok_or_else(|| Error::Format("truncated header".to_owned()))?;
+        if count < 0 || directory < 0 {
+            return Err(Error::Format(
+                "negative directory size or offset".to_owned(),
+            ));
+        }
+
+        // Checked before allocating, as the count may be anything.
+        let room = bytes.len().saturating_sub(directory as usize) / 16;
+        if count as usize > room {
+            return Err(Error::Format(format!(
+                "directory of {} lumps doesn't fit in the file",
+                count
+            )));
+        }
+
+        let mut lumps = Vec::with_capacity(count as usize);
+        for n in 0..count as usize {
+            let entry = directory as usize + n * 16;
+            let (offset, size, name) = match (
+                read_i32(bytes, entry),
+                read_i32(bytes, entry + 4),
+                bytes.get(entry + 8..entry + 16),
+            ) {
+                (Some(offset), Some(size), Some(name)) => (offset, size, name),
+                _ => return Err(Error::Format(format!("directory entry {} is truncated", n))),
+            };
+            let name = lump_name(name);
+            let data = usize::try_from(offset)
+                .ok()
+                .zip(usize::try_from(size).ok())
+                .and_then(|(offset, size)| bytes.get(offset..offset.checked_add(size)?))
+                .ok_or_else(|| Error::Format(format!("lump {} lies outside the file", name)))?;
+            lumps.push(Lump {
+                name,
+                data: data.to_vec(),
+            });
+        }
+        Ok(Wad { kind, lumps })
+    }
+
+    /// Data of the last lump called `name`, so later lumps replace earlier ones.
+    pub fn lump(&self, name: &str) -> Option<&[u8]> {
+        self.lumps
+            .iter()
+            .rev()
+            .find(|lump| lump.name.eq_ignore_ascii_case(name))
+            .map(|lump| lump.data.as_slice())
+    }
+
+    /// Names of the maps in the WAD, in directory order.
+    pub fn maps(&self) -> Vec<&str> {
+        self.lumps
+            .windows(2)
+            .filter(|pair| {
+                !MAP_LUMPS.contains(&pair[0].name.as_str())
+                    && (pair[1].name == "THINGS" || pair[1].name == "LINEDEFS")
+            })
+            .map(|pair| pair[0].name.as_str())
+            .collect()
+    }
+
+    /// Data lump `name` of the last map called `map`.
+    pub fn map_lump(&self, map: &str, name: &str) -> Result<&[u8], Error> {
+        let marker = self
+            .lumps
+            .iter()
+            .rposition(|lump| lump.name.eq_ignore_ascii_case(map))
+            .ok_or_else(|| Error::Map(format!("no map {} in {}", map, self.kind)))?;
+        self.lumps[marker + 1..]
+            .iter()
+            .take_while(|lump| MAP_LUMPS.contains(&lump.name.as_str()))
+            .find(|lump| lump.name == name)
+            .map(|lump| lump.data.as_slice())
+            .ok_or_else(|| Error::Map(format!("map {} has no {} lump", map, name)))
+    }
+}
+
+struct Sidedef {
+    upper: String,
+    lower: String,
+    middle: String,
+    sector: usize,
+}
+
+/// Convert `map` to a level. Two-sided linedefs become a pair of portals,
+/// one wall in each sector. Textures and flats are shown as flat colours.
+pub fn load_map(wad: &Wad, map: &str) -> Result<Level, Error> {
+    let vertexes: Vec<(i32, i32)> = records(wad, map, "VERTEXES", 4)?
+        .map(|r| (i16_at(r, 0) as i32, i16_at(r, 2) as i32))
+        .collect();
+
+    let mut sectors: Vec<Sector> = Vec::new();
+    for r in records(wad, map, "SECTORS", 26)? {
+        let ceiling_pic = lump_name(&r[12..20]);
+        sectors.push(Sector {
+            wall_start: 0,
+            wall_end: 0,
+            z1: i16_at(r, 0) as i32,
+            z2: i16_at(r, 2) as i32,
+            floor_color: color_of(&lump_name(&r[4..12])),
+            ceiling_color: if ceiling_pic.starts_with("F_SKY") {
+                SKY
+            } else {
+                color_of(&ceiling_pic)
+            },
+            light: i16_at(r, 20).clamp(0, 255) as u8,
+            special: None,
+            tag: Some(u16_at(r, 24) as u32).filter(|&tag| tag != 0),
+        });
+    }
+
+    let mut sidedefs = Vec::new();
+    for (n, r) in records(wad, map, "SIDEDEFS", 30)?.enumerate() {
+        let sector = u16_at(r, 28) as usize;
+        if sector >= sectors.len() {
+            return Err(Error::Map(format!(
+                "sidedef {} refers to missing sector {}",
+                n, sector
+            )));
+        }
+        sidedefs.push(Sidedef {
+            upper: lump_name(&r[4..12]),
+            lower: lump_name(&r[12..20]),
+            middle: lump_name(&r[20..28]),
+            sector,
+        });
+    }
+
+    // The tags of lines turning sectors into doors and lifts.
+    let mut targets = Vec::new();
+    // Walls grouped by the sector they face into.
+    let mut sector_walls: Vec<Vec<Wall>> = sectors.iter().map(|_| Vec::new()).collect();
+    for (n, r) in records(wad, map, "LINEDEFS", 14)?.enumerate() {
+        let vertex = |i: u16| {
+            vertexes
+                .get(i as usize)
+                .copied()
+                .ok_or_else(|| Error::Map(format!("linedef {} refers to missing vertex {}", n, i)))
+        };
+        let side = |i: u16| match i {
+            NO_SIDEDEF => Ok(None),
+            i => sidedefs.get(i as usize).map(Some).ok_or_else(|| {
+                Error::Map(format!("linedef {} refers to missing sidedef {}", n, i))
+            }),
+        };
+        let (v1, v2) = (vertex(u16_at(r, 0))?, vertex(u16_at(r, 2))?);
+        let (front, back) = (side(u16_at(r, 10))?, side(u16_at(r, 12))?);
+        if v1 == v2 {
+            continue;
+        }
+        if let Some(back) = back.filter(|_| DOOR_SPECIALS.contains(&u16_at(r, 6))) {
+            sectors[back.sector].special = Some(Special::Door);
+        }
+        let tag = u16_at(r, 8) as u32;
+        let line_special = LINE_SPECIALS
+            .iter()
+            .find(|&&(special, ..)| special == u16_at(r, 6));
+        let trigger = line_special.map(|&(_, activation, once, effect)| {
+            let effect = match effect {
+                LineEffect::Door | LineEffect::Lift => Effect::Start { tag },
+                LineEffect::Light(level) => Effect::Light { tag, level },
+                LineEffect::Exit => Effect::Exit,
+            };
+            Trigger {
+                activation,
+                effect,
+                once,
+            }
+        });
+        if let Some(&(_, _, _, effect @ (LineEffect::Door | LineEffect::Lift))) = line_special {
+            targets.push((tag, effect));
+        }
+
+        // The front side lies to the right of v1 -> v2, like the inside of a
+        // clockwise sector; the back side sees the line the other way round.
+        for (side, other, (x1, y1), (x2, y2)) in [(front, back, v1, v2), (back, front, v2, v1)] {
+            if let Some(side) = side {
+                let name = match other {
+                    Some(_) if side.upper != "-" => &side.upper,
+                    Some(_) => &side.lower,
+                    None => &side.middle,
+                };
+                sector_walls[side.sector].push(Wall {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    color: color_of(name),
+                    neighbour: other.map(|o| o.sector),
+                    texture: None,
+                    // Only the front side triggers, as in Doom.
+                    trigger: trigger.filter(|_| x1 == v1.0 && y1 == v1.1),
+                });
+            }
+        }
+    }
+
+    let mut walls = Vec::new();
+    for (sector, mut own) in sectors.iter_mut().zip(sector_walls) {
+        sector.wall_start = walls.len();
+        walls.append(&mut own);
+        sector.wall_end = walls.len();
+    }
+    for (tag, effect) in targets {
+        for s in 0..sectors.len() {
+            if sectors[s].tag != Some(tag) || sectors[s].special.is_some() {
+                continue;
+            }
+            let sector = &sectors[s];
+            sectors[s].special = Some(match effect {
+                LineEffect::Lift => {
+                    let low = walls[sector.wall_start..sector.wall_end]
+                        .iter()
+                        .filter_map(|wall| wall.neighbour)
+                        .map(|n| sectors[n].z1)
+                        .fold(sector.z1, i32::min);
+                    Special::Lift { low }
+                }
+                _ => Special::Door,
+            });
+        }
+    }
+
+    let start = records(wad, map, "THINGS", 10)?
+        .find(|r| i16_at(r, 6) == PLAYER1_START)
+        .ok_or_else(|| Error::Map(format!("map {} has no player 1 start", map)))?;
+    let position = Vec2::new(i16_at(start, 0) as f64, i16_at(start, 2) as f64);
+    let floor = crate::world::sector_at(&sectors, &walls, position).map_or(0, |s| sectors[s].z1);
+    // Doom measures angles counterclockwise from east, the player's angle
+    // runs clockwise from north.
+    let angle = Angle::from_degrees(90.0 - i16_at(start, 4) as f64);
+    let player = Player::new(position.extend(floor as f64), angle);
+
+    Ok(Level {
+        player,
+        sectors,
+        walls,
+        textures: Vec::new(),
+        things: Vec::new(),
+        sprites: Vec::new(),
+        palettes: Vec::new(),
+        texture_sources: Vec::new(),
+        sprite_sources: Vec::new(),
+        palette_file: None,
+        script: Script::default(),
+    })
+}
+
+/// The fixed size records of a map lump.
+fn records<'a>(
+    wad: &'a Wad,
+    map: &str,
+    name: &str,
+    size: usize,
+) -> Result<std::slice::ChunksExact<'a, u8>, Error> {
+    let data = wad.map_lump(map, name)?;
+    if data.len() % size != 0 {
+        return Err(Error::Map(format!(
+            "{} lump of map {} is not a multiple of {} bytes",
+            name, map, size
+        )));
+    }
+    Ok(data.chunks_exact(size))
+}
+
+/// A stable palette colour for a texture or flat name.
+fn color_of(name: &str) -> u8 {
+    let hash = name.bytes().fold(2_166_136_261u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(16_777_619)
+    });
+    COLORS[hash as usize % COLORS.len()]
+}
+
+/// An 8 byte name, cut at the first NUL and upper-cased as Doom does.
+fn lump_name(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_ascii_uppercase()
+}
+
+fn read_i32(bytes: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn i16_at(record: &[u8], at: usize) -> i16 {
+    i16::from_le_bytes([record[at], record[at + 1]])
+}
+
+fn u16_at(record: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([record[at], record[at + 1]])
+}
@@ -0,0 +1,385 @@
+//! Weapons: hitscan shots, bullet puffs and the weapon drawn over the view.
+//!
+//! Like Doom's pistol and shotgun, every weapon here is hitscan: a shot hits
+//! at once whatever first lies along a ray from the player's eyes. Each of
+//! its pellets leaves in the direction the player faces, turned aside by a
+//! random spread, and climbs or falls with `look` so that it goes where the
+//! middle of the view shows. The ray is followed through the sectors as the
+//! player sees them, stopping at
+//!
+//! - a solid wall, or the part of a portal below or above the opening into
+//!   the sector beyond;
+//! - the floor or ceiling of the sector it is in;
+//! - a thing, taken up by an upright cylinder as wide and tall as its sprite.
+//!
+//! A pellet does `damage` times a roll of 1 to 3 to a thing it hits, see
+//! `World::damage_thing`, fires the shoot trigger of a wall it hits, and
+//! leaves a `Puff` where it hit. Pellets going into the sky, through a sky
+//! ceiling or above a portal between two, are lost without a puff.
+//!
+//! Random numbers come from a generator seeded the same in every game, so
+//! the same inputs always give the same shots.
+
+use crate::colormap::{self, FULL_BRIGHT};
+use crate::input::Action;
+use crate::math::{Angle, Vec2, Vec3};
+use crate::mover::crossing;
+use crate::palette::Palette;
+use crate::texture::Texture;
+use crate::world::World;
+use crate::{HEIGHT, SKY, WIDTH};
+
+/// Furthest a shot reaches.
+pub const RANGE: f64 = 2048.0;
+
+/// Ticks a puff lasts, its `PUFF_FRAMES` each showing for an equal share.
+pub const PUFF_TICKS: u32 = 16;
+
+/// Frames of a puff's animation, see `Texture::puff`.
+const PUFF_FRAMES: usize = 4;
+
+/// Height a puff drifts up by per tick.
+const PUFF_RISE: f64 = 0.5;
+
+/// How far short of what they hit puffs appear, so that they aren't
+/// hidden inside it.
+const PUFF_BACK: f64 = 4.0;
+
+/// Ticks the muzzle flash shows for after a shot.
+const FLASH_TICKS: u32 = 4;
+
+/// Rows the weapon drops by as it kicks back from a shot, and rises back
+/// from until it can fire again.
+const RECOIL: u32 = 8;
+
+/// Screen pixels per texel of the weapon and its flash, which are drawn
+/// chunkier than the world, like Doom's.
+const WEAPON_SCALE: i32 = 2;
+
+/// Seed of the random number generator at the start of a game.
+const SEED: u32 = 0x2545_f491;
+
+/// A kind of weapon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weapon {
+    pub name: &'static str,
+    /// Damage of a pellet, before the roll of 1 to 3.
+    pub damage: u32,
+    /// Rays traced per shot.
+    pub pellets: u32,
+    /// Most a pellet strays from the aim, either way, in degrees.
+    pub spread: f64,
+    /// Ticks from one shot to the next while fire is held.
+    pub refire: u32,
+}
+
+pub const PISTOL: Weapon = Weapon {
+    name: "pistol",
+    damage: 5,
+    pellets: 1,
+    spread: 2.8,
+    refire: 14,
+};
+
+pub const SHOTGUN: Weapon = Weapon {
+    name: "shotgun",
+    damage: 5,
+    pellets: 7,
+    spread: 5.6,
+    refire: 37,
+};
+
+/// Every weapon, in the order `Action::NextWeapon` goes through them.
+pub const WEAPONS: [Weapon; 2] = [PISTOL, SHOTGUN];
+
+/// The cloud of dust and sparks a shot throws up where it hits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Puff {
+    /// Position of the middle of the puff.
+    pub position: Vec3,
+    /// Ticks since the shot.
+    pub age: u32,
+}
+
+/// What a shot hits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Wall(usize),
+    Thing(usize),
+    /// The floor of a sector.
+    Floor(usize),
+    /// The ceiling of a sector, never the sky.
+    Ceiling(usize),
+}
+
+/// Where a shot hits, and what.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub point: Vec3,
+    pub target: Target,
+}
+
+/// The player's weapons: the one in hand, how far it is through firing,
+/// the puffs its shots left, and the images to draw them all with.
+pub struct Arsenal {
+    /// Index into `WEAPONS` of the weapon in hand.
+    pub current: usize,
+    /// Ticks until the weapon in hand can fire again. Its firing frames
+    /// show meanwhile, and weapons can't be switched.
+    pub cooldown: u32,
+    pub puffs: Vec<Puff>,
+    /// State of the random number generator spreading and rolling shots.
+    pub seed: u32,
+    /// Images of `WEAPONS`, their muzzle flash and the frames of a puff.
+    pub sprites: Vec<Texture>,
+    pub flash: Texture,
+    pub puff_frames: Vec<Texture>,
+}
+
+impl Arsenal {
+    /// The pistol in hand, ready to fire, with images in `palette`.
+    pub fn new(palette: &Palette) -> Self {
+        let metal = [90, 90, 100];
+        let mut sprites = vec![
+            Texture::pistol(metal),
+            Texture::shotgun(metal, [120, 80, 40]),
+        ];
+        let mut flash = Texture::flash([255, 170, 40]);
+        let mut puff_frames: Vec<_> = (0..PUFF_FRAMES).map(Texture::puff).collect();
+        for texture in sprites
+            .iter_mut()
+            .chain(&mut puff_frames)
+            .chain([&mut flash])
+        {
+            texture.quantize(palette);
+        }
+        Arsenal {
+            current: 0,
+            cooldown: 0,
+            puffs: Vec::new(),
+            seed: SEED,
+            sprites,
+            flash,
+            puff_frames,
+        }
+    }
+
+    /// The weapon in hand.
+    pub fn weapon(&self) -> Weapon {
+        WEAPONS[self.current]
+    }
+
+    /// The next random number, in `0..256` like Doom's.
+    pub fn random(&mut self) -> u32 {
+        // Xorshift.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed >> 24
+    }
+
+    /// The image of a puff `age` ticks old.
+    pub fn puff_frame(&self, age: u32) -> &Texture {
+        let frame = age as usize * PUFF_FRAMES / PUFF_TICKS as usize;
+        &self.puff_frames[frame.min(PUFF_FRAMES - 1)]
+    }
+}
+
+impl World {
+    /// Switch and fire weapons as the actions say, and age the puffs.
+    /// Holding fire shoots again every `refire` ticks.
+    pub(crate) fn update_weapon(&mut self) {
+        for puff in &mut self.arsenal.puffs {
+            puff.age += 1;
+            puff.position.z += PUFF_RISE;
+        }
+        self.arsenal.puffs.retain(|puff| puff.age < PUFF_TICKS);
+
+        self.arsenal.cooldown = self.arsenal.cooldown.saturating_sub(1);
+        if self.arsenal.cooldown > 0 {
+            return;
+        }
+        if self.actions.pressed(Action::NextWeapon) {
+            self.arsenal.current = (self.arsenal.current + 1) % WEAPONS.len();
+        } else if self.actions.held(Action::Fire) {
+            self.shoot();
+            self.arsenal.cooldown = self.arsenal.weapon().refire;
+        }
+    }
+
+    /// Fire the weapon in hand from the player's eyes: trace its pellets,
+    /// hurt and trigger what they hit and leave puffs there.
+    pub fn shoot(&mut self) {
+        let weapon = self.arsenal.weapon();
+        let eye = self.player.camera().position;
+        // The middle of the view, as `render` shears it.
+        let slope = -self.player.look / 32.0;
+        for _ in 0..weapon.pellets {
+            let stray = self.arsenal.random() as f64 - self.arsenal.random() as f64;
+            let angle = self.player.angle + Angle::from_degrees(stray / 255.0 * weapon.spread);
+            let hit = match self.trace(eye, angle, slope) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let damage = weapon.damage * (1 + self.arsenal.random() % 3);
+            match hit.target {
+                Target::Thing(t) => self.damage_thing(t, damage),
+                Target::Wall(w) => self.shoot_wall(w),
+                Target::Floor(_) | Target::Ceiling(_) => {}
+            }
+            let back =
+                Vec2::from_angle(angle).extend(slope) * (PUFF_BACK / (1.0 + slope * slope).sqrt());
+            self.arsenal.puffs.push(Puff {
+                position: hit.point - back,
+                age: 0,
+            });
+        }
+    }
+
+    /// Take `damage` off the health of thing `t`, destroying it once none
+    /// is left. Things without health take no damage.
+    pub fn damage_thing(&mut self, t: usize, damage: u32) {
+        if let Some(health) = &mut self.things[t].health {
+            *health = health.saturating_sub(damage);
+        }
+    }
+
+    /// What a shot from `origin` towards `angle` hits within `RANGE`, if
+    /// anything. It climbs by `slope` per unit of distance on the map.
+    pub fn trace(&self, origin: Vec3, angle: Angle, slope: f64) -> Option<Hit> {
+        let direction = Vec2::from_angle(angle);
+        let (mut distance, mut target) = self.trace_scenery(origin, direction, slope);
+        for (t, thing) in self.things.iter().enumerate() {
+            if thing.destroyed() {
+                continue;
+            }
+            let sprite = &self.sprites[thing.sprite];
+            let radius = sprite.width as f64 / 2.0;
+            let offset = thing.position.xy() - origin.xy();
+            let (along, across) = (offset.dot(direction), offset.dot(direction.perp()));
+            if along <= 0.0 || across.abs() >= radius {
+                continue;
+            }
+            // Where the ray enters the cylinder.
+            let entry = (along - (radius * radius - across * across).sqrt()).max(0.0);
+            let z = origin.z + slope * entry;
+            if entry < distance
+                && z >= thing.position.z
+                && z <= thing.position.z + sprite.height as f64
+            {
+                distance = entry;
+                target = Some(Target::Thing(t));
+            }
+        }
+        target.map(|target| Hit {
+            point: (origin.xy() + direction * distance).extend(origin.z + slope * distance),
+            target,
+        })
+    }
+
+    /// How far a shot goes before it hits a wall, floor or ceiling, and
+    /// which. No target if it goes into the sky or nothing is in range.
+    fn trace_scenery(&self, origin: Vec3, direction: Vec2, slope: f64) -> (f64, Option<Target>) {
+        let start = origin.xy();
+        let end = start + direction * RANGE;
+        let mut sector = match self.sector_at(start) {
+            Some(sector) => sector,
+            None => return (0.0, None),
+        };
+        let mut crossed = Vec::new();
+        for (s, here) in self.sectors.iter().enumerate() {
+            for w in here.wall_start..here.wall_end {
+                if let Some(t) = crossing(&self.walls[w], start, end) {
+                    crossed.push((t * RANGE, s, w));
+                }
+            }
+        }
+        crossed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut crossed = crossed.into_iter();
+
+        let z = |distance: f64| origin.z + slope * distance;
+        // Where the shot reaches a floor or ceiling at `height`. A level shot
+        // only meets one that has moved past the eye, and does so at once.
+        let reaching = |height: i32| {
+            if slope == 0.0 {
+                0.0
+            } else {
+                ((height as f64 - origin.z) / slope).max(0.0)
+            }
+        };
+        loop {
+            // Walls of other sectors crossed on the way are behind the
+            // sector the shot is in, or beyond it.
+            let next = crossed.by_ref().find(|&(_, s, _)| s == sector);
+            let distance = next.map_or(RANGE, |(distance, _, _)| distance);
+            let here = &self.sectors[sector];
+            if z(distance) < here.z1 as f64 {
+                return (reaching(here.z1), Some(Target::Floor(sector)));
+            }
+            if z(distance) > here.z2 as f64 {
+                let ceiling = (here.ceiling_color != SKY).then_some(Target::Ceiling(sector));
+                return (reaching(here.z2), ceiling);
+            }
+            let w = match next {
+                Some((_, _, w)) => w,
+                None => return (RANGE, None),
+            };
+            let beyond = match self.walls[w].neighbour {
+                Some(n) => &self.sectors[n],
+                None => return (distance, Some(Target::Wall(w))),
+            };
+            let height = z(distance);
+            if height > beyond.z1 as f64 && height < beyond.z2 as f64 {
+                sector = self.walls[w].neighbour.unwrap();
+            } else if height >= beyond.z2 as f64
+                && here.ceiling_color == SKY
+                && beyond.ceiling_color == SKY
+            {
+                return (distance, None);
+            } else {
+                return (distance, Some(Target::Wall(w)));
+            }
+        }
+    }
+
+    /// Draw the weapon in hand over the palette indices of a rendered view,
+    /// at the bottom in the middle and shaded by the light of the player's
+    /// sector. Just after a shot it is lit by its muzzle flash; it then
+    /// drops back and rises again until it can fire once more.
+    pub fn draw_weapon(&self, frame: &mut [u8]) {
+        let arsenal = &self.arsenal;
+        let sprite = &arsenal.sprites[arsenal.current];
+        let refire = arsenal.weapon().refire;
+        let flashing = arsenal.cooldown > 0 && refire - arsenal.cooldown < FLASH_TICKS;
+        let light = match self.player.sector {
+            Some(s) if !flashing => self.sectors[s].light,
+            _ => FULL_BRIGHT,
+        };
+        let recoil = (RECOIL * arsenal.cooldown / refire) as i32;
+        let (width, height) = (sprite.width as i32, sprite.height as i32);
+        let left = WIDTH as i32 / 2 - width * WEAPON_SCALE / 2;
+        let top = HEIGHT as i32 - height * WEAPON_SCALE + recoil;
+        if flashing {
+            let flash = &arsenal.flash;
+            let (width, height) = (flash.width as i32, flash.height as i32);
+            let left = WIDTH as i32 / 2 - width * WEAPON_SCALE / 2;
+            self.blit(frame, flash, left, top - (height - 2) * WEAPON_SCALE, 0);
+        }
+        self.blit(frame, sprite, left, top, colormap::level(light, 0.0));
+    }
+
+    /// Draw `sprite` `WEAPON_SCALE` times its size with its top left corner
+    /// at `(left, top)` in colormap `level`, leaving out its transparent
+    /// texels and what is off screen.
+    fn blit(&self, frame: &mut [u8], sprite: &Texture, left: i32, top: i32, level: usize) {
+        for y in top.max(0)..(top + sprite.height as i32 * WEAPON_SCALE).min(HEIGHT as i32) {
+            let v = (y - top) / WEAPON_SCALE;
+            for x in left.max(0)..(left + sprite.width as i32 * WEAPON_SCALE).min(WIDTH as i32) {
+                let u = (x - left) / WEAPON_SCALE;
+                if let Some(color) = sprite.texel(u, v) {
+                    frame[(y * WIDTH as i32 + x) as usize] = self.colormap.shade(color, level);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+
+use crate::automap::Automap;
+use crate::bsp::Bsp;
+use crate::colormap::Colormap;
+use crate::input::{Action, Actions};
+use crate::level::Level;
+use crate::math::{Angle, Vec2, Vec3};
+use crate::mover::{self, Mover, Special};
+use crate::palette::{self, Palette, BONUS_PALETTES, DAMAGE_PALETTES};
+use crate::render::Camera;
+use crate::script::{self, Event, Script, Value};
+use crate::texture::Texture;
+use crate::trigger::Trigger;
+use crate::weapon::Arsenal;
+
+/// Radius of the circle the player takes up on the map.
+pub const PLAYER_RADIUS: f64 = 8.0;
+
+/// Headroom the player needs to enter a sector.
+pub const PLAYER_HEIGHT: f64 = 32.0;
+
+/// Headroom the player needs while crouching.
+pub const CROUCH_HEIGHT: f64 = 18.0;
+
+/// Height of the player's eyes above their feet.
+pub const EYE_HEIGHT: f64 = 20.0;
+
+/// Height of the player's eyes while crouching.
+pub const CROUCH_EYE_HEIGHT: f64 = 10.0;
+
+/// Highest ledge the player can step up onto.
+pub const MAX_STEP: f64 = 24.0;
+
+/// Distance walked per tick.
+const MOVE_SPEED: f64 = 5.0;
+
+/// Angle turned per tick, 3 degrees.
+const TURN_SPEED: Angle = Angle(0x0222_2222);
+
+/// Vertical speed lost per tick while falling.
+const GRAVITY: f64 = 1.0;
+
+/// Vertical speed at the start of a jump.
+const JUMP_SPEED: f64 = 6.0;
+
+/// Vertical speed while flying.
+const FLY_SPEED: f64 = 4.0;
+
+/// Furthest the player can look up or down, as `look` (45 degrees).
+pub const MAX_LOOK: f64 = 32.0;
+
+/// `look` per degree of pitch near the horizon.
+const LOOK_PER_DEGREE: f64 = MAX_LOOK / 45.0;
+
+/// How fast the eyes move towards their resting height, per tick.
+const VIEW_HEIGHT_SPEED: f64 = 2.0;
+
+/// Most damage remembered for the red flash, in ticks of fading.
+const MAX_DAMAGE_COUNT: u32 = 100;
+
+/// The player's health at the start.
+pub const MAX_HEALTH: u32 = 100;
+
+/// Ticks of yellow flash added by each pickup.
+const BONUS_ADD: u32 = 6;
+
+/// Passes pushing the player out of walls per movement step; corners need more than one.
+const COLLISION_PASSES: usize = 4;
+
+/// A level being played: its sectors and things, the player in it, and
+/// everything that changes as it runs. `update` advances it by one tick of
+/// the fixed-rate simulation, and `render` draws the player's view of it.
+pub struct World {
+    pub actions: Actions,
+    pub player: Player,
+    /// The player's camera before the last tick, to interpolate from.
+    previous: Camera,
+    pub sectors: Vec<Sector>,
+    pub walls: Vec<Wall>,
+    /// The walls partitioned for rendering, built from `sectors` and `walls`.
+    pub bsp: Bsp,
+    pub textures: Vec<Texture>,
+    pub things: Vec<Thing>,
+    /// Images things are drawn with.
+    pub sprites: Vec<Texture>,
+    /// The normal palette and its flashes, `palette::PALETTES` in all.
+    pub palettes: Vec<Palette>,
+    /// Shading tables for light and distance, in the normal palette.
+    pub colormap: Colormap,
+    pub mouse: MouseSettings,
+    pub automap: Automap,
+    /// The sectors with a special and how they are moving.
+    pub movers: Vec<Mover>,
+    /// Set once a trigger ends the level.
+    pub exited: bool,
+    /// The level's script handlers, and the values of their variables.
+    pub script: Script,
+    pub script_vars: HashMap<String, Value>,
+    /// Messages from scripts, not yet shown.
+    pub messages: Vec<String>,
+    /// Errors of scripts that have stopped, not yet reported.
+    pub script_errors: Vec<script::Error>,
+    /// The names of `sprites`, for scripts to spawn things by.
+    pub sprite_names: Vec<String>,
+    /// The player's weapons and the puffs of their shots.
+    pub arsenal: Arsenal,
+}
+
+/// How mouse motion turns the player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseSettings {
+    /// Degrees turned per count of mouse motion, horizontally and vertically.
+    pub sensitivity: f64,
+    /// Look down when the mouse moves away from the player.
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        MouseSettings {
+            sensitivity: 0.15,
+            invert_y: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Player {
+    /// Position of the player's feet.
+    pub position: Vec3,
+    pub angle: Angle,
+    /// Vertical shear of the view: the height seen straight ahead falls by
+    /// `look / 32` per unit of distance, so negative values look up. Kept
+    /// within `-MAX_LOOK..=MAX_LOOK`.
+    pub look: f64,
+    /// Vertical speed, upwards.
+    pub vz: f64,
+    /// Height of the eyes above the feet. Lags behind when stepping up so the
+    /// view rises smoothly.
+    pub view_height: f64,
+    pub crouching: bool,
+    /// Flying players ignore gravity and move up and down with `look` held.
+    pub flying: bool,
+    /// The sector the player stands in, `None` outside of the level.
+    pub sector: Option<usize>,
+    pub health: u32,
+    /// Ticks of red flash left from taking damage.
+    pub damage_count: u32,
+    /// Ticks of yellow flash left from picking things up.
+    pub bonus_count: u32,
+}
+
+impl Player {
+    /// A player with their feet at `position`, facing `angle`.
+    pub fn new(position: Vec3, angle: Angle) -> Self {
+        Player {
+            position,
+            angle,
+            look: 0.0,
+            vz: 0.0,
+            view_height: EYE_HEIGHT,
+            crouching: false,
+            flying: false,
+            sector: None,
+            health: MAX_HEALTH,
+            damage_count: 0,
+            bonus_count: 0,
+        }
+    }
+
+    /// Headroom the player currently needs.
+    pub fn height(&self) -> f64 {
+        if self.crouching {
+            CROUCH_HEIGHT
+        } else {
+            PLAYER_HEIGHT
+        }
+    }
+}
+
+/// An object in the world, such as an enemy, an item or a decoration. It is
+/// drawn as a billboard: its sprite always faces the camera, one texel per
+/// unit, standing on its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thing {
+    /// Position of the bottom centre of the sprite.
+    pub position: Vec3,
+    /// The direction the thing faces.
+    pub angle: Angle,
+    /// Index into `World::sprites`.
+    pub sprite: usize,
+    /// Damage the thing takes before it is destroyed; things without health
+    /// can't be destroyed.
+    pub health: Option<u32>,
+}
+
+impl Thing {
+    /// Whether shots have taken all of the thing's health. Destroyed things
+    /// stay in `World::things`, neither drawn nor hit, so that the indices
+    /// scripts hold keep naming the same things.
+    pub fn destroyed(&self) -> bool {
+        self.health == Some(0)
+    }
+}
+
+/// A wall seen from inside its sector; walls of a sector run clockwise.
+#[derive(Clone)]
+pub struct Wall {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+    pub color: u8,
+    /// The sector on the other side, if the wall is a portal.
+    pub neighbour: Option<usize>,
+    /// Drawn instead of the flat `color` when set.
+    pub texture: Option<TextureMapping>,
+    /// What the wall does when crossed, used or shot, see `trigger`.
+    pub trigger: Option<Trigger>,
+}
+
+/// How a texture is laid onto a wall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureMapping {
+    /// Index into `World::textures`.
+    pub texture: usize,
+    /// Texel offsets of the wall's start point and of its sector's ceiling.
+    pub u_offset: f64,
+    pub v_offset: f64,
+    /// Texels per world unit, along the wall and downwards.
+    pub u_scale: f64,
+    pub v_scale: f64,
+}
+
+#[derive(Clone)]
+pub struct Sector {
+    pub wall_start: usize,
+    pub wall_end: usize,
+    /// Floor height.
+    pub z1: i32,
+    /// Ceiling height.
+    pub z2: i32,
+    pub floor_color: u8,
+    pub ceiling_color: u8,
+    /// Light level, from 0 (dark) to `colormap::FULL_BRIGHT`.
+    pub light: u8,
+    /// How the sector's floor or ceiling moves, see `mover`.
+    pub special: Option<Special>,
+    /// Names the sector for the triggers acting on it; sectors may share one.
+    pub tag: Option<u32>,
+}
+
+impl Sector {
+    /// Whether the sector's walls, all of `walls`, enclose `point`. A point
+    /// inside a loop within the sector, a pillar, is not in the sector.
+    pub fn contains(&self, walls: &[Wall], point: Vec2) -> bool {
+        let Vec2 { x, y } = point;
+        let mut inside = false;
+        for wall in &walls[self.wall_start..self.wall_end] {
+            if (wall.y1 as f64 > y) != (wall.y2 as f64 > y) {
+                let cross_x = wall.x1 as f64
+                    + (y - wall.y1 as f64) * (wall.x2 - wall.x1) as f64
+                        / (wall.y2 - wall.y1) as f64;
+                if x < cross_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+/// Index of the sector whose walls enclose the point, if any.
+pub fn sector_at(sectors: &[Sector], walls: &[Wall], point: Vec2) -> Option<usize> {
+    sectors
+        .iter()
+        .position(|sector| sector.contains(walls, point))
+}
+
+impl World {
+    /// Create a new `World` instance that can draw the level.
+    pub fn new(level: Level) -> Self {
+        let palettes = if level.palettes.is_empty() {
+            palette::flashes(Palette::new())
+        } else {
+            level.palettes
+        };
+        let mut textures = level.textures;
+        let mut sprites = level.sprites;
+        for texture in textures.iter_mut().chain(&mut sprites) {
+            texture.quantize(&palettes[0]);
+        }
+        let mut world = Self {
+            actions: Actions::default(),
+            previous: level.player.camera(),
+            player: level.player,
+            bsp: Bsp::build(&level.sectors, &level.walls),
+            automap: Automap::new(level.walls.len()),
+            movers: mover::movers(&level.sectors, &level.walls),
+            sectors: level.sectors,
+            walls: level.walls,
+            textures,
+            things: level.things,
+            sprites,
+            colormap: Colormap::new(&palettes[0]),
+            arsenal: Arsenal::new(&palettes[0]),
+            palettes,
+            mouse: MouseSettings::default(),
+            exited: false,
+            script: level.script,
+            script_vars: HashMap::new(),
+            messages: Vec::new(),
+            script_errors: Vec::new(),
+            sprite_names: level
+                .sprite_sources
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+        };
+        world.player.sector = world.sector_at(world.player.position.xy());
+        world.run_script(Event::Start);
+        world
+    }
+
+    /// Flash the screen red for `damage` points of damage.
+    pub fn flash_damage(&mut self, damage: u32) {
+        self.player.damage_count = self
+            .player
+            .damage_count
+            .saturating_add(damage)
+            .min(MAX_DAMAGE_COUNT);
+    }
+
+    /// Take `damage` points off the player's health and flash for it.
+    pub fn hurt(&mut self, damage: u32) {
+        self.player.health = self.player.health.saturating_sub(damage);
+        self.flash_damage(damage);
+    }
+
+    /// Flash the screen yellow for picking something up.
+    pub fn flash_bonus(&mut self) {
+        self.player.bonus_count = self.player.bonus_count.saturating_add(BONUS_ADD);
+    }
+
+    /// The palette to show the frame in: red while hurt, yellow after a
+    /// pickup, fading as the counts run down.
+    pub fn palette(&self) -> &Palette {
+        let flash = |count: u32, palettes: std::ops::Range<usize>| {
+            let step = ((count as usize + 7) >> 3).min(palettes.len());
+            palettes.start + step - 1
+        };
+        let index = if self.player.damage_count > 0 {
+            flash(self.player.damage_count, DAMAGE_PALETTES)
+        } else if self.player.bonus_count > 0 {
+            flash(self.player.bonus_count, BONUS_PALETTES)
+        } else {
+            0
+        };
+        &self.palettes[index]
+    }
+
+    /// Index of the sector containing the point, if any.
+    pub fn sector_at(&self, point: Vec2) -> Option<usize> {
+        sector_at(&self.sectors, &self.walls, point)
+    }
+
+    /// Whether the player, at their current height, can move into sector `to`.
+    fn passable(&self, to: usize) -> bool {
+        let to = &self.sectors[to];
+        let (floor, ceiling) = (to.z1 as f64, to.z2 as f64);
+        let z = self.player.position.z;
+        floor - z <= MAX_STEP && ceiling - floor.max(z) >= self.player.height()
+    }
+
+    /// Move the player by `delta` on the map, pushing them out of every wall
+    /// they would overlap. Only the part of the motion into a wall is
+    /// removed, so the player slides along it.
+    fn move_player(&mut self, delta: Vec2) {
+        // Short steps keep the player from passing through thin walls.
+        let steps = (delta.length() / (PLAYER_RADIUS / 2.0)).ceil().max(1.0);
+        for _ in 0..steps as usize {
+            let here = self.sector_at(self.player.position.xy());
+            let mut point = self.player.position.xy() + delta * (1.0 / steps);
+            for _ in 0..COLLISION_PASSES {
+                let mut pushed = false;
+                for (s, sector) in self.sectors.iter().enumerate() {
+                    for wall in &self.walls[sector.wall_start..sector.wall_end] {
+                        let blocks = match (wall.neighbour, here) {
+                            (None, _) => true,
+                            // Each portal is also a wall of the sector beyond,
+                            // only the side facing the player counts.
+                            (Some(n), Some(here)) if here == s => !self.passable(n),
+                            (Some(_), _) => false,
+                        };
+                        if blocks {
+                            if let Some(push) = push_out(wall, point) {
+                                point += push;
+                                pushed = true;
+                            }
+                        }
+                    }
+                }
+                if !pushed {
+                    break;
+                }
+            }
+            self.player.position.x = point.x;
+            self.player.position.y = point.y;
+        }
+    }
+
+    /// The player's camera `alpha` of the way from the previous tick to the
+    /// last one.
+    pub fn camera(&self, alpha: f64) -> Camera {
+        self.previous.lerp(&self.player.camera(), alpha)
+    }
+
+    /// Turn the player by raw mouse motion `(dx, dy)`, in counts; positive
+    /// `dy` is towards the player.
+    ///
+    /// The turn shows at once rather than at the next tick: it is applied to
+    /// the camera being interpolated from as well.
+    pub fn mouse_moved(&mut self, dx: f64, dy: f64) {
+        let sensitivity = self.mouse.sensitivity;
+        let turn = Angle::from_degrees(dx * sensitivity);
+        let dy = if self.mouse.invert_y { -dy } else { dy };
+        let look =
+            (self.player.look + dy * sensitivity * LOOK_PER_DEGREE).clamp(-MAX_LOOK, MAX_LOOK);
+
+        self.previous.angle += turn;
+        self.previous.look += look - self.player.look;
+        self.player.angle += turn;
+        self.player.look = look;
+    }
+
+    /// Advance the `World` by one tick.
+    pub fn update(&mut self) {
+        self.previous = self.player.camera();
+        self.automap
+            .update(&self.actions, self.player.position.xy());
+        // While the automap pans, the movement actions are its own.
+        let moving = !self.automap.panning();
+        if moving && self.actions.held(Action::TurnLeft) && !self.actions.held(Action::Look) {
+            self.player.angle -= TURN_SPEED;
+        }
+        if moving && self.actions.held(Action::TurnRight) && !self.actions.held(Action::Look) {
+            self.player.angle += TURN_SPEED;
+        }
+
+        let forward = Vec2::from_angle(self.player.angle) * MOVE_SPEED;
+        let mut motion = Vec2::ZERO;
+        if self.actions.held(Action::Forward) && !self.actions.held(Action::Look) {
+            motion += forward;
+        }
+        if self.actions.held(Action::Backward) && !self.actions.held(Action::Look) {
+            motion -= forward;
+        }
+        if self.actions.held(Action::StrafeLeft) {
+            motion -= forward.perp();
+        }
+        if self.actions.held(Action::StrafeRight) {
+            motion += forward.perp();
+        }
+        if moving && motion != Vec2::ZERO {
+            let from = self.player.position.xy();
+            self.move_player(motion);
+            self.walk_triggers(from, self.player.position.xy());
+        }
+        let sector = self.sector_at(self.player.position.xy());
+        let entered = sector.filter(|_| sector != self.player.sector);
+        self.player.sector = sector;
+        if let Some(s) = entered {
+            self.run_script(Event::Enter(s));
+        }
+
+        if self.actions.held(Action::TurnLeft) && self.actions.held(Action::Look) {
+            self.player.look = (self.player.look - 1.0).max(-MAX_LOOK);
+        }
+        if self.actions.held(Action::TurnRight) && self.actions.held(Action::Look) {
+            self.player.look = (self.player.look + 1.0).min(MAX_LOOK);
+        }
+
+        if self.actions.pressed(Action::Fly) {
+            self.player.flying = !self.player.flying;
+        }
+        if self.actions.pressed(Action::Use) {
+            if let Some(wall) = self.trigger_in_reach() {
+                self.fire(wall);
+            } else if let Some(mover) = self.mover_in_reach() {
+                self.activate(mover);
+            }
+        }
+        self.update_weapon();
+        self.update_movers();
+        self.update_height();
+        self.player.damage_count = self.player.damage_count.saturating_sub(1);
+        self.player.bonus_count = self.player.bonus_count.saturating_sub(1);
+        self.actions.end_tick();
+    }
+
+    /// Follow the floor of the player's sector: fall, land, step up, jump,
+    /// crouch and fly, keeping the player's head below the ceiling.
+    fn update_height(&mut self) {
+        // Outside of the level there is nothing to stand on or fall to.
+        let (floor, ceiling) = match self.player.sector {
+            Some(s) => (self.sectors[s].z1 as f64, self.sectors[s].z2 as f64),
+            None => return,
+        };
+        let player = &mut self.player;
+
+        // Standing up needs room above the head.
+        if self.actions.held(Action::Crouch) {
+            player.crouching = true;
+        } else if player.crouching && ceiling - player.position.z >= PLAYER_HEIGHT {
+            player.crouching = false;
+        }
+
+        let z = player.position.z;
+        if player.flying {
+            player.vz = 0.0;
+            if self.actions.held(Action::Forward) && self.actions.held(Action::Look) {
+                player.position.z += FLY_SPEED;
+            }
+            if self.actions.held(Action::Backward) && self.actions.held(Action::Look) {
+                player.position.z -= FLY_SPEED;
+            }
+        } else {
+            let on_ground = player.position.z <= floor;
+            if on_ground && self.actions.held(Action::Jump) && !player.crouching {
+                player.vz = JUMP_SPEED;
+            }
+            player.vz -= GRAVITY;
+            player.position.z += player.vz;
+        }
+
+        if player.position.z < floor {
+            // Stepped up or landed: the feet snap to the floor, the eyes
+            // follow from where they were.
+            if player.vz >= -GRAVITY {
+                player.view_height -= (floor - z).max(0.0);
+            }
+            player.position.z = floor;
+            player.vz = 0.0;
+        }
+        if player.position.z + player.height() > ceiling {
+            player.position.z = (ceiling - player.height()).max(floor);
+            player.vz = player.vz.min(0.0);
+        }
+
+        let rest = if player.crouching {
+            CROUCH_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        };
+        player.view_height = if player.view_height < rest {
+            (player.view_height + VIEW_HEIGHT_SPEED).min(rest)
+        } else {
+            (player.view_height - VIEW_HEIGHT_SPEED).max(rest)
+        };
+    }
+}
+
+/// The shortest displacement moving a circle of `PLAYER_RADIUS` around
+/// `point` off `wall`, or `None` if they don't overlap.
+pub(crate) fn push_out(wall: &Wall, point: Vec2) -> Option<Vec2> {
+    let start = Vec2::new(wall.x1 as f64, wall.y1 as f64);
+    let along = Vec2::new(wall.x2 as f64, wall.y2 as f64) - start;
+    let t = ((point - start).dot(along) / along.dot(along)).clamp(0.0, 1.0);
+    let offset = point - (start + along * t);
+    let distance = offset.length();
+    if distance >= PLAYER_RADIUS {
+        return None;
+    }
+    match offset.normalize() {
+        Some(direction) => Some(direction * (PLAYER_RADIUS - distance)),
+        // Exactly on the wall: push towards the inside of its sector, on its right.
+        None => along.normalize().map(|along| along.perp() * PLAYER_RADIUS),
+    }
+}
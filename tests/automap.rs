@@ -0,0 +1,79 @@
+//! The automap overlay.
+
+mod common;
+
+use doomrust::{Action, World, HEIGHT, WIDTH};
+
+use common::default_world;
+
+fn press(world: &mut World, action: Action) {
+    world.actions.set(action, true);
+    world.update();
+    world.actions.set(action, false);
+}
+
+#[test]
+fn the_automap_key_toggles_it() {
+    let mut world = default_world();
+    assert!(!world.automap.active);
+    press(&mut world, Action::Automap);
+    assert!(world.automap.active);
+    press(&mut world, Action::Automap);
+    assert!(!world.automap.active);
+}
+
+#[test]
+fn walls_are_seen_once_drawn() {
+    let mut world = default_world();
+    assert!(world.automap.seen.iter().all(|&seen| !seen));
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    world.draw(&mut frame, 1.0);
+    let seen = world.automap.seen.iter().filter(|&&seen| seen).count();
+    assert!(seen > 0 && seen < world.walls.len(), "{} seen", seen);
+}
+
+#[test]
+fn the_map_shows_the_player_in_the_middle() {
+    let mut world = default_world();
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    world.draw(&mut frame, 1.0);
+    press(&mut world, Action::Automap);
+    let mut map = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    world.draw(&mut map, 1.0);
+    assert_ne!(map, frame);
+    let centre = ((HEIGHT / 2 * WIDTH + WIDTH / 2) * 4) as usize;
+    assert_eq!(map[centre..centre + 3], [255, 255, 255]);
+}
+
+#[test]
+fn without_follow_the_movement_keys_pan() {
+    let mut world = default_world();
+    press(&mut world, Action::Automap);
+    press(&mut world, Action::Follow);
+    let (position, centre) = (world.player.position, world.automap.center);
+    assert_eq!(centre, position.xy());
+    press(&mut world, Action::Forward);
+    assert_eq!(world.player.position, position);
+    assert!(world.automap.center.y > centre.y);
+
+    press(&mut world, Action::Follow);
+    press(&mut world, Action::Forward);
+    assert!(world.player.position.y > position.y);
+}
+
+#[test]
+fn zoom_is_limited() {
+    let mut world = default_world();
+    press(&mut world, Action::Automap);
+    world.actions.set(Action::ZoomIn, true);
+    for _ in 0..1000 {
+        world.update();
+    }
+    let closest = world.automap.scale;
+    world.update();
+    assert_eq!(world.automap.scale, closest);
+    world.actions.set(Action::ZoomIn, false);
+    world.actions.set(Action::ZoomOut, true);
+    world.update();
+    assert!(world.automap.scale < closest);
+}
@@ -0,0 +1,93 @@
+//! The BSP tree and rendering in front-to-back order.
+
+use std::path::Path;
+
+use doomrust::bsp::Bsp;
+use doomrust::math::{Angle, Vec2, Vec3};
+use doomrust::{level, Camera, DepthBuffer, World, DEFAULT_LEVEL, HEIGHT, WIDTH};
+
+/// An L-shaped room, one concave sector. Its walls start with the far east
+/// wall of the southern arm, so drawing them in order draws it before the
+/// nearer wall of the inner corner that hides it from the north-west.
+const L_ROOM: &str = "doomrust-level 1\n\
+                      player 50 150 0 90\n\
+                      sector 0 64 9 8\n\
+                      wall 200 100 200 0 2\n\
+                      wall 200 0 0 0 10\n\
+                      wall 0 0 0 200 10\n\
+                      wall 0 200 100 200 10\n\
+                      wall 100 200 100 100 1\n\
+                      wall 100 100 200 100 10\n";
+
+fn check_convex(bsp: &Bsp) {
+    for subsector in &bsp.subsectors {
+        let segs = &bsp.segs[subsector.segs.clone()];
+        assert!(!segs.is_empty());
+        for seg in segs {
+            assert_eq!(seg.sector, subsector.sector);
+            let inside = (seg.end - seg.start).perp();
+            for other in segs {
+                for point in [other.start, other.end] {
+                    assert!((point - seg.start).dot(inside) > -1e-6, "{:?}", subsector);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn subsectors_are_convex_and_cover_every_wall() {
+    for source in [DEFAULT_LEVEL, L_ROOM] {
+        let level = level::parse(source, Path::new(".")).unwrap();
+        let bsp = Bsp::build(&level.sectors, &level.walls);
+        check_convex(&bsp);
+        for w in 0..level.walls.len() {
+            let covered: f64 = bsp
+                .segs
+                .iter()
+                .filter(|seg| seg.wall == w)
+                .map(|seg| seg.s2 - seg.s1)
+                .sum();
+            assert!((covered - 1.0).abs() < 1e-9, "wall {}", w);
+        }
+    }
+}
+
+#[test]
+fn concave_sectors_are_split() {
+    let level = level::parse(L_ROOM, Path::new(".")).unwrap();
+    let bsp = Bsp::build(&level.sectors, &level.walls);
+    assert!(bsp.subsectors.len() >= 2);
+    assert!(bsp.segs.len() > level.walls.len());
+}
+
+#[test]
+fn traversal_starts_at_the_eye_and_visits_everything_once() {
+    let level = level::parse(DEFAULT_LEVEL, Path::new(".")).unwrap();
+    let bsp = Bsp::build(&level.sectors, &level.walls);
+    let eye = Vec2::new(70.0, -110.0);
+    let order: Vec<usize> = bsp.front_to_back(eye).collect();
+    assert_eq!(order[0], bsp.subsector_at(eye).unwrap());
+    let mut sorted = order.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..bsp.subsectors.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn nearer_walls_of_concave_sectors_hide_further_ones() {
+    let world = World::new(level::parse(L_ROOM, Path::new(".")).unwrap());
+    let camera = Camera {
+        position: Vec3::new(50.0, 150.0, 20.0),
+        angle: Angle::from_degrees(90.0),
+        look: 0.0,
+    };
+    let mut frame = vec![0; (WIDTH * HEIGHT) as usize];
+    let mut depth = DepthBuffer::new();
+    world.render_depth(&camera, &mut frame, &mut depth);
+    // 30 degrees right of straight ahead, the inner corner's wall is 50
+    // units ahead, with the east wall of the southern arm behind it.
+    let x = WIDTH as i32 / 2 + (200.0 * 30f64.to_radians().tan()) as i32;
+    assert!((depth.column(x) - 50.0).abs() < 1.0, "{}", depth.column(x));
+    let wall = world.colormap.shade(1, 0);
+    assert_eq!(frame[(HEIGHT / 2 * WIDTH) as usize + x as usize], wall);
+}
@@ -0,0 +1,100 @@
+//! Walls stopping the player, and sliding along them.
+
+use std::path::Path;
+
+use doomrust::math::Angle;
+use doomrust::world::PLAYER_RADIUS;
+use doomrust::{level, Action, World};
+
+/// A room 128 square with a doorway in its east wall onto a ledge room,
+/// whose floor and ceiling are `floor` and `ceiling`. The player stands in
+/// the middle of the room facing north.
+fn ledge(floor: i32, ceiling: i32) -> World {
+    let source = format!(
+        "\
+doomrust-level 1
+player 64 64 0 0
+
+sector 0 128 9 7
+wall 0 0 0 128 10
+wall 0 128 128 128 10
+wall 128 128 128 80 10
+wall 128 80 128 48 10 portal 1
+wall 128 48 128 0 10
+wall 128 0 0 0 10
+
+sector {} {} 9 7
+wall 128 48 128 80 10 portal 0
+wall 128 80 192 80 10
+wall 192 80 192 48 10
+wall 192 48 128 48 10
+",
+        floor, ceiling
+    );
+    World::new(level::parse(&source, Path::new(".")).unwrap())
+}
+
+fn walk(world: &mut World, degrees: f64, ticks: usize) {
+    world.player.angle = Angle::from_degrees(degrees);
+    world.actions.set(Action::Forward, true);
+    for _ in 0..ticks {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+}
+
+#[test]
+fn solid_walls_stop_the_player_at_their_radius() {
+    let mut world = ledge(0, 128);
+    walk(&mut world, 0.0, 40);
+    let position = world.player.position;
+    assert!(
+        (position.y - (128.0 - PLAYER_RADIUS)).abs() < 1e-6,
+        "{:?}",
+        position
+    );
+    assert!((position.x - 64.0).abs() < 1e-6);
+
+    walk(&mut world, 270.0, 40);
+    assert!((world.player.position.x - PLAYER_RADIUS).abs() < 1e-6);
+}
+
+#[test]
+fn moving_into_a_wall_at_an_angle_slides_along_it() {
+    let mut world = ledge(0, 128);
+    world.player.position.y = 112.0;
+    walk(&mut world, 315.0, 4);
+    let position = world.player.position;
+    assert!(
+        (position.y - (128.0 - PLAYER_RADIUS)).abs() < 1e-6,
+        "{:?}",
+        position
+    );
+    // Only the motion into the wall is lost.
+    assert!(position.x < 64.0 - 10.0, "{:?}", position);
+}
+
+#[test]
+fn portals_let_the_player_through() {
+    let mut world = ledge(16, 128);
+    walk(&mut world, 90.0, 30);
+    assert_eq!(world.player.sector, Some(1));
+    assert!((world.player.position.x - (192.0 - PLAYER_RADIUS)).abs() < 1e-6);
+}
+
+#[test]
+fn ledges_too_high_block_the_player() {
+    let mut world = ledge(32, 128);
+    walk(&mut world, 90.0, 30);
+    assert_eq!(world.player.sector, Some(0));
+    assert!((world.player.position.x - (128.0 - PLAYER_RADIUS)).abs() < 1e-6);
+    assert_eq!(world.player.position.z, 0.0);
+}
+
+#[test]
+fn low_ceilings_block_the_player() {
+    let mut world = ledge(0, 24);
+    walk(&mut world, 90.0, 30);
+    assert_eq!(world.player.sector, Some(0));
+    assert!((world.player.position.x - (128.0 - PLAYER_RADIUS)).abs() < 1e-6);
+}
@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests.
+
+use std::path::Path;
+
+use doomrust::{level, World, DEFAULT_LEVEL};
+
+/// A world in the built-in level.
+pub fn default_world() -> World {
+    World::new(level::parse(DEFAULT_LEVEL, Path::new(".")).unwrap())
+}
+
+/// The built-in level without its things, for tests placing their own.
+// Not every test crate including this module uses it.
+#[allow(dead_code)]
+pub fn empty_world() -> World {
+    let mut world = default_world();
+    world.things.clear();
+    world
+}
@@ -0,0 +1,95 @@
+//! Config files and the action bindings they hold.
+
+use doomrust::config::{Config, Error};
+use doomrust::input::{Bindings, Input};
+use doomrust::{Action, Actions};
+
+fn parse_error(source: &str) -> (usize, String) {
+    match Config::parse(source) {
+        Err(Error::Parse { line, message }) => (line, message),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("parsed invalid config"),
+    }
+}
+
+#[test]
+fn defaults_round_trip() {
+    let config = Config::default();
+    assert_eq!(Config::parse(&config.to_string()).unwrap(), config);
+}
+
+#[test]
+fn binds_replace_the_defaults() {
+    let config = Config::parse(
+        "doomrust-config 1\n\
+         # AZERTY\n\
+         bind z forward\n\
+         bind Q strafe-left\n\
+         bind Mouse2 use\n\
+         bind WheelUp jump\n\
+         sensitivity 0.3\n\
+         invert-y true\n",
+    )
+    .unwrap();
+    let actions = |input: Input| config.bindings.actions(&input).collect::<Vec<_>>();
+    assert_eq!(actions(Input::Key("Z".to_owned())), [Action::Forward]);
+    assert_eq!(actions(Input::Key("W".to_owned())), []);
+    assert_eq!(actions(Input::Key("q".to_owned())), [Action::StrafeLeft]);
+    assert_eq!(actions(Input::Mouse(2)), [Action::Use]);
+    assert_eq!(actions(Input::WheelUp), [Action::Jump]);
+    assert_eq!(config.mouse.sensitivity, 0.3);
+    assert!(config.mouse.invert_y);
+}
+
+#[test]
+fn settings_alone_keep_the_default_bindings() {
+    let config = Config::parse("doomrust-config 1\nsensitivity 0.5\n").unwrap();
+    assert_eq!(config.bindings, Bindings::default());
+}
+
+#[test]
+fn errors_name_the_line() {
+    assert_eq!(parse_error("bind W forward\n").0, 1);
+    assert_eq!(
+        parse_error("doomrust-config 2\n"),
+        (1, "unsupported format version `2`, expected 1".to_owned())
+    );
+    assert_eq!(
+        parse_error("doomrust-config 1\n\nbind W sprint\n"),
+        (3, "unknown action `sprint`".to_owned())
+    );
+    assert_eq!(
+        parse_error("doomrust-config 1\nbind W\n"),
+        (2, "`bind` takes 2 arguments".to_owned())
+    );
+    assert_eq!(
+        parse_error("doomrust-config 1\nbind Mouse0 fire\n"),
+        (2, "bad input `Mouse0`".to_owned())
+    );
+    assert_eq!(
+        parse_error("doomrust-config 1\nsensitivity -1\n"),
+        (2, "expected a positive number, found `-1`".to_owned())
+    );
+    assert_eq!(parse_error("# empty\n").0, 2);
+}
+
+#[test]
+fn presses_last_until_the_end_of_the_tick() {
+    let mut actions = Actions::default();
+    actions.set(Action::Fly, true);
+    actions.set(Action::Fly, false);
+    assert!(actions.pressed(Action::Fly) && actions.held(Action::Fly));
+    actions.end_tick();
+    assert!(!actions.held(Action::Fly));
+
+    // Key repeat is not a new press.
+    actions.set(Action::Fire, true);
+    actions.end_tick();
+    actions.set(Action::Fire, true);
+    assert!(actions.held(Action::Fire) && !actions.pressed(Action::Fire));
+
+    actions.tap(Action::Jump);
+    assert!(actions.held(Action::Jump));
+    actions.end_tick();
+    assert!(!actions.held(Action::Jump));
+}
@@ -0,0 +1,68 @@
+//! The depth buffer filled in while rendering.
+
+mod common;
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::{Camera, DepthBuffer, Thing, World, HEIGHT, WIDTH};
+
+use common::empty_world;
+
+fn render(world: &World, camera: &Camera) -> DepthBuffer {
+    let mut frame = vec![0; (WIDTH * HEIGHT) as usize];
+    let mut depth = DepthBuffer::new();
+    world.render_depth(camera, &mut frame, &mut depth);
+    depth
+}
+
+fn camera(x: f64, y: f64, degrees: f64) -> Camera {
+    Camera {
+        position: Vec3::new(x, y, 20.0),
+        angle: Angle::from_degrees(degrees),
+        look: 0.0,
+    }
+}
+
+#[test]
+fn walls_record_their_distance() {
+    let world = empty_world();
+    // Facing the southern wall of the level at y = -160, from 60 units away.
+    let depth = render(&world, &camera(130.0, -100.0, 180.0));
+    let (x, y) = (WIDTH as i32 / 2, HEIGHT as i32 / 2);
+    assert!((depth.column(x) - 60.0).abs() < 0.5, "{}", depth.column(x));
+    assert_eq!(depth.at(x, y), depth.column(x));
+    // The floor gets closer towards the bottom of the screen.
+    assert!(depth.at(x, HEIGHT as i32 - 1) < depth.at(x, y));
+    assert!(depth.visible(x, y, 10.0));
+    assert!(!depth.visible(x, y, 70.0));
+}
+
+#[test]
+fn nothing_is_drawn_outside_of_the_level() {
+    let world = empty_world();
+    let depth = render(&world, &camera(1000.0, 1000.0, 0.0));
+    assert!(depth.pixels.iter().all(|&d| d == f64::INFINITY));
+    assert!(depth.columns.iter().all(|&d| d == f64::INFINITY));
+}
+
+#[test]
+fn things_record_their_distance() {
+    let mut world = empty_world();
+    let camera = camera(130.0, -140.0, 0.0);
+    let before = render(&world, &camera);
+    world.things.push(Thing {
+        position: Vec3::new(130.0, -100.0, 0.0),
+        angle: Angle::from_degrees(0.0),
+        sprite: 0,
+        health: None,
+    });
+    let after = render(&world, &camera);
+    let changed: Vec<f64> = before
+        .pixels
+        .iter()
+        .zip(&after.pixels)
+        .filter(|(b, a)| b != a)
+        .map(|(_, &a)| a)
+        .collect();
+    assert!(!changed.is_empty());
+    assert!(changed.iter().all(|&d| (d - 40.0).abs() < 1e-9));
+}
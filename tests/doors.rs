@@ -0,0 +1,153 @@
+//! Doors opening and closing.
+
+use std::path::Path;
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::mover::{Special, State, DOOR_WAIT};
+use doomrust::{level, Action, Camera, World};
+
+/// Two rooms joined by a shut door, the player in the west room facing it.
+const DOOR_LEVEL: &str = "\
+doomrust-level 1
+player 40 32 0 90
+
+sector 0 72 9 8
+wall 0 0 0 64 10
+wall 0 64 64 64 10
+wall 64 64 64 48 10
+wall 64 48 64 16 10 portal 1
+wall 64 16 64 0 10
+wall 64 0 0 0 10
+
+sector 0 0 9 8 door
+wall 64 16 64 48 3 portal 0
+wall 64 48 72 48 10
+wall 72 48 72 16 3 portal 2
+wall 72 16 64 16 10
+
+sector 0 72 9 8
+wall 72 0 72 16 10
+wall 72 16 72 48 10 portal 1
+wall 72 48 72 64 10
+wall 72 64 136 64 10
+wall 136 64 136 0 10
+wall 136 0 72 0 10
+";
+
+/// Ticks a door takes to open fully from shut.
+const OPEN_TICKS: usize = 68 / 2;
+
+fn door_world() -> World {
+    World::new(level::parse(DOOR_LEVEL, Path::new(".")).unwrap())
+}
+
+fn press(world: &mut World, action: Action) {
+    world.actions.set(action, true);
+    world.update();
+    world.actions.set(action, false);
+}
+
+fn run(world: &mut World, ticks: usize) {
+    for _ in 0..ticks {
+        world.update();
+    }
+}
+
+#[test]
+fn door_sectors_are_saved() {
+    let level = level::parse(DOOR_LEVEL, Path::new(".")).unwrap();
+    assert_eq!(level.sectors[1].special, Some(Special::Door));
+    assert!(level.to_string().contains("sector 0 0 9 8 door"));
+    assert_eq!(level.validate(), []);
+}
+
+#[test]
+fn using_a_door_opens_it_then_it_closes() {
+    let mut world = door_world();
+    assert_eq!(world.movers.len(), 1);
+    assert_eq!(world.movers[0].high, 68);
+    press(&mut world, Action::Use);
+    assert_eq!(world.movers[0].state, State::Rising);
+    assert_eq!(world.sectors[1].z2, Special::Door.speed());
+
+    run(&mut world, OPEN_TICKS);
+    assert_eq!(world.sectors[1].z2, 68);
+    assert!(matches!(world.movers[0].state, State::Waiting(_)));
+
+    run(&mut world, DOOR_WAIT as usize + 1);
+    assert_eq!(world.movers[0].state, State::Lowering);
+    run(&mut world, OPEN_TICKS);
+    assert_eq!(world.sectors[1].z2, 0);
+    assert_eq!(world.movers[0].state, State::Idle);
+}
+
+#[test]
+fn doors_out_of_reach_cannot_be_used() {
+    let mut world = door_world();
+    world.player.position.x = 8.0;
+    press(&mut world, Action::Use);
+    assert_eq!(world.movers[0].state, State::Idle);
+
+    // Nor can doors behind the player.
+    let mut world = door_world();
+    world.player.angle = Angle::from_degrees(270.0);
+    press(&mut world, Action::Use);
+    assert_eq!(world.movers[0].state, State::Idle);
+}
+
+#[test]
+fn shut_doors_block_and_open_ones_let_through() {
+    let mut world = door_world();
+    world.actions.set(Action::Forward, true);
+    run(&mut world, 20);
+    assert!(world.player.position.x < 64.0);
+
+    world.actions.set(Action::Forward, false);
+    press(&mut world, Action::Use);
+    run(&mut world, OPEN_TICKS);
+    world.actions.set(Action::Forward, true);
+    run(&mut world, 20);
+    assert_eq!(world.player.sector, Some(2));
+}
+
+#[test]
+fn a_closing_door_goes_back_up_over_the_player() {
+    let mut world = door_world();
+    press(&mut world, Action::Use);
+    run(&mut world, OPEN_TICKS);
+    world.player.position = Vec3::new(68.0, 32.0, 0.0);
+    run(&mut world, DOOR_WAIT as usize + 1);
+    for _ in 0..100 {
+        world.update();
+        assert!(world.sectors[1].z2 as f64 >= world.player.height());
+    }
+    assert_ne!(world.movers[0].state, State::Idle);
+}
+
+#[test]
+fn using_a_moving_door_turns_it_around() {
+    let mut world = door_world();
+    press(&mut world, Action::Use);
+    run(&mut world, 5);
+    press(&mut world, Action::Use);
+    assert_eq!(world.movers[0].state, State::Lowering);
+    press(&mut world, Action::Use);
+    assert_eq!(world.movers[0].state, State::Rising);
+}
+
+#[test]
+fn the_view_follows_the_door() {
+    let mut world = door_world();
+    let camera = Camera {
+        position: Vec3::new(20.0, 32.0, 20.0),
+        ..world.camera(1.0)
+    };
+    let shut = world.render_frame(&camera);
+    press(&mut world, Action::Use);
+    run(&mut world, OPEN_TICKS / 2);
+    let half_open = world.render_frame(&camera);
+    run(&mut world, OPEN_TICKS);
+    let open = world.render_frame(&camera);
+    assert_ne!(shut, half_open);
+    assert_ne!(half_open, open);
+}
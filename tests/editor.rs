@@ -0,0 +1,162 @@
+//! The level editor and saving levels.
+
+use std::path::Path;
+
+use doomrust::editor::{Editor, Selection};
+use doomrust::math::Vec2;
+use doomrust::{level, Level, DEFAULT_LEVEL, HEIGHT, WIDTH};
+
+fn default_level() -> Level {
+    level::parse(DEFAULT_LEVEL, Path::new(".")).unwrap()
+}
+
+/// An editor on the default level with everything deleted.
+fn empty_editor() -> Editor {
+    let mut editor = Editor::new(&default_level());
+    editor.map.vertices.clear();
+    editor.map.walls.clear();
+    editor.map.sectors.clear();
+    editor
+}
+
+/// Draw the clockwise square of `size` with its corner at the origin and
+/// group it into a sector.
+fn draw_square(editor: &mut Editor, size: f64) -> usize {
+    editor.add_vertex(Vec2::new(0.0, 0.0));
+    let first = editor.add_wall(Vec2::new(0.0, size)).expect("first wall");
+    editor.add_wall(Vec2::new(size, size)).unwrap();
+    editor.add_wall(Vec2::new(size, 0.0)).unwrap();
+    editor.add_wall(Vec2::new(0.0, 0.0)).unwrap();
+    editor.selection = Some(Selection::Wall(first));
+    editor.make_sector().expect("sector")
+}
+
+#[test]
+fn saved_levels_load_the_same() {
+    let level = default_level();
+    let saved = level.to_string();
+    let loaded = level::parse(&saved, Path::new(".")).unwrap();
+    assert_eq!(loaded.to_string(), saved);
+    assert_eq!(loaded.sectors.len(), level.sectors.len());
+    assert_eq!(loaded.walls.len(), level.walls.len());
+    for (a, b) in loaded.walls.iter().zip(&level.walls) {
+        assert_eq!((a.x1, a.y1, a.x2, a.y2), (b.x1, b.y1, b.x2, b.y2));
+        assert_eq!(a.neighbour, b.neighbour);
+        assert_eq!(a.texture, b.texture);
+    }
+}
+
+#[test]
+fn the_unedited_map_gives_back_the_level() {
+    let level = default_level();
+    let edited = Editor::new(&level).level().unwrap();
+    assert_eq!(edited.to_string(), level.to_string());
+}
+
+#[test]
+fn a_drawn_loop_becomes_a_sector() {
+    let mut editor = empty_editor();
+    let sector = draw_square(&mut editor, 64.0);
+    assert_eq!(sector, 0);
+    assert_eq!(editor.map.vertices.len(), 4);
+    editor.place_player(Vec2::new(32.0, 32.0));
+    let level = editor.level().unwrap();
+    assert_eq!(level.sectors.len(), 1);
+    assert_eq!(level.walls.len(), 4);
+    assert!(level.walls.iter().all(|wall| wall.neighbour.is_none()));
+    assert_eq!(
+        editor.pick(Vec2::new(32.0, 32.0)),
+        Some(Selection::Sector(0))
+    );
+}
+
+#[test]
+fn walls_in_no_sector_keep_the_map_from_being_a_level() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    editor.add_vertex(Vec2::new(128.0, 0.0));
+    editor.add_wall(Vec2::new(128.0, 64.0)).unwrap();
+    assert!(editor.level().is_err());
+}
+
+#[test]
+fn twin_walls_become_portals() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    // The square to the right, sharing the wall from (64, 64) to (64, 0).
+    editor.add_vertex(Vec2::new(64.0, 0.0));
+    let first = editor.add_wall(Vec2::new(64.0, 64.0)).unwrap();
+    editor.add_wall(Vec2::new(128.0, 64.0)).unwrap();
+    editor.add_wall(Vec2::new(128.0, 0.0)).unwrap();
+    editor.add_wall(Vec2::new(64.0, 0.0)).unwrap();
+    editor.selection = Some(Selection::Wall(first));
+    assert_eq!(editor.make_sector(), Ok(1));
+
+    let level = editor.level().unwrap();
+    let portals: Vec<_> = level.walls.iter().filter_map(|w| w.neighbour).collect();
+    assert_eq!(portals, [1, 0]);
+}
+
+#[test]
+fn edits_can_be_undone_and_redone() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    let square = editor.map.clone();
+    editor.selection = Some(Selection::Sector(0));
+    editor.raise(0, 8);
+    assert_eq!(editor.map.sectors[0].z2, square.sectors[0].z2 + 8);
+    assert!(editor.undo());
+    assert_eq!(editor.map, square);
+    assert!(editor.redo());
+    assert_eq!(editor.map.sectors[0].z2, square.sectors[0].z2 + 8);
+    assert!(!editor.redo());
+}
+
+#[test]
+fn deleting_a_vertex_deletes_its_walls() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    editor.selection = editor.pick(Vec2::new(64.0, 64.0));
+    assert!(matches!(editor.selection, Some(Selection::Vertex(_))));
+    editor.delete();
+    assert_eq!(editor.map.vertices.len(), 3);
+    assert_eq!(editor.map.walls.len(), 2);
+    assert!(editor.map.walls.iter().all(|w| w.v1 < 3 && w.v2 < 3));
+}
+
+#[test]
+fn dragged_vertices_snap_to_the_grid() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    let grid = editor.grid_size() as f64;
+    editor.grab(Vec2::new(64.0, 64.0));
+    editor.drag(Vec2::new(64.0 + grid * 2.3, 64.0 - grid * 0.6));
+    editor.release();
+    let moved = (64 + 2 * grid as i32, 64 - grid as i32);
+    assert!(editor.map.vertices.contains(&moved));
+    assert!(editor.undo());
+    assert!(editor.map.vertices.contains(&(64, 64)));
+}
+
+#[test]
+fn a_click_without_moving_is_not_an_edit() {
+    let mut editor = empty_editor();
+    draw_square(&mut editor, 64.0);
+    editor.grab(Vec2::new(64.0, 64.0));
+    editor.release();
+    assert!(editor.undo());
+    // Undoing the click would have left the square as it was.
+    assert_eq!(editor.map.sectors.len(), 0);
+}
+
+#[test]
+fn the_map_is_drawn() {
+    let editor = Editor::new(&default_level());
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    editor.draw(&mut frame);
+    let lit = frame
+        .chunks_exact(4)
+        .filter(|pixel| pixel[..3] != [0, 0, 0])
+        .count();
+    assert!(lit > 0);
+}
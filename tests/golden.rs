@@ -0,0 +1,130 @@
+//! Renders fixed views headlessly and compares them with the PNG images in
+//! `tests/golden`. Run with `DOOMRUST_BLESS=1` to write the current output as
+//! the new golden images after an intended rendering change.
+
+mod common;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::{Camera, World, HEIGHT, WIDTH};
+
+use common::default_world;
+
+/// Largest difference of a colour channel that still counts as equal.
+const TOLERANCE: u8 = 8;
+
+/// Fraction of pixels allowed to differ by more than `TOLERANCE`.
+const MAX_DIFFERENT: f64 = 0.002;
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+fn write_png(path: &Path, rgba: &[u8]) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(rgba).unwrap();
+}
+
+fn read_png(path: &Path) -> Vec<u8> {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba).unwrap();
+    assert_eq!(
+        (info.width, info.height, info.color_type),
+        (WIDTH, HEIGHT, png::ColorType::Rgba),
+        "{} is not a {}x{} RGBA image",
+        path.display(),
+        WIDTH,
+        HEIGHT
+    );
+    rgba.truncate(info.buffer_size());
+    rgba
+}
+
+/// Render `camera`'s view of `world` and compare it with the golden image `name`.
+fn check(name: &str, world: &World, camera: Camera) {
+    let actual = world.render_frame(&camera);
+    let golden = golden_path(name);
+    if std::env::var_os("DOOMRUST_BLESS").is_some() {
+        write_png(&golden, &actual);
+        return;
+    }
+    assert!(
+        golden.exists(),
+        "missing {}, run with DOOMRUST_BLESS=1 to create it",
+        golden.display()
+    );
+
+    let expected = read_png(&golden);
+    let different = actual
+        .chunks_exact(4)
+        .zip(expected.chunks_exact(4))
+        .filter(|(a, e)| {
+            a.iter()
+                .zip(e.iter())
+                .any(|(a, e)| a.abs_diff(*e) > TOLERANCE)
+        })
+        .count();
+    let allowed = (MAX_DIFFERENT * (WIDTH * HEIGHT) as f64) as usize;
+    if different > allowed {
+        let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.png", name));
+        write_png(&output, &actual);
+        panic!(
+            "{}: {} pixels differ from {} (at most {} may), the frame is in {}",
+            name,
+            different,
+            golden.display(),
+            allowed,
+            output.display()
+        );
+    }
+}
+
+#[test]
+fn default_level_start() {
+    let world = default_world();
+    check("default_start", &world, world.player.camera());
+}
+
+#[test]
+fn default_level_from_above() {
+    let world = default_world();
+    let camera = Camera {
+        position: Vec3::new(48.0, -60.0, 70.0),
+        angle: Angle::from_degrees(10.0),
+        look: 0.0,
+    };
+    check("default_above", &world, camera);
+}
+
+#[test]
+fn default_level_textured_wall() {
+    let world = default_world();
+    let camera = Camera {
+        position: Vec3::new(40.0, -120.0, 20.0),
+        angle: Angle::from_degrees(300.0),
+        look: 4.0,
+    };
+    check("default_textured_wall", &world, camera);
+}
+
+#[test]
+fn outside_of_the_level() {
+    let world = default_world();
+    let camera = Camera {
+        position: Vec3::new(1000.0, 1000.0, 20.0),
+        angle: Angle::from_degrees(0.0),
+        look: 0.0,
+    };
+    check("outside", &world, camera);
+}
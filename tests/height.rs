@@ -0,0 +1,159 @@
+//! Gravity, steps, jumping and crouching.
+
+use std::path::Path;
+
+use doomrust::math::Angle;
+use doomrust::world::{CROUCH_EYE_HEIGHT, EYE_HEIGHT};
+use doomrust::{level, Action, World};
+
+/// A room 128 square with a doorway in its east wall onto a ledge room,
+/// whose floor and ceiling are `floor` and `ceiling`. The player starts
+/// `z` above the middle of the room, facing east.
+fn ledge(z: i32, floor: i32, ceiling: i32) -> World {
+    let source = format!(
+        "\
+doomrust-level 1
+player 64 64 {} 90
+
+sector 0 128 9 7
+wall 0 0 0 128 10
+wall 0 128 128 128 10
+wall 128 128 128 80 10
+wall 128 80 128 48 10 portal 1
+wall 128 48 128 0 10
+wall 128 0 0 0 10
+
+sector {} {} 9 7
+wall 128 48 128 80 10 portal 0
+wall 128 80 192 80 10
+wall 192 80 192 48 10
+wall 192 48 128 48 10
+",
+        z, floor, ceiling
+    );
+    World::new(level::parse(&source, Path::new(".")).unwrap())
+}
+
+fn run(world: &mut World, ticks: usize) {
+    for _ in 0..ticks {
+        world.update();
+    }
+}
+
+#[test]
+fn the_player_falls_to_the_floor() {
+    let mut world = ledge(40, 0, 128);
+    world.update();
+    assert_eq!(world.player.position.z, 39.0);
+    world.update();
+    assert_eq!(world.player.position.z, 37.0);
+    run(&mut world, 10);
+    assert_eq!(world.player.position.z, 0.0);
+    assert_eq!(world.player.vz, 0.0);
+    // A fall doesn't dip the view like a step does.
+    assert_eq!(world.player.view_height, EYE_HEIGHT);
+}
+
+#[test]
+fn steps_are_climbed_and_the_view_catches_up() {
+    let mut world = ledge(0, 16, 128);
+    world.actions.set(Action::Forward, true);
+    while world.player.sector != Some(1) {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+    assert_eq!(world.player.position.z, 16.0);
+    let dipped = world.player.view_height;
+    assert!(dipped < EYE_HEIGHT - 8.0, "{}", dipped);
+
+    world.update();
+    assert_eq!(world.player.view_height, dipped + 2.0);
+    run(&mut world, 10);
+    assert_eq!(world.player.view_height, EYE_HEIGHT);
+    assert_eq!(world.player.position.z, 16.0);
+}
+
+#[test]
+fn ledges_too_high_are_not_climbed() {
+    let mut world = ledge(0, 32, 128);
+    world.actions.set(Action::Forward, true);
+    run(&mut world, 30);
+    assert_eq!(world.player.sector, Some(0));
+    assert_eq!(world.player.position.z, 0.0);
+    assert_eq!(world.player.view_height, EYE_HEIGHT);
+}
+
+#[test]
+fn jumps_rise_and_land() {
+    let mut world = ledge(0, 0, 128);
+    world.update();
+    world.actions.set(Action::Jump, true);
+    world.update();
+    world.actions.set(Action::Jump, false);
+    assert_eq!(world.player.position.z, 5.0);
+
+    let mut highest = 0.0f64;
+    for _ in 0..10 {
+        world.update();
+        highest = highest.max(world.player.position.z);
+    }
+    assert_eq!(highest, 15.0);
+    assert_eq!(world.player.position.z, 0.0);
+    assert_eq!(world.player.view_height, EYE_HEIGHT);
+}
+
+#[test]
+fn jumps_stop_at_the_ceiling() {
+    let mut world = ledge(0, 0, 40);
+    world.actions.set(Action::Forward, true);
+    while world.player.sector != Some(1) {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+    world.actions.set(Action::Jump, true);
+    world.update();
+    world.actions.set(Action::Jump, false);
+    world.update();
+    // The head stops 40 - 32 above the floor, and the player falls at once.
+    assert_eq!(world.player.position.z, 8.0);
+    world.update();
+    assert_eq!(world.player.position.z, 7.0);
+}
+
+#[test]
+fn crouching_fits_under_low_ceilings() {
+    let mut world = ledge(0, 0, 24);
+    world.actions.set(Action::Crouch, true);
+    run(&mut world, 5);
+    assert!(world.player.crouching);
+    assert_eq!(world.player.view_height, CROUCH_EYE_HEIGHT);
+
+    // Crouched, the player fits through into the low room, and can't stand
+    // up there.
+    world.actions.set(Action::Forward, true);
+    while world.player.sector != Some(1) {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+    world.actions.set(Action::Crouch, false);
+    run(&mut world, 5);
+    assert!(world.player.crouching);
+    assert_eq!(world.player.view_height, CROUCH_EYE_HEIGHT);
+
+    // Nor jump.
+    world.actions.set(Action::Jump, true);
+    world.update();
+    world.actions.set(Action::Jump, false);
+    assert_eq!(world.player.position.z, 0.0);
+
+    // Back out under the high ceiling, the player stands again.
+    world.player.angle = Angle::from_degrees(270.0);
+    world.actions.set(Action::Forward, true);
+    while world.player.sector != Some(0) {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+    run(&mut world, 10);
+    assert!(!world.player.crouching);
+    assert_eq!(world.player.view_height, EYE_HEIGHT);
+}
@@ -0,0 +1,91 @@
+//! The text level format and the positions of its errors.
+
+use std::path::Path;
+
+use doomrust::{level, Level};
+
+/// One square room, with a comment and blank lines.
+const ROOM: &str = "\
+doomrust-level 1
+# A square room.
+player 32 32 0 90
+
+sector 0 72 9 7 light 160
+wall 0 0 0 64 10
+wall 0 64 64 64 10   # north
+wall 64 64 64 0 10
+wall 64 0 0 0 10
+";
+
+fn parse(source: &str) -> Result<Level, level::Error> {
+    level::parse(source, Path::new("."))
+}
+
+/// The error of `source`, as `line:column: message`.
+fn error(source: &str) -> String {
+    parse(source).err().unwrap().to_string()
+}
+
+#[test]
+fn levels_are_read() {
+    let level = parse(ROOM).unwrap();
+    assert_eq!(level.player.position.x, 32.0);
+    assert_eq!(level.player.angle.degrees(), 90.0);
+    assert_eq!(level.sectors.len(), 1);
+    let sector = &level.sectors[0];
+    assert_eq!((sector.z1, sector.z2, sector.light), (0, 72, 160));
+    assert_eq!((sector.floor_color, sector.ceiling_color), (9, 7));
+    assert_eq!((sector.wall_start, sector.wall_end), (0, 4));
+    let north = &level.walls[1];
+    assert_eq!((north.x1, north.y1, north.x2, north.y2), (0, 64, 64, 64));
+    assert_eq!(north.neighbour, None);
+
+    let saved = level.to_string();
+    assert!(saved.starts_with("doomrust-level 1\n"));
+    assert_eq!(parse(&saved).unwrap().to_string(), saved);
+}
+
+#[test]
+fn the_header_comes_first() {
+    assert_eq!(
+        error(&ROOM.replace("doomrust-level 1\n", "")),
+        "2:1: expected `doomrust-level <version>` header"
+    );
+    assert_eq!(
+        error(&ROOM.replace("doomrust-level 1", "doomrust-level 2")),
+        "1:16: unsupported format version `2`, expected 1"
+    );
+    assert_eq!(error(""), "1:1: expected `doomrust-level <version>` header");
+}
+
+#[test]
+fn errors_name_the_line_and_column() {
+    assert_eq!(
+        error(&ROOM.replace("player 32 32 0 90", "player 32 32 0 90\nteleport 1 2")),
+        "4:1: unknown directive `teleport`"
+    );
+    assert_eq!(
+        error(&ROOM.replace("wall 64 64 64 0 10", "wall 64 64 64 64 10")),
+        "8:1: wall has zero length"
+    );
+    assert_eq!(
+        error(&ROOM.replace("wall 64 64 64 0 10", "wall 64 64 64 0 10 portal 3")),
+        "8:27: portal to missing sector 3"
+    );
+    assert_eq!(
+        error(&ROOM.replace("wall 0 64 64 64 10", "wall 0 64 sixty 64 10")),
+        "7:11: expected an integer, found `sixty`"
+    );
+    assert_eq!(
+        error(&ROOM.replace("light 160", "light 300")),
+        "5:23: expected a light level 0-255, found `300`"
+    );
+    assert_eq!(
+        error(&ROOM.replace("wall 64 0 0 0 10\n", "wall 64 0 0\n")),
+        "9:12: missing wall y2"
+    );
+    assert_eq!(
+        error(&ROOM.replace("player 32 32 0 90\n", "")),
+        "9:1: missing player start"
+    );
+}
@@ -0,0 +1,154 @@
+//! The math types against `f64` reference values.
+
+use std::f64::consts::{PI, TAU};
+
+use doomrust::math::{Angle, Fixed, Vec2, Vec3};
+
+fn close(a: f64, b: f64, epsilon: f64) -> bool {
+    (a - b).abs() <= epsilon
+}
+
+#[test]
+fn angle_trigonometry_matches_f64() {
+    // Every 1/1000 of a degree would be slow; an odd step covers the table
+    // entries and the points between them.
+    let mut degrees = -720.0;
+    while degrees < 720.0 {
+        let angle = Angle::from_degrees(degrees);
+        let radians = degrees.to_radians();
+        assert!(close(angle.sin(), radians.sin(), 1e-6), "sin {}", degrees);
+        assert!(close(angle.cos(), radians.cos(), 1e-6), "cos {}", degrees);
+        degrees += 0.0137;
+    }
+}
+
+#[test]
+fn angle_constants_are_exact() {
+    assert_eq!(Angle::from_degrees(90.0), Angle::DEG90);
+    assert_eq!(Angle::from_degrees(180.0), Angle::DEG180);
+    assert_eq!(Angle::from_degrees(-90.0), Angle::DEG270);
+    assert_eq!(Angle::from_degrees(360.0), Angle::ZERO);
+    assert_eq!(Angle::from_radians(PI / 4.0), Angle::DEG45);
+    assert_eq!(Angle::DEG90.sin(), 1.0);
+    assert_eq!(Angle::DEG180.cos(), -1.0);
+}
+
+#[test]
+fn angle_conversions_round_trip() {
+    for degrees in [0.0, 0.5, 1.0, 3.0, 45.25, 123.456, 270.0, 359.99] {
+        let angle = Angle::from_degrees(degrees);
+        assert!(close(angle.degrees(), degrees, 1e-6), "{}", degrees);
+        assert!(close(angle.radians(), degrees * TAU / 360.0, 1e-8));
+    }
+    assert!(close(
+        Angle::from_degrees(350.0).signed_degrees(),
+        -10.0,
+        1e-6
+    ));
+}
+
+#[test]
+fn angles_wrap_around() {
+    let a = Angle::from_degrees(350.0) + Angle::from_degrees(20.0);
+    assert!(close(a.degrees(), 10.0, 1e-6));
+    let b = Angle::from_degrees(10.0) - Angle::from_degrees(20.0);
+    assert!(close(b.degrees(), 350.0, 1e-6));
+    assert_eq!(-Angle::DEG90, Angle::DEG270);
+}
+
+#[test]
+fn angle_lerp_takes_the_short_way() {
+    let from = Angle::from_degrees(350.0);
+    let to = Angle::from_degrees(10.0);
+    assert!(close(from.lerp(to, 0.5).degrees(), 0.0, 1e-6));
+    assert!(close(to.lerp(from, 0.25).degrees(), 5.0, 1e-6));
+}
+
+#[test]
+fn fixed_conversions() {
+    assert_eq!(Fixed::from_int(1), Some(Fixed::ONE));
+    assert_eq!(Fixed::from_int(-3).unwrap().to_f64(), -3.0);
+    assert_eq!(Fixed::from_int(32768), None);
+    assert_eq!(Fixed::from_f64(1.5), Some(Fixed(0x0001_8000)));
+    assert_eq!(Fixed::from_f64(40000.0), None);
+    assert_eq!(Fixed::from_f64(-2.25).unwrap().floor(), -3);
+    assert!(close(
+        Fixed::from_f64(PI).unwrap().to_f64(),
+        PI,
+        1.0 / 65536.0
+    ));
+}
+
+#[test]
+fn fixed_arithmetic_matches_f64() {
+    let values = [-150.75, -2.5, -0.01, 0.0, 0.3, 1.0, 7.125, 90.5];
+    let step = 1.0 / 65536.0;
+    for &a in &values {
+        for &b in &values {
+            let (fa, fb) = (Fixed::from_f64(a).unwrap(), Fixed::from_f64(b).unwrap());
+            let (a, b) = (fa.to_f64(), fb.to_f64());
+            assert_eq!((fa + fb).to_f64(), a + b);
+            assert_eq!((fa - fb).to_f64(), a - b);
+            assert!(close((fa * fb).to_f64(), a * b, step), "{} * {}", a, b);
+            if fb != Fixed::ZERO {
+                assert!(close((fa / fb).to_f64(), a / b, step), "{} / {}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn fixed_arithmetic_is_checked() {
+    let big = Fixed::from_int(30000).unwrap();
+    assert_eq!(big.checked_add(big), None);
+    assert_eq!(big.checked_sub(-big), None);
+    assert_eq!(big.checked_mul(big), None);
+    assert_eq!(big.checked_div(Fixed::from_f64(0.5).unwrap()), None);
+    assert_eq!(Fixed::ONE.checked_div(Fixed::ZERO), None);
+    assert_eq!(Fixed::MIN.checked_neg(), None);
+    assert_eq!(
+        Fixed::ONE.checked_add(Fixed::ONE),
+        Some(Fixed::from_int(2).unwrap())
+    );
+}
+
+#[test]
+#[should_panic(expected = "fixed-point overflow")]
+fn fixed_operators_panic_on_overflow() {
+    let _ = Fixed::MAX + Fixed::ONE;
+}
+
+#[test]
+fn vector_operations() {
+    let a = Vec2::new(3.0, 4.0);
+    let b = Vec2::new(-1.0, 2.0);
+    assert_eq!(a + b, Vec2::new(2.0, 6.0));
+    assert_eq!(a - b, Vec2::new(4.0, 2.0));
+    assert_eq!(a * 2.0, Vec2::new(6.0, 8.0));
+    assert_eq!(a.dot(b), 5.0);
+    assert_eq!(a.cross(b), 10.0);
+    assert_eq!(a.length(), 5.0);
+    assert_eq!(Vec2::new(0.0, -5.0).normalize(), Some(Vec2::new(0.0, -1.0)));
+    assert_eq!(Vec2::ZERO.normalize(), None);
+    // Turning clockwise takes north to east.
+    assert_eq!(Vec2::new(0.0, 1.0).perp(), Vec2::new(1.0, 0.0));
+
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    assert_eq!(x.cross(y), Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(Vec3::new(2.0, 3.0, 6.0).length(), 7.0);
+    assert_eq!(x.lerp(y, 0.5), Vec3::new(0.5, 0.5, 0.0));
+    assert_eq!(a.extend(1.0).xy(), a);
+}
+
+#[test]
+fn headings_run_clockwise_from_north() {
+    let north = Vec2::from_angle(Angle::ZERO);
+    let east = Vec2::from_angle(Angle::DEG90);
+    assert!(close(north.x, 0.0, 1e-12) && close(north.y, 1.0, 1e-12));
+    assert!(close(east.x, 1.0, 1e-12) && close(east.y, 0.0, 1e-12));
+    for degrees in [10.0, 100.0, 200.0, 300.0] {
+        let heading = Vec2::from_angle(Angle::from_degrees(degrees));
+        assert!(close(heading.length(), 1.0, 1e-6));
+    }
+}
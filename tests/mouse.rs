@@ -0,0 +1,45 @@
+//! Mouse motion turns the player and tilts their view.
+
+mod common;
+
+use doomrust::math::Angle;
+use doomrust::world::MAX_LOOK;
+
+use common::default_world;
+
+#[test]
+fn horizontal_motion_turns_by_the_sensitivity() {
+    let mut world = default_world();
+    world.mouse.sensitivity = 0.5;
+    let start = world.player.angle;
+    world.mouse_moved(30.0, 0.0);
+    let turned = (world.player.angle - start).signed_degrees();
+    assert!((turned - 15.0).abs() < 1e-6, "turned {}", turned);
+    // The turn shows at once, not only after the next tick.
+    assert_eq!(world.camera(0.0).angle, world.player.angle);
+    world.mouse_moved(-60.0, 0.0);
+    assert!(((world.player.angle - start).signed_degrees() + 15.0).abs() < 1e-6);
+}
+
+#[test]
+fn moving_the_mouse_away_looks_up() {
+    let mut world = default_world();
+    world.mouse_moved(0.0, -10.0);
+    assert!(world.player.look < 0.0);
+
+    let mut inverted = default_world();
+    inverted.mouse.invert_y = true;
+    inverted.mouse_moved(0.0, -10.0);
+    assert_eq!(inverted.player.look, -world.player.look);
+}
+
+#[test]
+fn pitch_is_clamped() {
+    let mut world = default_world();
+    world.mouse_moved(0.0, 1e6);
+    assert_eq!(world.player.look, MAX_LOOK);
+    world.mouse_moved(0.0, -1e6);
+    assert_eq!(world.player.look, -MAX_LOOK);
+    assert_eq!(world.camera(0.5).look, -MAX_LOOK);
+    assert_eq!(world.player.angle, Angle::ZERO);
+}
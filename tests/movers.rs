@@ -0,0 +1,192 @@
+//! Lifts, platforms, crushers and stairs, and saving the movers' state.
+
+use std::path::Path;
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::mover::{Special, State, LIFT_WAIT};
+use doomrust::texture::Texture;
+use doomrust::world::MAX_HEALTH;
+use doomrust::{level, Action, Thing, World};
+
+/// A row of square sectors 64 units wide from west to east, joined by
+/// portals, one per `sector` line. The player stands in the first one,
+/// facing the second.
+fn row(sectors: &[&str]) -> World {
+    let mut source = "doomrust-level 1\nplayer 40 32 0 90\n".to_owned();
+    for (i, sector) in sectors.iter().enumerate() {
+        let (x1, x2) = (64 * i as i32, 64 * (i as i32 + 1));
+        let portal = |n: usize| {
+            if n < sectors.len() {
+                format!(" portal {}", n)
+            } else {
+                String::new()
+            }
+        };
+        source += &format!("{}\n", sector);
+        source += &format!("wall {} 0 {} 64 10{}\n", x1, x1, portal(i.wrapping_sub(1)));
+        source += &format!("wall {} 64 {} 64 10\n", x1, x2);
+        source += &format!("wall {} 64 {} 0 10{}\n", x2, x2, portal(i + 1));
+        source += &format!("wall {} 0 {} 0 10\n", x2, x1);
+    }
+    let level = level::parse(&source, Path::new(".")).unwrap();
+    assert_eq!(level.validate(), []);
+    World::new(level)
+}
+
+fn press(world: &mut World, action: Action) {
+    world.actions.set(action, true);
+    world.update();
+    world.actions.set(action, false);
+}
+
+fn run(world: &mut World, ticks: usize) {
+    for _ in 0..ticks {
+        world.update();
+    }
+}
+
+#[test]
+fn lifts_lower_wait_and_return() {
+    let mut world = row(&["sector 0 96 9 8", "sector 32 96 9 8 lift 0"]);
+    assert_eq!((world.movers[0].low, world.movers[0].high), (0, 32));
+    press(&mut world, Action::Use);
+    assert_eq!(world.sectors[1].z1, 28);
+    run(&mut world, 7);
+    assert_eq!(world.sectors[1].z1, 0);
+    assert_eq!(world.movers[0].state, State::Waiting(LIFT_WAIT));
+
+    run(&mut world, LIFT_WAIT as usize + 8);
+    assert_eq!(world.sectors[1].z1, 32);
+    assert_eq!(world.movers[0].state, State::Idle);
+}
+
+#[test]
+fn lifts_carry_the_player() {
+    let mut world = row(&["sector 0 96 9 8", "sector 32 96 9 8 lift 0"]);
+    world.player.position.x = 96.0;
+    world.player.position.z = 32.0;
+    world.activate(0);
+    run(&mut world, 20);
+    assert_eq!(world.player.position.z, 0.0);
+    run(&mut world, LIFT_WAIT as usize + 20);
+    assert_eq!(world.player.position.z, 32.0);
+}
+
+#[test]
+fn platforms_keep_going() {
+    let mut world = row(&["sector 0 96 9 8", "sector 32 96 9 8 platform 0"]);
+    world.activate(0);
+    // The ends reached, in order.
+    let mut ends = Vec::new();
+    for _ in 0..3 * (LIFT_WAIT as usize + 8) {
+        world.update();
+        let z = world.sectors[1].z1;
+        if (z == 0 || z == 32) && ends.last() != Some(&z) {
+            ends.push(z);
+        }
+    }
+    assert_eq!(ends, [0, 32, 0]);
+    assert_ne!(world.movers[0].state, State::Idle);
+}
+
+#[test]
+fn crushers_hurt_the_player_under_them() {
+    let mut world = row(&["sector 0 72 9 8", "sector 0 72 9 8 crusher"]);
+    world.player.position.x = 96.0;
+    world.activate(0);
+    run(&mut world, 100);
+    assert!(world.player.health < MAX_HEALTH);
+    assert!(world.player.damage_count > 0);
+    assert!(world.sectors[1].z2 as f64 >= world.player.height());
+
+    // Out from under it, the crusher comes all the way down.
+    world.player.position.x = 32.0;
+    let lowest = (0..2 * 64)
+        .map(|_| {
+            world.update();
+            world.sectors[1].z2
+        })
+        .min();
+    assert_eq!(lowest, Some(8));
+}
+
+#[test]
+fn crushers_hurt_things_under_them() {
+    let mut world = row(&["sector 0 72 9 8", "sector 0 72 9 8 crusher"]);
+    world.sprites.push(Texture::barrel([200, 60, 40]));
+    let thing = |x: f64, health: Option<u32>| Thing {
+        position: Vec3::new(x, 32.0, 0.0),
+        angle: Angle::ZERO,
+        sprite: 0,
+        health,
+    };
+    world.things.push(thing(96.0, Some(MAX_HEALTH)));
+    world.things.push(thing(32.0, Some(MAX_HEALTH)));
+    world.things.push(thing(112.0, None));
+    world.activate(0);
+    // Not until the ceiling reaches the top of the barrel, 32 high.
+    run(&mut world, 9);
+    assert_eq!(world.things[0].health, Some(MAX_HEALTH));
+    run(&mut world, 100);
+    assert!(world.things[0].health < Some(MAX_HEALTH));
+    // Things elsewhere, or without health, are left alone.
+    assert_eq!(world.things[1].health, Some(MAX_HEALTH));
+    assert_eq!(world.things[2].health, None);
+}
+
+#[test]
+fn stairs_rise_a_step_more_each() {
+    let mut world = row(&[
+        "sector 0 96 9 8",
+        "sector 0 96 9 8 stairs 8",
+        "sector 0 96 9 8 stairs 8",
+        "sector 0 96 9 8 stairs 8",
+    ]);
+    press(&mut world, Action::Use);
+    run(&mut world, 30);
+    let floors: Vec<_> = world.sectors.iter().map(|sector| sector.z1).collect();
+    assert_eq!(floors, [0, 8, 16, 24]);
+    assert!(world.movers.iter().all(|mover| mover.state == State::Idle));
+
+    // Stairs rise once.
+    press(&mut world, Action::Use);
+    run(&mut world, 30);
+    assert_eq!(world.sectors[1].z1, 8);
+}
+
+#[test]
+fn specials_are_saved_with_the_level() {
+    let world = row(&["sector 0 96 9 8", "sector 32 96 9 8 lift -16"]);
+    assert_eq!(world.sectors[1].special, Some(Special::Lift { low: -16 }));
+    let source = "doomrust-level 1\nplayer 0 0 0 0\nsector 0 0 0 0 door crusher\n";
+    let err = level::parse(source, Path::new(".")).err().unwrap();
+    assert_eq!(err.to_string(), "3:21: sector has two specials");
+}
+
+#[test]
+fn movers_save_and_load() {
+    let sectors = ["sector 0 96 9 8", "sector 32 96 9 8 lift 0"];
+    let mut world = row(&sectors);
+    press(&mut world, Action::Use);
+    run(&mut world, 3);
+    let saved = world.save_movers();
+    assert_eq!(saved, "1 16 96 lowering 0 32\n");
+
+    let mut loaded = row(&sectors);
+    loaded.load_movers(&saved).unwrap();
+    assert_eq!(loaded.movers, world.movers);
+    for _ in 0..200 {
+        world.update();
+        loaded.update();
+        assert_eq!(loaded.sectors[1].z1, world.sectors[1].z1);
+    }
+
+    assert_eq!(
+        loaded.load_movers("1 0 96 idle 0 32\n0 0 96 flying 0 0"),
+        Err("2: unknown state `flying`".to_owned())
+    );
+    assert_eq!(
+        loaded.load_movers("0 0 96 idle 0 32"),
+        Err("1: sector 0 has no special".to_owned())
+    );
+}
@@ -0,0 +1,123 @@
+//! Palettes, colormaps and screen flashes.
+
+mod common;
+
+use doomrust::colormap::{self, Colormap, FULL_BRIGHT, LEVELS};
+use doomrust::palette::{self, Palette, COLORS, DAMAGE_PALETTES, PALETTES};
+use doomrust::{HEIGHT, WIDTH};
+
+use common::default_world;
+
+#[test]
+fn every_palette_color_is_its_own_nearest() {
+    let palette = Palette::new();
+    for (i, &rgb) in palette.colors.iter().enumerate() {
+        assert_eq!(palette.colors[palette.nearest(rgb) as usize], rgb, "{}", i);
+    }
+}
+
+#[test]
+fn playpal_files_fill_in_missing_flashes() {
+    let mut bytes = vec![0; COLORS * 3];
+    bytes[3..6].copy_from_slice(&[10, 20, 30]);
+    let palettes = palette::parse(&bytes).unwrap();
+    assert_eq!(palettes.len(), PALETTES);
+    assert_eq!(palettes[0].colors[1], [10, 20, 30]);
+    // The strongest damage flash is mostly red.
+    let red = palettes[DAMAGE_PALETTES.end - 1].colors[1];
+    assert!(red[0] > 200 && red[1] < 30, "{:?}", red);
+
+    let all = vec![7; COLORS * 3 * PALETTES];
+    assert!(palette::parse(&all)
+        .unwrap()
+        .iter()
+        .all(|p| p.colors[0] == [7, 7, 7]));
+    assert!(palette::parse(&bytes[..100]).is_err());
+    assert!(palette::parse(&[]).is_err());
+}
+
+#[test]
+fn colormaps_darken() {
+    let palette = Palette::new();
+    let colormap = Colormap::new(&palette);
+    let brightness = |c: u8| {
+        palette.colors[c as usize]
+            .iter()
+            .map(|&v| v as u32)
+            .sum::<u32>()
+    };
+    for color in 0..=255u8 {
+        let unshaded = colormap.shade(color, 0);
+        assert_eq!(
+            palette.colors[unshaded as usize],
+            palette.colors[color as usize]
+        );
+        let mut last = brightness(color);
+        for level in 1..LEVELS {
+            let shaded = brightness(colormap.shade(color, level));
+            assert!(shaded <= last + 24, "color {} level {}", color, level);
+            last = shaded;
+        }
+    }
+}
+
+#[test]
+fn colormap_levels_follow_light_and_distance() {
+    assert_eq!(colormap::level(FULL_BRIGHT, 0.0), 0);
+    assert_eq!(colormap::level(FULL_BRIGHT, 64.0), 0);
+    assert_eq!(colormap::level(FULL_BRIGHT, 96.0), 1);
+    assert_eq!(colormap::level(FULL_BRIGHT, 200.0), 4);
+    assert_eq!(colormap::level(247, 0.0), 1);
+    assert_eq!(colormap::level(160, 10.0), 11);
+    assert_eq!(colormap::level(160, 100.0), 12);
+    assert_eq!(colormap::level(0, 0.0), LEVELS - 1);
+    assert_eq!(colormap::level(0, 1000.0), LEVELS - 1);
+    assert_eq!(colormap::level(128, 1e9), LEVELS - 1);
+    // Darker never gives a lighter colormap, near or far.
+    for depth in [0.0, 80.0, 300.0] {
+        for light in 1..=255 {
+            assert!(colormap::level(light, depth) <= colormap::level(light - 1, depth));
+        }
+    }
+}
+
+#[test]
+fn dim_sectors_render_darker() {
+    let brightness = |frame: &[u8]| {
+        frame
+            .chunks_exact(4)
+            .map(|rgba| rgba[..3].iter().map(|&c| c as u64).sum::<u64>())
+            .sum::<u64>()
+    };
+    let mut world = default_world();
+    let mut light_all = |light: u8| {
+        for sector in &mut world.sectors {
+            sector.light = light;
+        }
+        brightness(&world.render_frame(&world.player.camera()))
+    };
+    let lit = light_all(FULL_BRIGHT);
+    let dim = light_all(96);
+    assert!(dim * 4 < lit * 3, "{} {}", dim, lit);
+}
+
+#[test]
+fn damage_flashes_red_and_fades() {
+    let mut world = default_world();
+    let normal = world.render_frame(&world.player.camera());
+    world.flash_damage(100);
+    assert_eq!(world.palette(), &world.palettes[DAMAGE_PALETTES.end - 1]);
+    let hurt = world.render_frame(&world.player.camera());
+    let redness = |frame: &[u8]| -> i64 {
+        frame
+            .chunks_exact(4)
+            .map(|p| p[0] as i64 - p[1] as i64)
+            .sum()
+    };
+    assert!(redness(&hurt) > redness(&normal) + (WIDTH * HEIGHT) as i64 * 50);
+
+    for _ in 0..100 {
+        world.update();
+    }
+    assert_eq!(world.palette(), &world.palettes[0]);
+}
@@ -0,0 +1,228 @@
+//! Level scripts: events, the world API, errors and the sandbox.
+
+use std::path::Path;
+
+use doomrust::script::{Event, MAX_STEPS};
+use doomrust::validate::Problem;
+use doomrust::{level, Action, Level, World};
+
+/// Two rooms, the line between them running script 7. The player stands
+/// in the west room facing the east one.
+const ROOMS: &str = "\
+doomrust-level 1
+player 32 32 0 90
+sprite barrel barrel
+
+sector 0 72 9 8
+wall 0 0 0 64 10
+wall 0 64 64 64 10
+wall 64 64 64 0 10 portal 1 trigger walk once script 7
+wall 64 0 0 0 10
+
+sector 0 72 9 8
+wall 64 0 64 64 10 portal 0
+wall 64 64 128 64 10
+wall 128 64 128 0 10
+wall 128 0 64 0 10
+";
+
+const SCRIPT: &str = "
+on start
+  let visits = 0
+  message(\"Welcome\")
+end
+
+on enter 1
+  let visits = visits + 1   # kept between events
+  message(\"visit \" + visits)
+  if player_health() < 100
+    message(\"you look hurt\")
+  else
+    message(\"at \" + player_x() + \" \" + player_y())
+  end
+end
+
+on trigger 7
+  set_floor(1, floor(1) + 16)
+  set_light(0, 40)
+  let thing = spawn(\"barrel\", 96, 32, 0, 90)
+end
+";
+
+fn parse(source: &str) -> Result<Level, level::Error> {
+    level::parse(source, Path::new("."))
+}
+
+fn rooms(script: &str) -> World {
+    World::new(parse(&format!("{}{}", ROOMS, script)).unwrap())
+}
+
+fn walk_east(world: &mut World, ticks: usize) {
+    world.actions.set(Action::Forward, true);
+    for _ in 0..ticks {
+        world.update();
+    }
+    world.actions.set(Action::Forward, false);
+}
+
+#[test]
+fn handlers_run_on_their_events() {
+    let mut world = rooms(SCRIPT);
+    assert_eq!(world.messages, ["Welcome"]);
+    world.messages.clear();
+
+    walk_east(&mut world, 8);
+    assert_eq!(world.player.sector, Some(1));
+    assert_eq!(world.sectors[1].z1, 16);
+    assert_eq!(world.sectors[0].light, 40);
+    assert_eq!(world.things.len(), 1);
+    assert_eq!(world.things[0].position.x, 96.0);
+    assert_eq!(world.messages[0], "visit 1");
+    assert!(world.messages[1].starts_with("at "));
+    assert_eq!(world.script_errors, []);
+
+    // Back and in again: the count goes on.
+    world.player.position.x = 32.0;
+    world.update();
+    world.player.position.x = 96.0;
+    world.player.health = 50;
+    world.update();
+    assert_eq!(world.messages[2..], ["visit 2", "you look hurt"]);
+}
+
+#[test]
+fn scripts_are_saved_with_the_level() {
+    let level = parse(&format!("{}{}", ROOMS, SCRIPT)).unwrap();
+    assert_eq!(level.script.handlers.len(), 3);
+    assert_eq!(level.script.handlers[1].event, Event::Enter(1));
+    assert_eq!(level.script.handlers[1].line, 22);
+    let saved = level.to_string();
+    assert!(saved.contains("\non enter 1\n  let visits = visits + 1   # kept between events\n"));
+    let reloaded = parse(&saved).unwrap();
+    assert_eq!(reloaded.script.handlers.len(), 3);
+    assert_eq!(reloaded.to_string(), saved);
+}
+
+#[test]
+fn script_mistakes_are_found_at_their_place_in_the_level() {
+    let error = |script: &str| {
+        parse(&format!("{}{}", ROOMS, script))
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    // The script starts on line 16 of the level.
+    assert_eq!(
+        error("on start\n  explode(1)\nend\n"),
+        "17:3: unknown function `explode`"
+    );
+    assert_eq!(
+        error("on start\n  set_floor(1)\nend\n"),
+        "17:3: `set_floor` takes 2 arguments, not 1"
+    );
+    assert_eq!(
+        error("on start\n  let x = (1 + \nend\n"),
+        "17:15: expected a value"
+    );
+    assert_eq!(
+        error("on start\n  message(\"hi)\nend\n"),
+        "17:11: string has no closing `\"`"
+    );
+    assert_eq!(
+        error("on start\n  if 1\n  message(\"hi\")\n"),
+        "16:1: `on` has no `end`"
+    );
+    assert_eq!(
+        error("on start\n  else\nend\n"),
+        "17:3: `else` without a block to close"
+    );
+    assert_eq!(
+        error("on start\n  1 + 2\nend\n"),
+        "17:3: expected a statement, such as a function call"
+    );
+    assert_eq!(
+        error("on fire\nend\n"),
+        "16:4: expected an event: `start`, `trigger` or `enter`"
+    );
+}
+
+#[test]
+fn runtime_errors_stop_the_handler() {
+    let world = rooms("on start\n  let x = 1 / (2 - 2)\n  message(\"not reached\")\nend\n");
+    assert_eq!(world.messages, Vec::<String>::new());
+    assert_eq!(world.script_errors.len(), 1);
+    assert_eq!(
+        world.script_errors[0].to_string(),
+        "17:11: division by zero"
+    );
+
+    let world = rooms("on start\n  set_floor(9, 0)\nend\n");
+    assert_eq!(world.script_errors[0].to_string(), "17:3: no sector 9");
+
+    let world = rooms("on start\n  message(missing)\nend\n");
+    assert_eq!(
+        world.script_errors[0].to_string(),
+        "17:11: unknown variable `missing`"
+    );
+}
+
+#[test]
+fn runaway_scripts_are_stopped() {
+    let mut world = rooms("on start\n  let n = 0\n  while true\n    let n = n + 1\n  end\nend\n");
+    assert_eq!(
+        world.script_errors[0].to_string(),
+        format!("19:13: stopped after {} steps", MAX_STEPS)
+    );
+    // The world goes on.
+    world.update();
+
+    let world = rooms("on start\n  let s = \"x\"\n  while 1\n    let s = s + s\n  end\nend\n");
+    assert!(world.script_errors[0]
+        .message
+        .starts_with("string longer than"));
+}
+
+#[test]
+fn script_problems_are_validated() {
+    let level = parse(&format!("{}{}", ROOMS, SCRIPT)).unwrap();
+    assert_eq!(level.validate(), []);
+
+    let level = parse(&format!("{}on enter 5\nend\n", ROOMS)).unwrap();
+    assert_eq!(
+        level.validate(),
+        [
+            Problem::MissingHandler {
+                sector: 0,
+                wall: 2,
+                event: 7,
+            },
+            Problem::HandlerSector {
+                line: 16,
+                sector: 5,
+            },
+        ]
+    );
+    assert_eq!(
+        level.validate()[0].to_string(),
+        "sector 0, wall 2: trigger runs script 7, which has no `on trigger 7`"
+    );
+}
+
+#[test]
+fn scripts_cant_hurt_past_the_limits() {
+    let world =
+        rooms("on start\n  hurt(1)\n  hurt(4294967295)\n  hurt(9223372036854775807)\nend\n");
+    assert_eq!(world.script_errors, []);
+    assert_eq!(world.player.health, 0);
+
+    let world = rooms("on start\n  hurt(0 - 5)\nend\n");
+    assert_eq!(world.script_errors[0].to_string(), "17:3: bad damage -5");
+    assert_eq!(world.player.health, 100);
+
+    let mut world = rooms("");
+    world.flash_damage(u32::MAX);
+    world.flash_damage(u32::MAX);
+    world.player.bonus_count = u32::MAX;
+    world.flash_bonus();
+    assert_eq!(world.player.bonus_count, u32::MAX);
+}
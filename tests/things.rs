@@ -0,0 +1,133 @@
+//! Things drawn as billboard sprites.
+
+mod common;
+
+use std::path::Path;
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::texture::{Texture, TRANSPARENT};
+use doomrust::{level, Camera, Thing, WIDTH};
+
+use common::empty_world;
+
+fn camera(x: f64, y: f64) -> Camera {
+    Camera {
+        position: Vec3::new(x, y, 20.0),
+        angle: Angle::from_degrees(0.0),
+        look: 0.0,
+    }
+}
+
+fn barrel(x: f64, y: f64) -> Thing {
+    Thing {
+        position: Vec3::new(x, y, 0.0),
+        angle: Angle::from_degrees(0.0),
+        sprite: 0,
+        health: None,
+    }
+}
+
+fn differing_columns(a: &[u8], b: &[u8]) -> Vec<u32> {
+    (0..WIDTH)
+        .filter(|&x| {
+            a.chunks_exact(4)
+                .zip(b.chunks_exact(4))
+                .skip(x as usize)
+                .step_by(WIDTH as usize)
+                .any(|(p, q)| p != q)
+        })
+        .collect()
+}
+
+#[test]
+fn things_in_view_are_drawn_centred_and_scaled() {
+    let mut world = empty_world();
+    let camera = camera(130.0, -140.0);
+    let empty = world.render_frame(&camera);
+
+    world.things.push(barrel(130.0, -80.0));
+    let near = differing_columns(&empty, &world.render_frame(&camera));
+    assert!(!near.is_empty());
+    let centre = (near[0] + near[near.len() - 1]) as f64 / 2.0;
+    assert!((centre - WIDTH as f64 / 2.0).abs() <= 1.0, "{:?}", near);
+
+    world.things[0].position.y = -20.0;
+    let far = differing_columns(&empty, &world.render_frame(&camera));
+    assert!(!far.is_empty() && far.len() < near.len());
+
+    // Behind the camera.
+    world.things[0].position.y = -150.0;
+    assert_eq!(world.render_frame(&camera), empty);
+}
+
+#[test]
+fn things_are_hidden_by_walls_in_front() {
+    let mut world = empty_world();
+    // Straight behind the raised floor of the pillar at (0, 0)-(32, 32).
+    let camera = camera(16.0, -100.0);
+    let empty = world.render_frame(&camera);
+    world.things.push(barrel(16.0, 48.0));
+    assert_eq!(world.render_frame(&camera), empty);
+
+    // Seen past its side, it shows.
+    world.things[0].position.x = 48.0;
+    assert_ne!(world.render_frame(&camera), empty);
+}
+
+#[test]
+fn transparent_texels_are_not_drawn() {
+    let mut world = empty_world();
+    let camera = camera(130.0, -140.0);
+    let empty = world.render_frame(&camera);
+
+    let mut ppm = b"P6 4 4 255\n".to_vec();
+    ppm.extend(TRANSPARENT.repeat(16));
+    let mut sprite = Texture::from_ppm(&ppm).unwrap();
+    sprite.quantize(&world.palettes[0]);
+    world.sprites.push(sprite);
+    world.things.push(Thing {
+        sprite: world.sprites.len() - 1,
+        ..barrel(130.0, -100.0)
+    });
+    assert_eq!(world.render_frame(&camera), empty);
+}
+
+#[test]
+fn malformed_ppm_images_are_refused() {
+    let error = |ppm: &[u8]| Texture::from_ppm(ppm).err().unwrap();
+    assert_eq!(error(b"P6 1 1 255"), "missing pixel data");
+    assert_eq!(error(b"P6 1 1 255\n"), "truncated pixel data");
+    let huge = format!("P6 {} {} 255\n", usize::MAX, 2);
+    assert_eq!(
+        error(huge.as_bytes()),
+        format!("image of {}x2 is too large", usize::MAX)
+    );
+    assert_eq!(error(b"P3 1 1 255\n"), "not a binary PPM (P6) image");
+}
+
+#[test]
+fn levels_place_things() {
+    let source = "doomrust-level 1\n\
+                  player 0 0 0 0\n\
+                  sprite keg barrel\n\
+                  thing 10 20 4 90 keg\n\
+                  sector 0 64 0 8\n\
+                  wall 0 0 0 64 1\n\
+                  wall 0 64 64 64 1\n\
+                  wall 64 64 0 0 1\n";
+    let level = level::parse(source, Path::new(".")).unwrap();
+    assert_eq!(level.sprites.len(), 1);
+    assert_eq!(
+        level.things,
+        [Thing {
+            position: Vec3::new(10.0, 20.0, 4.0),
+            angle: Angle::from_degrees(90.0),
+            sprite: 0,
+            health: None,
+        }]
+    );
+
+    let unknown = source.replace("thing 10 20 4 90 keg", "thing 10 20 4 90 cask");
+    let err = level::parse(&unknown, Path::new(".")).err().unwrap();
+    assert_eq!(err.to_string(), "4:18: unknown sprite `cask`");
+}
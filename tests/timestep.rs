@@ -0,0 +1,69 @@
+//! The simulation advances by the same number of ticks however real time is
+//! split into frames.
+
+mod common;
+
+use std::time::Duration;
+
+use doomrust::timestep::{Timestep, MAX_FRAME_TIME, TICK, TICK_RATE};
+use doomrust::Action;
+
+use common::default_world;
+
+/// Walk forward for one second of frames of length `frame`, returning the
+/// player's position.
+fn walk_for_a_second(frame: Duration) -> (f64, f64) {
+    let mut world = default_world();
+    world.actions.set(Action::Forward, true);
+    let mut timestep = Timestep::new();
+    let mut elapsed = Duration::ZERO;
+    while elapsed < Duration::from_secs(1) {
+        for _ in 0..timestep.advance(frame) {
+            world.update();
+        }
+        elapsed += frame;
+    }
+    (world.player.position.x, world.player.position.y)
+}
+
+#[test]
+fn ticks_do_not_depend_on_frame_rate() {
+    for fps in [30, 60, 144, 1000] {
+        let mut timestep = Timestep::new();
+        let ticks: u32 = (0..fps)
+            .map(|_| timestep.advance(Duration::from_secs(1) / fps))
+            .sum();
+        // Rounding may leave the last tick a hair short of due.
+        assert!(
+            ticks == TICK_RATE || ticks == TICK_RATE - 1,
+            "{} ticks at {} fps",
+            ticks,
+            fps
+        );
+    }
+}
+
+#[test]
+fn movement_does_not_depend_on_frame_rate() {
+    let (x, y) = walk_for_a_second(TICK);
+    for fps in [30, 60, 144] {
+        let (fx, fy) = walk_for_a_second(Duration::from_secs(1) / fps);
+        assert!((fx - x).abs() < 8.0 && (fy - y).abs() < 8.0, "{} fps", fps);
+    }
+}
+
+#[test]
+fn alpha_is_the_fraction_of_a_tick_left_over() {
+    let mut timestep = Timestep::new();
+    assert_eq!(timestep.advance(TICK / 4), 0);
+    assert!((timestep.alpha() - 0.25).abs() < 1e-6);
+    assert_eq!(timestep.advance(TICK), 1);
+    assert!((timestep.alpha() - 0.25).abs() < 1e-6);
+}
+
+#[test]
+fn long_frames_are_cut_to_the_budget() {
+    let mut timestep = Timestep::new();
+    let ticks = timestep.advance(Duration::from_secs(10));
+    assert_eq!(ticks, (MAX_FRAME_TIME.as_nanos() / TICK.as_nanos()) as u32);
+}
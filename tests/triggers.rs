@@ -0,0 +1,182 @@
+//! Walls triggered by walking over, using and shooting them.
+
+use std::path::Path;
+
+use doomrust::math::Angle;
+use doomrust::mover::State;
+use doomrust::trigger::{Activation, Effect, Trigger};
+use doomrust::validate::Problem;
+use doomrust::{level, Action, Level, World};
+
+/// A room with a light switch on its north wall and a line into a hall
+/// that opens the door at the far end of the hall, whose south wall ends
+/// the level when shot. The player stands in the room facing the hall.
+const TRIGGER_LEVEL: &str = "\
+doomrust-level 1
+player 32 32 0 90
+
+sector 0 72 9 8
+wall 0 0 0 64 10
+wall 0 64 64 64 10 trigger use light 2 40
+wall 64 64 64 0 10 portal 1 trigger walk once start 1
+wall 64 0 0 0 10
+
+sector 0 72 9 8 tag 2
+wall 64 0 64 64 10 portal 0
+wall 64 64 128 64 10
+wall 128 64 128 48 10
+wall 128 48 128 16 10 portal 2
+wall 128 16 128 0 10
+wall 128 0 64 0 10 trigger shoot exit
+
+sector 0 0 9 8 door tag 1
+wall 128 16 128 48 3 portal 1
+wall 128 48 136 48 10
+wall 136 48 136 16 10
+wall 136 16 128 16 10
+";
+
+fn trigger_level() -> Level {
+    level::parse(TRIGGER_LEVEL, Path::new(".")).unwrap()
+}
+
+fn press(world: &mut World, action: Action) {
+    world.actions.set(action, true);
+    world.update();
+    world.actions.set(action, false);
+}
+
+#[test]
+fn triggers_are_saved_with_the_level() {
+    let level = trigger_level();
+    assert_eq!(
+        level.walls[2].trigger,
+        Some(Trigger {
+            activation: Activation::Walk,
+            effect: Effect::Start { tag: 1 },
+            once: true,
+        })
+    );
+    assert_eq!(level.sectors[2].tag, Some(1));
+    assert_eq!(level.validate(), []);
+
+    let saved = level.to_string();
+    assert!(saved.contains("wall 64 64 64 0 10 portal 1 trigger walk once start 1\n"));
+    assert!(saved.contains("sector 0 0 9 8 door tag 1\n"));
+    let reloaded = level::parse(&saved, Path::new(".")).unwrap();
+    assert_eq!(reloaded.to_string(), saved);
+
+    let source =
+        "doomrust-level 1\nplayer 0 0 0 0\nsector 0 0 0 0\nwall 0 0 0 64 1 trigger fly exit\n";
+    let err = level::parse(source, Path::new(".")).err().unwrap();
+    assert_eq!(err.to_string(), "4:25: unknown activation `fly`");
+}
+
+#[test]
+fn walking_over_a_line_starts_the_tagged_movers() {
+    let mut world = World::new(trigger_level());
+    world.actions.set(Action::Forward, true);
+    for _ in 0..4 {
+        world.update();
+    }
+    assert_eq!(world.movers[0].state, State::Idle);
+    for _ in 0..4 {
+        world.update();
+    }
+    assert_eq!(world.player.sector, Some(1));
+    assert_eq!(world.movers[0].state, State::Rising);
+    // Walk-once lines are spent.
+    assert_eq!(world.walls[2].trigger, None);
+}
+
+#[test]
+fn triggers_only_ever_open_doors() {
+    let mut world = World::new(trigger_level());
+    world.movers[0].state = State::Waiting(10);
+    world.walls[2].trigger.as_mut().unwrap().once = false;
+    world.fire(2);
+    assert_eq!(world.movers[0].state, State::Waiting(10));
+    world.movers[0].state = State::Lowering;
+    world.fire(2);
+    assert_eq!(world.movers[0].state, State::Rising);
+}
+
+#[test]
+fn using_a_switch_changes_the_light() {
+    let mut world = World::new(trigger_level());
+    world.player.position.y = 40.0;
+    world.player.angle = Angle::from_degrees(0.0);
+    press(&mut world, Action::Use);
+    assert_eq!(world.sectors[1].light, 40);
+    assert_eq!(world.sectors[0].light, 255);
+    // Switches not marked once can be used again.
+    assert!(world.walls[1].trigger.is_some());
+
+    // Out of reach, nothing happens.
+    let mut world = World::new(trigger_level());
+    world.player.position.y = 16.0;
+    world.player.angle = Angle::from_degrees(0.0);
+    press(&mut world, Action::Use);
+    assert_eq!(world.sectors[1].light, 255);
+}
+
+#[test]
+fn shooting_a_wall_ends_the_level() {
+    let mut world = World::new(trigger_level());
+    // Shots don't set off other triggers.
+    world.shoot_wall(1);
+    world.shoot_wall(2);
+    assert_eq!(world.sectors[1].light, 255);
+    assert_eq!(world.movers[0].state, State::Idle);
+    assert!(!world.exited);
+
+    world.shoot_wall(9);
+    assert!(world.exited);
+}
+
+#[test]
+fn triggers_without_sectors_are_found() {
+    let mut level = trigger_level();
+    level.sectors[2].tag = None;
+    let problems = level.validate();
+    assert_eq!(
+        problems,
+        [Problem::UnknownTag {
+            sector: 0,
+            wall: 2,
+            tag: 1,
+        }]
+    );
+    assert_eq!(
+        problems[0].to_string(),
+        "sector 0, wall 2: trigger acts on tag 1, which no sector has"
+    );
+}
+
+#[test]
+fn textured_trigger_walls_are_saved_and_loaded() {
+    let source = TRIGGER_LEVEL
+        .replace(
+            "player 32 32 0 90\n",
+            "player 32 32 0 90\ntexture bricks bricks\n",
+        )
+        .replace(
+            "wall 0 64 64 64 10 trigger use light 2 40",
+            "wall 0 64 64 64 10 texture bricks trigger use light 2 40",
+        )
+        .replace(
+            "wall 64 64 64 0 10 portal 1",
+            "wall 64 64 64 0 10 texture bricks portal 1",
+        );
+    let level = level::parse(&source, Path::new(".")).unwrap();
+    assert!(level.walls[1].texture.is_some());
+    assert!(level.walls[1].trigger.is_some());
+    assert_eq!(level.walls[2].neighbour, Some(1));
+
+    let saved = level.to_string();
+    assert!(saved.contains(" texture bricks trigger use light 2 40\n"));
+    let reloaded = level::parse(&saved, Path::new(".")).unwrap();
+    assert_eq!(reloaded.walls[1].trigger, level.walls[1].trigger);
+    assert_eq!(reloaded.walls[1].texture, level.walls[1].texture);
+    assert_eq!(reloaded.to_string(), saved);
+}
@@ -0,0 +1,153 @@
+//! Level validation.
+
+use std::path::Path;
+
+use doomrust::validate::Problem;
+use doomrust::{level, Level, DEFAULT_LEVEL};
+
+/// Two 64 unit square rooms side by side, joined by a portal.
+const TWO_ROOMS: &str = "\
+doomrust-level 1
+player 32 32 0 0
+
+sector 0 64 9 8
+wall 0 0 0 64 10
+wall 0 64 64 64 10
+wall 64 64 64 0 10 portal 1
+wall 64 0 0 0 10
+
+sector 0 64 9 8
+wall 64 0 64 64 10 portal 0
+wall 64 64 128 64 10
+wall 128 64 128 0 10
+wall 128 0 64 0 10
+";
+
+fn two_rooms() -> Level {
+    level::parse(TWO_ROOMS, Path::new(".")).unwrap()
+}
+
+#[test]
+fn sound_levels_have_no_problems() {
+    let default = level::parse(DEFAULT_LEVEL, Path::new(".")).unwrap();
+    assert_eq!(default.validate(), []);
+    assert_eq!(two_rooms().validate(), []);
+}
+
+#[test]
+fn open_loops_are_found() {
+    let mut level = two_rooms();
+    level.walls[1].x2 = 48;
+    let problems = level.validate();
+    assert!(problems.contains(&Problem::Unclosed {
+        sector: 0,
+        wall: 1,
+        at: (48, 64),
+    }));
+    assert_eq!(
+        problems[0].to_string(),
+        "sector 0, wall 1: loop is not closed, no wall goes on from (48, 64)"
+    );
+}
+
+#[test]
+fn zero_length_walls_are_found() {
+    let mut level = two_rooms();
+    level.walls[7].x1 = 128;
+    level.walls[7].y1 = 0;
+    level.walls[7].x2 = 128;
+    level.walls[7].y2 = 0;
+    assert!(level.validate().contains(&Problem::ZeroLength {
+        sector: 1,
+        wall: 7,
+        at: (128, 0),
+    }));
+}
+
+#[test]
+fn counter_clockwise_sectors_are_found() {
+    let mut level = two_rooms();
+    for wall in &mut level.walls[4..8] {
+        (wall.x1, wall.y1, wall.x2, wall.y2) = (wall.x2, wall.y2, wall.x1, wall.y1);
+    }
+    let problems = level.validate();
+    assert!(problems.contains(&Problem::WrongWinding { sector: 1 }));
+    assert!(!problems.contains(&Problem::WrongWinding { sector: 0 }));
+}
+
+#[test]
+fn crossing_walls_are_found() {
+    // A bow tie: the second and fourth walls cross in the middle.
+    let mut level = two_rooms();
+    level.walls[1].x2 = 64;
+    level.walls[1].y2 = 0;
+    level.walls[2].x1 = 64;
+    level.walls[2].y1 = 0;
+    level.walls[2].x2 = 64;
+    level.walls[2].y2 = 64;
+    level.walls[3].x1 = 64;
+    level.walls[3].y1 = 64;
+    assert!(level.validate().contains(&Problem::Crossing {
+        sector: 0,
+        walls: (1, 3),
+    }));
+}
+
+#[test]
+fn overlapping_sectors_are_found() {
+    let mut level = two_rooms();
+    for wall in &mut level.walls[4..8] {
+        wall.x1 -= 32;
+        wall.x2 -= 32;
+    }
+    assert!(level
+        .validate()
+        .contains(&Problem::Overlap { sectors: (0, 1) }));
+
+    // One sector wholly inside the other.
+    let mut level = two_rooms();
+    for wall in &mut level.walls[4..8] {
+        wall.x1 = (wall.x1 - 64) / 4 + 24;
+        wall.y1 = wall.y1 / 4 + 24;
+        wall.x2 = (wall.x2 - 64) / 4 + 24;
+        wall.y2 = wall.y2 / 4 + 24;
+    }
+    assert!(level
+        .validate()
+        .contains(&Problem::Overlap { sectors: (0, 1) }));
+}
+
+#[test]
+fn bad_portals_are_found() {
+    let mut level = two_rooms();
+    level.walls[2].neighbour = Some(5);
+    level.walls[5].neighbour = Some(1);
+    level.walls[6].neighbour = Some(0);
+    let problems = level.validate();
+    assert!(problems.contains(&Problem::DanglingPortal {
+        sector: 0,
+        wall: 2,
+        neighbour: 5,
+    }));
+    assert!(problems.contains(&Problem::PortalToSelf { sector: 1, wall: 5 }));
+    assert!(problems.contains(&Problem::OneWayPortal {
+        sector: 1,
+        wall: 6,
+        neighbour: 0,
+    }));
+}
+
+#[test]
+fn bad_wall_ranges_are_found() {
+    let mut level = two_rooms();
+    level.sectors[1].wall_end = 9;
+    assert_eq!(
+        level.validate(),
+        [Problem::WallsOutOfRange {
+            sector: 1,
+            wall_start: 4,
+            wall_end: 9,
+            walls: 8,
+        }]
+    );
+}
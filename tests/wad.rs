@@ -0,0 +1,248 @@
+//! WAD files and their maps, from synthetic WADs built here.
+
+use doomrust::wad::{self, Kind, Wad};
+use doomrust::SKY;
+
+/// A WAD of the given kind holding `lumps`, the directory after the data.
+fn build(kind: &[u8; 4], lumps: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut data = Vec::<u8>::new();
+    let mut directory = Vec::new();
+    for (name, lump) in lumps {
+        directory.extend((12 + data.len() as i32).to_le_bytes());
+        directory.extend((lump.len() as i32).to_le_bytes());
+        directory.extend(name8(name));
+        data.extend(lump);
+    }
+    let mut bytes = kind.to_vec();
+    bytes.extend((lumps.len() as i32).to_le_bytes());
+    bytes.extend((12 + data.len() as i32).to_le_bytes());
+    bytes.extend(data);
+    bytes.extend(directory);
+    bytes
+}
+
+fn name8(name: &str) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    bytes
+}
+
+fn shorts(values: &[i16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn sector(floor: i16, ceiling: i16, floor_pic: &str, ceiling_pic: &str, light: i16) -> Vec<u8> {
+    let mut r = shorts(&[floor, ceiling]);
+    r.extend(name8(floor_pic));
+    r.extend(name8(ceiling_pic));
+    r.extend(shorts(&[light, 0, 0]));
+    r
+}
+
+fn sidedef(upper: &str, lower: &str, middle: &str, sector: i16) -> Vec<u8> {
+    let mut r = shorts(&[0, 0]);
+    r.extend(name8(upper));
+    r.extend(name8(lower));
+    r.extend(name8(middle));
+    r.extend(shorts(&[sector]));
+    r
+}
+
+/// `(v1, v2, front, back)`, -1 for no back side.
+fn linedef(v1: i16, v2: i16, front: i16, back: i16) -> Vec<u8> {
+    shorts(&[v1, v2, 0, 0, 0, front, back])
+}
+
+/// The lumps of a map of two rooms side by side: a west room 0 to 64
+/// wide, floor 0 and ceiling `ceiling`, and an east room 64 to 128 on a
+/// step 16 high under the sky, joined by a two-sided line. The player
+/// starts in the west room facing east, past an imp.
+fn two_rooms(marker: &str, ceiling: i16) -> Vec<(&str, Vec<u8>)> {
+    let things = [[100, 32, 0, 3001, 7], [32, 32, 0, 1, 7]]
+        .iter()
+        .flat_map(|thing| shorts(thing))
+        .collect();
+    let linedefs = [
+        linedef(0, 1, 0, -1),
+        linedef(1, 2, 0, -1),
+        linedef(2, 3, 2, 3),
+        linedef(3, 0, 0, -1),
+        linedef(2, 4, 1, -1),
+        linedef(4, 5, 1, -1),
+        linedef(5, 3, 1, -1),
+    ]
+    .concat();
+    let sidedefs = [
+        sidedef("-", "-", "STARTAN3", 0),
+        sidedef("-", "-", "STARTAN3", 1),
+        sidedef("-", "STEP1", "-", 0),
+        sidedef("-", "-", "-", 1),
+    ]
+    .concat();
+    let vertexes = shorts(&[0, 0, 0, 64, 64, 64, 64, 0, 128, 64, 128, 0]);
+    let sectors = [
+        sector(0, ceiling, "FLOOR4_8", "CEIL3_5", 160),
+        sector(16, 100, "FLOOR4_8", "F_SKY1", 255),
+    ]
+    .concat();
+    vec![
+        (marker, Vec::new()),
+        ("THINGS", things),
+        ("LINEDEFS", linedefs),
+        ("SIDEDEFS", sidedefs),
+        ("VERTEXES", vertexes),
+        ("SECTORS", sectors),
+    ]
+}
+
+fn format_error(bytes: &[u8]) -> String {
+    match Wad::parse(bytes) {
+        Err(err @ wad::Error::Format(_)) => err.to_string(),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("parsed a bad WAD"),
+    }
+}
+
+#[test]
+fn the_directory_is_read() {
+    let bytes = build(
+        b"IWAD",
+        &[
+            ("PLAYPAL", vec![1, 2, 3]),
+            ("colormap", vec![]),
+            ("ENDOOM", vec![9; 20]),
+        ],
+    );
+    let wad = Wad::parse(&bytes).unwrap();
+    assert_eq!(wad.kind, Kind::Iwad);
+    let names: Vec<_> = wad.lumps.iter().map(|lump| lump.name.as_str()).collect();
+    assert_eq!(names, ["PLAYPAL", "COLORMAP", "ENDOOM"]);
+    assert_eq!(wad.lump("playpal"), Some(&[1, 2, 3][..]));
+    assert_eq!(wad.lump("ENDOOM").unwrap().len(), 20);
+    assert_eq!(wad.lump("COLORMAP"), Some(&[][..]));
+    assert_eq!(wad.lump("TITLEPIC"), None);
+}
+
+#[test]
+fn later_lumps_replace_earlier_ones() {
+    let mut lumps = vec![("PLAYPAL", vec![1]), ("PLAYPAL", vec![2])];
+    lumps.extend(two_rooms("MAP01", 128));
+    lumps.extend(two_rooms("MAP01", 96));
+    let wad = Wad::parse(&build(b"PWAD", &lumps)).unwrap();
+    assert_eq!(wad.kind, Kind::Pwad);
+    assert_eq!(wad.lump("PLAYPAL"), Some(&[2][..]));
+    let level = wad::load_map(&wad, "MAP01").unwrap();
+    assert_eq!(level.sectors[0].z2, 96);
+}
+
+#[test]
+fn maps_are_listed_in_order() {
+    let mut lumps = two_rooms("E1M1", 128);
+    lumps.push(("DEMO1", vec![0; 4]));
+    lumps.extend(two_rooms("E1M2", 128));
+    let wad = Wad::parse(&build(b"IWAD", &lumps)).unwrap();
+    assert_eq!(wad.maps(), ["E1M1", "E1M2"]);
+    assert_eq!(
+        wad::load_map(&wad, "E1M3").err().unwrap().to_string(),
+        "no map E1M3 in IWAD"
+    );
+}
+
+#[test]
+fn maps_become_levels() {
+    let wad = Wad::parse(&build(b"IWAD", &two_rooms("E1M1", 128))).unwrap();
+    let level = wad::load_map(&wad, "E1M1").unwrap();
+    assert_eq!(level.validate(), []);
+
+    let west = &level.sectors[0];
+    assert_eq!((west.z1, west.z2, west.light), (0, 128, 160));
+    assert_ne!(west.ceiling_color, SKY);
+    let east = &level.sectors[1];
+    assert_eq!((east.z1, east.z2, east.light), (16, 100, 255));
+    assert_eq!(east.ceiling_color, SKY);
+
+    // The two-sided line is a portal from each side.
+    let walls = |s: usize| &level.walls[level.sectors[s].wall_start..level.sectors[s].wall_end];
+    assert_eq!(walls(0).len(), 4);
+    assert_eq!(walls(1).len(), 4);
+    let west_portal = walls(0).iter().find(|w| w.neighbour.is_some()).unwrap();
+    let east_portal = walls(1).iter().find(|w| w.neighbour.is_some()).unwrap();
+    assert_eq!(west_portal.neighbour, Some(1));
+    assert_eq!(east_portal.neighbour, Some(0));
+    assert_eq!(
+        (
+            west_portal.x1,
+            west_portal.y1,
+            west_portal.x2,
+            west_portal.y2
+        ),
+        (64, 64, 64, 0)
+    );
+    assert_eq!(
+        (
+            east_portal.x1,
+            east_portal.y1,
+            east_portal.x2,
+            east_portal.y2
+        ),
+        (64, 0, 64, 64)
+    );
+    assert_eq!(walls(0).iter().filter(|w| w.neighbour.is_none()).count(), 3);
+
+    // The player start, not the imp, places the player.
+    assert_eq!(level.player.position.x, 32.0);
+    assert_eq!(level.player.position.y, 32.0);
+    assert_eq!(level.player.position.z, 0.0);
+    assert_eq!(level.player.angle.degrees(), 90.0);
+}
+
+#[test]
+fn bad_wads_are_refused() {
+    assert_eq!(
+        format_error(b"JWAD\0\0\0\0\x0c\0\0\0"),
+        "bad WAD: missing IWAD/PWAD signature"
+    );
+    assert_eq!(format_error(b"IWAD\x01\0"), "bad WAD: truncated header");
+
+    // A count far beyond what the file holds is refused, not allocated.
+    let mut huge = b"PWAD".to_vec();
+    huge.extend(i32::MAX.to_le_bytes());
+    huge.extend(12i32.to_le_bytes());
+    assert_eq!(
+        format_error(&huge),
+        format!(
+            "bad WAD: directory of {} lumps doesn't fit in the file",
+            i32::MAX
+        )
+    );
+
+    let mut bytes = build(b"IWAD", &[("PLAYPAL", vec![1, 2, 3])]);
+    // The lump's size, past the end of the file.
+    bytes[19] = 100;
+    assert_eq!(
+        format_error(&bytes),
+        "bad WAD: lump PLAYPAL lies outside the file"
+    );
+    let mut bytes = build(b"IWAD", &[("PLAYPAL", vec![1, 2, 3])]);
+    bytes.truncate(bytes.len() - 1);
+    assert_eq!(
+        format_error(&bytes),
+        "bad WAD: directory of 1 lumps doesn't fit in the file"
+    );
+
+    // Maps referring to what they don't have.
+    let mut lumps = two_rooms("E1M1", 128);
+    lumps[3].1[28] = 5;
+    let wad = Wad::parse(&build(b"IWAD", &lumps)).unwrap();
+    assert_eq!(
+        wad::load_map(&wad, "E1M1").err().unwrap().to_string(),
+        "sidedef 0 refers to missing sector 5"
+    );
+    let mut lumps = two_rooms("E1M1", 128);
+    lumps[1].1.truncate(10);
+    let wad = Wad::parse(&build(b"IWAD", &lumps)).unwrap();
+    assert_eq!(
+        wad::load_map(&wad, "E1M1").err().unwrap().to_string(),
+        "map E1M1 has no player 1 start"
+    );
+}
@@ -0,0 +1,271 @@
+//! Hitscan weapons: what shots hit, damage, puffs and the weapon sprite.
+
+use std::path::Path;
+
+use doomrust::math::{Angle, Vec3};
+use doomrust::weapon::{Target, PISTOL, PUFF_TICKS, SHOTGUN};
+use doomrust::{level, Action, Level, World, HEIGHT, SKY, WIDTH};
+
+/// A small room opening onto a long hall with a barrel in it, whose far
+/// wall ends the level when shot. The player stands in the room facing
+/// down the hall.
+const RANGE_LEVEL: &str = "\
+doomrust-level 1
+player 32 32 0 90
+sprite barrel barrel
+thing 160 32 0 0 barrel health 20
+
+sector 0 72 9 7
+wall 0 0 0 64 10
+wall 0 64 64 64 10
+wall 64 64 64 0 10 portal 1
+wall 64 0 0 0 10
+
+sector 0 72 9 7
+wall 64 0 64 64 10 portal 0
+wall 64 64 256 64 10
+wall 256 64 256 0 10 trigger shoot exit
+wall 256 0 64 0 10
+";
+
+fn range_level() -> Level {
+    level::parse(RANGE_LEVEL, Path::new(".")).unwrap()
+}
+
+fn fire(world: &mut World) {
+    world.actions.set(Action::Fire, true);
+    world.update();
+    world.actions.set(Action::Fire, false);
+}
+
+/// Where a level shot straight down the hall from the player's eyes hits.
+fn aim(world: &World, slope: f64) -> Option<(Target, Vec3)> {
+    let eye = world.player.camera().position;
+    world
+        .trace(eye, Angle::from_degrees(90.0), slope)
+        .map(|hit| (hit.target, hit.point))
+}
+
+#[test]
+fn shots_damage_things_until_they_are_destroyed() {
+    let mut world = World::new(range_level());
+    fire(&mut world);
+    let health = world.things[0].health.unwrap();
+    assert!((5..=15).contains(&(20 - health)), "{}", health);
+    assert_eq!(world.arsenal.puffs.len(), 1);
+    let puff = world.arsenal.puffs[0].position;
+    assert!(puff.x > 140.0 && puff.x < 148.0, "{:?}", puff);
+    assert!(!world.exited);
+
+    // Holding fire keeps shooting until the barrel is gone, then the shots
+    // reach the wall behind it.
+    world.actions.set(Action::Fire, true);
+    for _ in 0..PISTOL.refire * 4 {
+        world.update();
+    }
+    assert!(world.things[0].destroyed());
+    assert!(world.exited);
+}
+
+#[test]
+fn destroyed_things_keep_their_place() {
+    let script = "
+on start
+  let spare = spawn(\"barrel\", 16, 16, 0, 0)
+  message(\"\" + spare)
+end
+";
+    let level = level::parse(&format!("{}{}", RANGE_LEVEL, script), Path::new(".")).unwrap();
+    let mut world = World::new(level);
+    assert_eq!(world.script_errors, []);
+    let handle: usize = world.messages[0].parse().unwrap();
+    assert_eq!(handle, 1);
+
+    world.actions.set(Action::Fire, true);
+    for _ in 0..PISTOL.refire * 4 {
+        world.update();
+    }
+    assert!(world.things[0].destroyed());
+    // The handle still names the barrel the script spawned.
+    let spawned = &world.things[handle];
+    assert!(!spawned.destroyed());
+    assert_eq!((spawned.position.x, spawned.position.y), (16.0, 16.0));
+}
+
+#[test]
+fn things_without_health_only_stop_shots() {
+    let mut world = World::new(range_level());
+    world.things[0].health = None;
+    for _ in 0..5 {
+        world.arsenal.cooldown = 0;
+        fire(&mut world);
+    }
+    assert_eq!(world.things.len(), 1);
+    assert!(!world.exited);
+    assert_eq!(aim(&world, 0.0).unwrap().0, Target::Thing(0));
+}
+
+#[test]
+fn shots_follow_the_view_up_and_down() {
+    let mut world = World::new(range_level());
+    world.things.clear();
+    assert_eq!(aim(&world, 0.0).unwrap().0, Target::Wall(6));
+
+    // 45 degrees down from 20 units up lands 20 units ahead.
+    let (target, point) = aim(&world, -1.0).unwrap();
+    assert_eq!(target, Target::Floor(0));
+    assert!((point.x - 52.0).abs() < 1e-6 && point.z.abs() < 1e-6);
+    let (target, point) = aim(&world, 1.0).unwrap();
+    assert_eq!(target, Target::Ceiling(1));
+    assert!((point.x - 84.0).abs() < 1e-6 && (point.z - 72.0).abs() < 1e-6);
+    world.sectors[1].ceiling_color = SKY;
+    assert_eq!(aim(&world, 1.0), None);
+
+    // Looking all the way down fires at the floor.
+    world.player.look = 32.0;
+    fire(&mut world);
+    let puff = world.arsenal.puffs[0].position;
+    assert!(puff.x < 64.0 && puff.z < 4.0, "{:?}", puff);
+}
+
+#[test]
+fn portals_stop_shots_outside_their_opening() {
+    let mut world = World::new(range_level());
+    world.things.clear();
+    world.sectors[1].z1 = 32;
+    let (target, point) = aim(&world, 0.0).unwrap();
+    assert_eq!(target, Target::Wall(2));
+    assert!((point.x - 64.0).abs() < 1e-6);
+
+    world.sectors[1].z1 = 0;
+    world.sectors[1].z2 = 16;
+    assert_eq!(aim(&world, 0.0).unwrap().0, Target::Wall(2));
+    // Above an opening between two skies, shots are lost.
+    world.sectors[0].ceiling_color = SKY;
+    world.sectors[1].ceiling_color = SKY;
+    assert_eq!(aim(&world, 0.0), None);
+}
+
+#[test]
+fn level_shots_hit_planes_moved_past_the_eye_at_once() {
+    let mut world = World::new(range_level());
+    let eye = world.player.camera().position;
+    // The ceiling lowered below the eyes, as a script or mover might.
+    world.sectors[0].z2 = 10;
+    assert_eq!(aim(&world, 0.0), Some((Target::Ceiling(0), eye)));
+    world.sectors[0].z2 = 72;
+    world.sectors[0].z1 = 30;
+    assert_eq!(aim(&world, 0.0), Some((Target::Floor(0), eye)));
+
+    fire(&mut world);
+    let puff = world.arsenal.puffs[0].position;
+    assert!(
+        puff.x.is_finite() && puff.y.is_finite() && puff.z.is_finite(),
+        "{:?}",
+        puff
+    );
+}
+
+#[test]
+fn weapons_refire_and_switch() {
+    let mut world = World::new(range_level());
+    world.things.clear();
+    world.actions.set(Action::Fire, true);
+    let mut shots = 0;
+    for _ in 0..PISTOL.refire * 2 + 1 {
+        world.update();
+        if world.arsenal.cooldown == PISTOL.refire {
+            shots += 1;
+        }
+    }
+    assert_eq!(shots, 3);
+    world.actions.set(Action::Fire, false);
+
+    // No switching while firing.
+    world.actions.set(Action::NextWeapon, true);
+    world.update();
+    world.actions.set(Action::NextWeapon, false);
+    assert_eq!(world.arsenal.weapon(), PISTOL);
+    world.arsenal.cooldown = 0;
+    world.actions.set(Action::NextWeapon, true);
+    world.update();
+    world.actions.set(Action::NextWeapon, false);
+    assert_eq!(world.arsenal.weapon(), SHOTGUN);
+
+    // The shotgun's pellets spread over the wall.
+    world.arsenal.puffs.clear();
+    fire(&mut world);
+    let puffs = &world.arsenal.puffs;
+    assert_eq!(puffs.len(), SHOTGUN.pellets as usize);
+    assert!(puffs.iter().all(|puff| puff.position.x > 250.0));
+    let ys = puffs.iter().map(|puff| puff.position.y);
+    let spread = ys.clone().fold(f64::MIN, f64::max) - ys.fold(f64::MAX, f64::min);
+    assert!(spread > 4.0 && spread < 40.0, "{}", spread);
+    assert_eq!(world.arsenal.cooldown, SHOTGUN.refire);
+}
+
+#[test]
+fn shots_replay_the_same() {
+    let shoot = || {
+        let mut world = World::new(range_level());
+        world.arsenal.current = 1;
+        fire(&mut world);
+        (world.arsenal.puffs.clone(), world.things.clone())
+    };
+    assert_eq!(shoot(), shoot());
+}
+
+#[test]
+fn puffs_and_the_weapon_are_drawn() {
+    let mut world = World::new(range_level());
+    world.things.clear();
+    let camera = world.player.camera();
+    let view = world.render_frame(&camera);
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    world.draw(&mut frame, 1.0);
+    let pixel = |frame: &[u8], x: u32, y: u32| {
+        let i = ((y * WIDTH + x) * 4) as usize;
+        frame[i..i + 4].to_vec()
+    };
+    // The weapon covers the bottom of the middle of the view.
+    assert_ne!(
+        pixel(&frame, WIDTH / 2, HEIGHT - 4),
+        pixel(&view, WIDTH / 2, HEIGHT - 4)
+    );
+    assert_eq!(pixel(&frame, 8, HEIGHT - 4), pixel(&view, 8, HEIGHT - 4));
+
+    // Firing flashes and kicks the weapon, and puffs the wall.
+    let ready = frame.clone();
+    fire(&mut world);
+    world.draw(&mut frame, 1.0);
+    assert_ne!(frame, ready);
+    let camera = world.player.camera();
+    assert_ne!(world.render_frame(&camera), view);
+
+    // Both settle again.
+    for _ in 0..PISTOL.refire.max(PUFF_TICKS) {
+        world.update();
+    }
+    assert!(world.arsenal.puffs.is_empty());
+    world.draw(&mut frame, 1.0);
+    assert_eq!(frame, ready);
+}
+
+#[test]
+fn thing_health_is_saved_with_the_level() {
+    let level = range_level();
+    assert_eq!(level.things[0].health, Some(20));
+    let saved = level.to_string();
+    assert!(saved.contains("thing 160 32 0 0 barrel health 20\n"));
+    assert_eq!(
+        level::parse(&saved, Path::new(".")).unwrap().to_string(),
+        saved
+    );
+
+    let source = RANGE_LEVEL.replace("health 20", "health 0");
+    let err = level::parse(&source, Path::new(".")).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "4:32: expected a health above 0, found `0`"
+    );
+}